@@ -5,7 +5,7 @@ use crossterm::{
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap},
@@ -14,15 +14,250 @@ use ratatui::{
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::time::SystemTime;
 use std::os::unix::fs::PermissionsExt;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 enum SortMode {
     Name,
     Date,
+    Size,
+    Extension,
+    Type,
+    NameLength,
+    DirSize, // Directories by recursive size, files by name
+}
+
+impl SortMode {
+    // Order and shortcut keys of the entries in the sort menu
+    const ALL: [SortMode; 7] = [
+        SortMode::Name,
+        SortMode::Date,
+        SortMode::Size,
+        SortMode::Extension,
+        SortMode::Type,
+        SortMode::NameLength,
+        SortMode::DirSize,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SortMode::Name => "Name",
+            SortMode::Date => "Date",
+            SortMode::Size => "Size",
+            SortMode::Extension => "Extension",
+            SortMode::Type => "Type",
+            SortMode::NameLength => "Name Length",
+            SortMode::DirSize => "Directory Size",
+        }
+    }
+
+    fn key(self) -> char {
+        match self {
+            SortMode::Name => 'n',
+            SortMode::Date => 'd',
+            SortMode::Size => 's',
+            SortMode::Extension => 'e',
+            SortMode::Type => 't',
+            SortMode::NameLength => 'l',
+            SortMode::DirSize => 'z',
+        }
+    }
+}
+
+// How pasted copies are checked against their source
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CopyVerification {
+    Off,
+    Size,
+    Content, // Byte-for-byte comparison, which also catches same-size corruption
+}
+
+// How much vertical room the listing takes per entry
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Density {
+    Compact, // One line per entry
+    Comfortable, // A blank line between the directories and the files
+    Detailed, // A second line per entry with its size and type
+}
+
+impl Density {
+    fn next(self) -> Self {
+        match self {
+            Density::Compact => Density::Comfortable,
+            Density::Comfortable => Density::Detailed,
+            Density::Detailed => Density::Compact,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Density::Compact => "compact",
+            Density::Comfortable => "comfortable",
+            Density::Detailed => "detailed",
+        }
+    }
+}
+
+// Where directories go relative to files in a listing
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DirsPosition {
+    First,
+    Last,
+    Mixed, // Sorted together with the files
+}
+
+impl DirsPosition {
+    fn next(self) -> Self {
+        match self {
+            DirsPosition::First => DirsPosition::Last,
+            DirsPosition::Last => DirsPosition::Mixed,
+            DirsPosition::Mixed => DirsPosition::First,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DirsPosition::First => "first",
+            DirsPosition::Last => "last",
+            DirsPosition::Mixed => "mixed with files",
+        }
+    }
+}
+
+// How the listing is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ViewMode {
+    Tree, // Ancestor directories above the entries, joined by tree glyphs
+    Flat, // Only the entries, like ls; the title still shows the path
+}
+
+impl ViewMode {
+    fn next(self) -> Self {
+        match self {
+            ViewMode::Tree => ViewMode::Flat,
+            ViewMode::Flat => ViewMode::Tree,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ViewMode::Tree => "tree",
+            ViewMode::Flat => "flat list",
+        }
+    }
+}
+
+// What Enter does when the cursor is on a directory
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DirEnterAction {
+    Enter, // Replace the listing with the directory's contents
+    Expand, // Toggle it open or closed in the inline tree (enters while the tree is off)
+    Open, // Hand it to the OS file manager
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TypeFilter {
+    All,
+    DirsOnly,
+    FilesOnly,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TimeField {
+    Modified,
+    Created,
+    Accessed,
+}
+
+impl TimeField {
+    fn next(self) -> Self {
+        match self {
+            TimeField::Modified => TimeField::Created,
+            TimeField::Created => TimeField::Accessed,
+            TimeField::Accessed => TimeField::Modified,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TimeField::Modified => "modified",
+            TimeField::Created => "created",
+            TimeField::Accessed => "accessed",
+        }
+    }
+}
+
+// Entries that are neither regular files nor directories; reading them can block forever
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SpecialKind {
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+}
+
+impl SpecialKind {
+    fn of(file_type: &fs::FileType) -> Option<Self> {
+        use std::os::unix::fs::FileTypeExt;
+
+        if file_type.is_fifo() {
+            Some(SpecialKind::Fifo)
+        } else if file_type.is_socket() {
+            Some(SpecialKind::Socket)
+        } else if file_type.is_block_device() {
+            Some(SpecialKind::BlockDevice)
+        } else if file_type.is_char_device() {
+            Some(SpecialKind::CharDevice)
+        } else {
+            None
+        }
+    }
+
+    // find -printf %y/%Y type letters
+    fn from_find_type(file_type: &str) -> Option<Self> {
+        match file_type {
+            "p" => Some(SpecialKind::Fifo),
+            "s" => Some(SpecialKind::Socket),
+            "b" => Some(SpecialKind::BlockDevice),
+            "c" => Some(SpecialKind::CharDevice),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SpecialKind::Fifo => "named pipe",
+            SpecialKind::Socket => "socket",
+            SpecialKind::BlockDevice => "block device",
+            SpecialKind::CharDevice => "character device",
+        }
+    }
+
+    // File type character of ls -l
+    fn type_char(self) -> &'static str {
+        match self {
+            SpecialKind::Fifo => "p",
+            SpecialKind::Socket => "s",
+            SpecialKind::BlockDevice => "b",
+            SpecialKind::CharDevice => "c",
+        }
+    }
+
+    // Same indicators as ls -F where it has one
+    fn glyph(self) -> &'static str {
+        match self {
+            SpecialKind::Fifo => "|",
+            SpecialKind::Socket => "=",
+            SpecialKind::BlockDevice | SpecialKind::CharDevice => "#",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -30,8 +265,14 @@ struct DirEntry {
     path: PathBuf,
     name: String,
     is_dir: bool,
-    modified: SystemTime,
+    time: SystemTime, // Timestamp chosen by the explorer's TimeField; shown in the date column and sorted by
     permissions: u32, // Unix permission bits
+    size: u64, // File length in bytes (0 for directories)
+    is_symlink: bool,
+    is_broken_link: bool, // Symlink whose target no longer exists
+    special: Option<SpecialKind>, // FIFO, socket or device node
+    lossy_name: bool, // File name isn't valid UTF-8; `name` is only for display, `path` stays exact
+    depth: usize, // Nesting below the current directory (inline tree mode)
 }
 
 #[derive(Clone, Debug)]
@@ -53,13 +294,14 @@ struct Clipboard {
     operation: ClipboardOp,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 enum UndoAction {
     Copy {
         copied_files: Vec<PathBuf>,
     },
     Move {
         moved_files: Vec<(PathBuf, PathBuf)>,
+        trashed_originals: Vec<(PathBuf, PathBuf)>, // Originals a cross-filesystem move copied and then put in the trash
     },
     Delete {
         deleted_files: Vec<(PathBuf, PathBuf)>,
@@ -68,12 +310,498 @@ enum UndoAction {
         original_path: PathBuf,
         new_path: PathBuf,
     },
+    PasteIntoNewDir {
+        directory: PathBuf, // Created for the paste; removed again once the paste is undone
+        paste: Box<UndoAction>,
+    },
+    SwapNames {
+        first: PathBuf, // Swapping the two names again undoes it
+        second: PathBuf,
+    },
+    Replace {
+        replaced: Vec<(PathBuf, PathBuf)>, // (original, trash path) of items trashed to free their names
+        action: Box<UndoAction>, // What then took those names; undone first
+    },
+}
+
+impl UndoAction {
+    // Every path the action touched, on either side
+    fn paths(&self) -> Vec<&Path> {
+        fn pairs(pairs: &[(PathBuf, PathBuf)]) -> Vec<&Path> {
+            pairs.iter().flat_map(|(from, to)| [from.as_path(), to.as_path()]).collect()
+        }
+        match self {
+            UndoAction::Copy { copied_files } => copied_files.iter().map(PathBuf::as_path).collect(),
+            UndoAction::Move { moved_files, trashed_originals } => {
+                let mut paths = pairs(moved_files);
+                paths.extend(pairs(trashed_originals));
+                paths
+            }
+            UndoAction::Delete { deleted_files } => pairs(deleted_files),
+            UndoAction::Rename { original_path, new_path } => vec![original_path, new_path],
+            UndoAction::PasteIntoNewDir { directory, paste } => {
+                let mut paths = paste.paths();
+                paths.push(directory);
+                paths
+            }
+            UndoAction::SwapNames { first, second } => vec![first, second],
+            UndoAction::Replace { replaced, action } => {
+                let mut paths = action.paths();
+                paths.extend(pairs(replaced));
+                paths
+            }
+        }
+    }
+}
+
+// Filename clean-ups applied by the sanitize command, toggled in its preview
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SanitizeOptions {
+    spaces: bool, // Whitespace becomes '_'
+    lowercase: bool,
+    ascii: bool, // Drop characters outside ASCII
+}
+
+impl SanitizeOptions {
+    fn apply(self, name: &str) -> String {
+        let mut cleaned = String::with_capacity(name.len());
+        for c in name.chars().filter(|c| !self.ascii || c.is_ascii()) {
+            let c = if self.spaces && c.is_whitespace() { '_' } else { c };
+            if self.lowercase {
+                cleaned.extend(c.to_lowercase());
+            } else {
+                cleaned.push(c);
+            }
+        }
+        cleaned
+    }
+}
+
+// What Alt+L writes out
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ExportFormat {
+    Text, // The listing as aligned columns
+    Csv,
+    Tree, // The subtree drawn like `tree`, down to tree_export_depth
+}
+
+impl ExportFormat {
+    fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "text",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Tree => "a tree",
+        }
+    }
+}
+
+// Entries of the right-click menu, in display order
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ContextAction {
+    Open,
+    Copy,
+    Cut,
+    Rename,
+    Delete,
+    Properties,
+}
+
+impl ContextAction {
+    const ALL: [ContextAction; 6] = [
+        ContextAction::Open,
+        ContextAction::Copy,
+        ContextAction::Cut,
+        ContextAction::Rename,
+        ContextAction::Delete,
+        ContextAction::Properties,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ContextAction::Open => "Open",
+            ContextAction::Copy => "Copy",
+            ContextAction::Cut => "Cut",
+            ContextAction::Rename => "Rename",
+            ContextAction::Delete => "Delete",
+            ContextAction::Properties => "Properties",
+        }
+    }
+
+    fn modifies_files(self) -> bool {
+        matches!(self, ContextAction::Cut | ContextAction::Rename | ContextAction::Delete)
+    }
+}
+
+// Actions listed by the command palette (Ctrl+P), with the key that runs them directly
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PaletteCommand {
+    Open,
+    GoToParent,
+    Copy,
+    Cut,
+    Paste,
+    PasteIntoCursorDir,
+    PasteIntoNewDir,
+    CreateNew,
+    CreateFromClipboard,
+    Rename,
+    SwapNames,
+    SanitizeNames,
+    BulkRename,
+    Delete,
+    Undo,
+    RestoreLastDelete,
+    ToggleExecutable,
+    EditTags,
+    SplitFile,
+    JoinParts,
+    CopyPath,
+    SelectAll,
+    SelectSameExtension,
+    ToggleSelection,
+    JumpToNumber,
+    SetMark,
+    JumpToMark,
+    ItemInfo,
+    ViewInPager,
+    TogglePin,
+    FuzzyFind,
+    RecentFiles,
+    GoToGitRoot,
+    GoToClipboardPath,
+    GoToTrash,
+    SizeScan,
+    ExportListing,
+    TmuxPanes,
+    ToggleMount,
+    ToggleQueueMode,
+    RunQueue,
+    SortMenu,
+    ToggleHidden,
+    CycleTypeFilter,
+    ChangedSince,
+    Help,
+    CycleTheme,
+    ToggleAgeColors,
+    ToggleTitleStats,
+    ToggleInlineTree,
+    MessageLog,
+    CycleTimeField,
+    ToggleSplitDirectories,
+    CycleDensity,
+    ToggleTypeIndicators,
+    ToggleKeyHints,
+    ToggleViewMode,
+    EditConfig,
+}
+
+impl PaletteCommand {
+    const ALL: [PaletteCommand; 58] = [
+        PaletteCommand::Open,
+        PaletteCommand::GoToParent,
+        PaletteCommand::Copy,
+        PaletteCommand::Cut,
+        PaletteCommand::Paste,
+        PaletteCommand::PasteIntoCursorDir,
+        PaletteCommand::PasteIntoNewDir,
+        PaletteCommand::CreateNew,
+        PaletteCommand::CreateFromClipboard,
+        PaletteCommand::Rename,
+        PaletteCommand::SwapNames,
+        PaletteCommand::SanitizeNames,
+        PaletteCommand::BulkRename,
+        PaletteCommand::Delete,
+        PaletteCommand::Undo,
+        PaletteCommand::RestoreLastDelete,
+        PaletteCommand::ToggleExecutable,
+        PaletteCommand::EditTags,
+        PaletteCommand::SplitFile,
+        PaletteCommand::JoinParts,
+        PaletteCommand::CopyPath,
+        PaletteCommand::SelectAll,
+        PaletteCommand::SelectSameExtension,
+        PaletteCommand::ToggleSelection,
+        PaletteCommand::JumpToNumber,
+        PaletteCommand::SetMark,
+        PaletteCommand::JumpToMark,
+        PaletteCommand::ItemInfo,
+        PaletteCommand::ViewInPager,
+        PaletteCommand::TogglePin,
+        PaletteCommand::FuzzyFind,
+        PaletteCommand::RecentFiles,
+        PaletteCommand::GoToGitRoot,
+        PaletteCommand::GoToClipboardPath,
+        PaletteCommand::GoToTrash,
+        PaletteCommand::SizeScan,
+        PaletteCommand::ExportListing,
+        PaletteCommand::TmuxPanes,
+        PaletteCommand::ToggleMount,
+        PaletteCommand::ToggleQueueMode,
+        PaletteCommand::RunQueue,
+        PaletteCommand::SortMenu,
+        PaletteCommand::ToggleHidden,
+        PaletteCommand::CycleTypeFilter,
+        PaletteCommand::ChangedSince,
+        PaletteCommand::Help,
+        PaletteCommand::CycleTheme,
+        PaletteCommand::ToggleAgeColors,
+        PaletteCommand::ToggleTitleStats,
+        PaletteCommand::ToggleInlineTree,
+        PaletteCommand::MessageLog,
+        PaletteCommand::CycleTimeField,
+        PaletteCommand::ToggleSplitDirectories,
+        PaletteCommand::CycleDensity,
+        PaletteCommand::ToggleTypeIndicators,
+        PaletteCommand::ToggleKeyHints,
+        PaletteCommand::ToggleViewMode,
+        PaletteCommand::EditConfig,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            PaletteCommand::Open => "Open file or enter directory",
+            PaletteCommand::GoToParent => "Go to parent directory",
+            PaletteCommand::Copy => "Copy",
+            PaletteCommand::Cut => "Cut",
+            PaletteCommand::Paste => "Paste",
+            PaletteCommand::PasteIntoCursorDir => "Paste into directory under cursor",
+            PaletteCommand::PasteIntoNewDir => "Paste into a new directory",
+            PaletteCommand::CreateNew => "New file or directory",
+            PaletteCommand::CreateFromClipboard => "New file from clipboard text",
+            PaletteCommand::Rename => "Rename",
+            PaletteCommand::SwapNames => "Swap names of two selected items",
+            PaletteCommand::SanitizeNames => "Sanitize names",
+            PaletteCommand::BulkRename => "Bulk rename in editor",
+            PaletteCommand::Delete => "Delete",
+            PaletteCommand::Undo => "Undo",
+            PaletteCommand::RestoreLastDelete => "Restore last delete",
+            PaletteCommand::ToggleExecutable => "Toggle executable bits",
+            PaletteCommand::EditTags => "Edit tags",
+            PaletteCommand::SplitFile => "Split file into parts",
+            PaletteCommand::JoinParts => "Join .partNNN files",
+            PaletteCommand::CopyPath => "Copy path",
+            PaletteCommand::SelectAll => "Select everything listed",
+            PaletteCommand::SelectSameExtension => "Select files with the cursor item's extension",
+            PaletteCommand::ToggleSelection => "Toggle selection",
+            PaletteCommand::JumpToNumber => "Jump to item number",
+            PaletteCommand::SetMark => "Mark current directory",
+            PaletteCommand::JumpToMark => "Jump to marked directory",
+            PaletteCommand::ItemInfo => "Item info",
+            PaletteCommand::ViewInPager => "View in pager",
+            PaletteCommand::TogglePin => "Pin or unpin item",
+            PaletteCommand::FuzzyFind => "Fuzzy find",
+            PaletteCommand::RecentFiles => "Recently modified files",
+            PaletteCommand::GoToGitRoot => "Go to git repository root",
+            PaletteCommand::GoToClipboardPath => "Go to path in clipboard",
+            PaletteCommand::GoToTrash => "Browse trash",
+            PaletteCommand::SizeScan => "Calculate directory size",
+            PaletteCommand::ExportListing => "Export listing",
+            PaletteCommand::TmuxPanes => "Open in tmux panes",
+            PaletteCommand::ToggleMount => "Mount or unmount",
+            PaletteCommand::ToggleQueueMode => "Toggle queue mode",
+            PaletteCommand::RunQueue => "Run queued operations",
+            PaletteCommand::SortMenu => "Sort menu",
+            PaletteCommand::ToggleHidden => "Show or hide hidden files",
+            PaletteCommand::CycleTypeFilter => "Cycle type filter",
+            PaletteCommand::ChangedSince => "Show only items changed from now on",
+            PaletteCommand::Help => "Help",
+            PaletteCommand::CycleTheme => "Cycle theme",
+            PaletteCommand::ToggleAgeColors => "Toggle age-colored timestamps",
+            PaletteCommand::ToggleTitleStats => "Toggle title stats",
+            PaletteCommand::ToggleInlineTree => "Toggle inline tree",
+            PaletteCommand::MessageLog => "Message log",
+            PaletteCommand::CycleTimeField => "Cycle shown time field",
+            PaletteCommand::ToggleSplitDirectories => "Toggle pinned directories",
+            PaletteCommand::CycleDensity => "Cycle density",
+            PaletteCommand::ToggleTypeIndicators => "Toggle type indicators",
+            PaletteCommand::ToggleKeyHints => "Toggle key hints",
+            PaletteCommand::ToggleViewMode => "Toggle tree/flat view",
+            PaletteCommand::EditConfig => "Edit config file",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            PaletteCommand::Open => "Enter",
+            PaletteCommand::GoToParent => "Left",
+            PaletteCommand::Copy => "Ctrl+C",
+            PaletteCommand::Cut => "Ctrl+X",
+            PaletteCommand::Paste => "Ctrl+V",
+            PaletteCommand::PasteIntoCursorDir => "Alt+V",
+            PaletteCommand::PasteIntoNewDir => "Alt+F",
+            PaletteCommand::CreateNew => "Ctrl+N",
+            PaletteCommand::CreateFromClipboard => "Alt+N",
+            PaletteCommand::Rename => "Ctrl+R",
+            PaletteCommand::SwapNames => "Alt+B",
+            PaletteCommand::SanitizeNames => "Alt+C",
+            PaletteCommand::BulkRename => "Alt+R",
+            PaletteCommand::Delete => "Delete",
+            PaletteCommand::Undo => "Ctrl+Z",
+            PaletteCommand::RestoreLastDelete => "Ctrl+U",
+            PaletteCommand::ToggleExecutable => "Alt+X",
+            PaletteCommand::EditTags => "Alt+A",
+            PaletteCommand::SplitFile => "Alt+P",
+            PaletteCommand::JoinParts => "Alt+J",
+            PaletteCommand::CopyPath => "Ctrl+D",
+            PaletteCommand::SelectAll => "Ctrl+A",
+            PaletteCommand::SelectSameExtension => "*",
+            PaletteCommand::ToggleSelection => "Ctrl+Space",
+            PaletteCommand::JumpToNumber => "#",
+            PaletteCommand::SetMark => "m",
+            PaletteCommand::JumpToMark => "'",
+            PaletteCommand::ItemInfo => "i",
+            PaletteCommand::ViewInPager => "v",
+            PaletteCommand::TogglePin => "p",
+            PaletteCommand::FuzzyFind => "Ctrl+F",
+            PaletteCommand::RecentFiles => "Alt+U",
+            PaletteCommand::GoToGitRoot => "Ctrl+G",
+            PaletteCommand::GoToClipboardPath => "Alt+G",
+            PaletteCommand::GoToTrash => "Alt+T",
+            PaletteCommand::SizeScan => "Alt+S",
+            PaletteCommand::ExportListing => "Alt+L",
+            PaletteCommand::TmuxPanes => "Alt+W",
+            PaletteCommand::ToggleMount => "Alt+M",
+            PaletteCommand::ToggleQueueMode => "Alt+Q",
+            PaletteCommand::RunQueue => "Alt+E",
+            PaletteCommand::SortMenu => "Ctrl+S",
+            PaletteCommand::ToggleHidden => "Ctrl+H",
+            PaletteCommand::CycleTypeFilter => "Ctrl+T",
+            PaletteCommand::ChangedSince => "Alt+D",
+            PaletteCommand::Help => "F1",
+            PaletteCommand::CycleTheme => "F2",
+            PaletteCommand::ToggleAgeColors => "F3",
+            PaletteCommand::ToggleTitleStats => "F4",
+            PaletteCommand::ToggleInlineTree => "F5",
+            PaletteCommand::MessageLog => "F6",
+            PaletteCommand::CycleTimeField => "F7",
+            PaletteCommand::ToggleSplitDirectories => "F8",
+            PaletteCommand::CycleDensity => "F9",
+            PaletteCommand::ToggleTypeIndicators => "F10",
+            PaletteCommand::ToggleKeyHints => "F11",
+            PaletteCommand::ToggleViewMode => "F12",
+            PaletteCommand::EditConfig => "Alt+O",
+        }
+    }
+
+    // Same keys is_mutating_key refuses in read-only mode
+    fn modifies_files(self) -> bool {
+        matches!(
+            self,
+            PaletteCommand::Copy
+                | PaletteCommand::Cut
+                | PaletteCommand::Paste
+                | PaletteCommand::PasteIntoCursorDir
+                | PaletteCommand::PasteIntoNewDir
+                | PaletteCommand::CreateNew
+                | PaletteCommand::CreateFromClipboard
+                | PaletteCommand::Rename
+                | PaletteCommand::SwapNames
+                | PaletteCommand::SanitizeNames
+                | PaletteCommand::BulkRename
+                | PaletteCommand::Delete
+                | PaletteCommand::Undo
+                | PaletteCommand::RestoreLastDelete
+                | PaletteCommand::ToggleExecutable
+                | PaletteCommand::EditTags
+                | PaletteCommand::SplitFile
+                | PaletteCommand::JoinParts
+                | PaletteCommand::ToggleMount
+        )
+    }
+}
+
+// What to do with a batch item whose target name is already taken
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ConflictChoice {
+    Overwrite, // Trash the existing item first
+    Skip,
+    KeepBoth, // Number the new name, "name (1).ext"
+}
+
+// A paste or bulk rename waiting for its name conflicts to be settled
+#[derive(Clone, Debug)]
+enum ConflictBatch {
+    Paste {
+        items: Vec<PathBuf>,
+        destination: PathBuf,
+        is_move: bool,
+    },
+    BulkRename {
+        renames: Vec<(PathBuf, PathBuf)>, // (original, target)
+    },
+}
+
+// A file operation collected in queue mode, run later with the others
+#[derive(Clone, Debug)]
+enum QueuedOperation {
+    Paste {
+        items: Vec<PathBuf>,
+        destination: PathBuf,
+        is_move: bool,
+    },
+    // The directory is created when the operation runs
+    PasteIntoNewDir {
+        items: Vec<PathBuf>,
+        directory: PathBuf,
+        is_move: bool,
+    },
+    Delete {
+        items: Vec<PathBuf>,
+    },
+    Rename {
+        original_path: PathBuf,
+        new_name: String,
+    },
+}
+
+impl QueuedOperation {
+    fn describe(&self) -> String {
+        let name = |path: &Path| path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        match self {
+            QueuedOperation::Paste { items, destination, is_move } => format!(
+                "{} {} item(s) to {}",
+                if *is_move { "Move" } else { "Copy" },
+                items.len(),
+                destination.display()
+            ),
+            QueuedOperation::PasteIntoNewDir { items, directory, is_move } => format!(
+                "{} {} item(s) into new folder {}",
+                if *is_move { "Move" } else { "Copy" },
+                items.len(),
+                directory.display()
+            ),
+            QueuedOperation::Delete { items } => format!("Delete {} item(s)", items.len()),
+            QueuedOperation::Rename { original_path, new_name } => {
+                format!("Rename '{}' to '{}'", name(original_path), new_name)
+            }
+        }
+    }
+
+    fn sources(&self) -> &[PathBuf] {
+        match self {
+            QueuedOperation::Paste { items, .. }
+            | QueuedOperation::PasteIntoNewDir { items, .. }
+            | QueuedOperation::Delete { items } => items,
+            QueuedOperation::Rename { original_path, .. } => std::slice::from_ref(original_path),
+        }
+    }
+}
+
+// How a paste, delete or rename ended, so the queue summary doesn't have to read the status bar
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OperationOutcome {
+    Done,
+    Failed,
+    Skipped,
+    Deferred, // Waiting on a prompt, the queue or a background task
 }
 
 #[derive(Clone, Debug)]
 enum CreationType {
     File,
     Directory,
+    ClipboardText, // File holding the system clipboard's text
+    PasteFolder, // Directory that the clipboard is pasted into right away
 }
 
 #[derive(Clone, Debug)]
@@ -89,23 +817,99 @@ enum UIMode {
     },
     ConfirmDelete {
         items: Vec<PathBuf>,
+        scroll: usize, // First line of the item list shown for multi-item deletes
+    },
+    ConfirmPaste {
+        items: Vec<PathBuf>,
+        destination: PathBuf,
+        is_move: bool,
+        new_dir: bool, // The destination is created once confirmed
+        scroll: usize,
+    },
+    ConfirmOverwrite {
+        original_path: PathBuf,
+        new_path: PathBuf,
+    },
+    ConfirmCrossDeviceMove {
+        items: Vec<PathBuf>,
+        destination: PathBuf,
+        cross_device: usize, // How many of the items live on another filesystem
+        external: Option<String>, // Network or removable destination; every item is copied then
+    },
+    SanitizeNames {
+        items: Vec<PathBuf>,
+        options: SanitizeOptions,
+        scroll: usize,
+    },
+    ConfirmBatchConflicts {
+        batch: ConflictBatch,
+        conflicts: Vec<PathBuf>, // Targets that already exist
+        choices: Vec<ConflictChoice>, // Decided so far, in the order of conflicts
+        deciding: bool, // Asking about each conflict in turn instead of once for all
     },
     CreateNew {
         creation_type: Option<CreationType>,
         name: String,
     },
+    SplitFile {
+        path: PathBuf,
+        chunk_size: String, // As typed, e.g. "100M"
+    },
+    JumpToNumber {
+        buffer: String, // Digits typed so far; entries are numbered from 1 in the gutter
+    },
+    ContextMenu {
+        row: u16, // Where the right-click happened; the menu opens just below it
+        column: u16,
+        selected: usize, // Index into ContextAction::ALL
+    },
+    ExportListing {
+        format: Option<ExportFormat>, // None until (t)ext, (c)sv or t(r)ee is chosen
+        name: String, // File to write in the current directory; empty means the clipboard
+    },
+    EditTag {
+        path: PathBuf,
+        tags: Vec<String>, // Tags the item carries now
+        tag: String, // As typed; added, or removed if already present
+    },
     RenameItem {
         original_path: PathBuf,
         new_name: String,
         cursor_pos: usize,
         selection_start: Option<usize>,
     },
-    Help,
+    Help {
+        scroll: usize,
+        filter: String,
+    },
+    MessageLog {
+        scroll: usize,
+    },
+    QueueSummary {
+        results: Vec<(OperationOutcome, String, String)>, // Outcome, operation, detail
+        scroll: usize,
+    },
+    SortMenu {
+        selected_index: usize,
+    },
+    ItemInfo {
+        path: PathBuf,
+        lines: Vec<String>, // Gathered once when opened; directory sizes are expensive
+    },
     FuzzyFind {
         search_term: String,
         matches: Vec<FuzzyMatch>,
         selected_index: usize,
     },
+    RecentFiles {
+        files: Vec<(PathBuf, SystemTime)>, // Newest first; empty until the walk finishes
+        selected_index: usize,
+    },
+    CommandPalette {
+        query: String,
+        filtered: Vec<PaletteCommand>, // Commands matching the query, in PaletteCommand::ALL order
+        selected: usize,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -125,6 +929,12 @@ enum OperationType {
     Move,
     Delete,
     Undo,
+    ListDirectory,
+    Mount,
+    Unmount,
+    MakeExecutable,
+    ClearExecutable,
+    SwapNames,
 }
 
 #[derive(Clone, Debug)]
@@ -135,771 +945,5042 @@ struct PendingOperation {
     undo_action: Option<UndoAction>,
 }
 
-#[allow(dead_code)]
-struct TreeLine {
-    tree_prefix: String, // The indent + tree chars + icon part (styled dimly)
-    text: String,
-    timestamp: Option<String>, // Separate timestamp for styling
-    entry_index: Option<usize>,
-    is_selected: bool,
-    is_cursor: bool,
-    is_dir: bool,
-    is_current_dir: bool,
-    is_hidden: bool, // Whether this is a hidden file/directory
+// Comma-separated tags, the same attribute KDE's Dolphin reads and writes
+#[cfg(feature = "xattr")]
+const TAGS_XATTR: &str = "user.xdg.tags";
+
+// Contents of the F1 overlay; section headings end with a colon
+const HELP_LINES: &[&str] = &[
+    "Keyboard Shortcuts",
+    "",
+    "Navigation:",
+    "  Up/Down, j/k   - Move cursor (prefix a count, e.g. 5j)",
+    "  Left           - Go to parent directory",
+    "  Right          - Enter directory",
+    "  Enter          - Open file/directory",
+    "  Ctrl+G         - Jump to git repository root",
+    "  Alt+G          - Go to the path in the system clipboard",
+    "  Alt+T          - Browse the raw trash directory",
+    "  Alt+U          - List the newest files anywhere below this directory",
+    "  i              - Show item info (path, size, owner, times)",
+    "  v              - View text file in $PAGER (default less)",
+    "  Alt+S          - Calculate directory size (Esc cancels)",
+    "  m<letter>      - Mark current directory",
+    "  '<letter>      - Jump to marked directory",
+    "  p              - Pin/unpin item to the top of its directories or files",
+    "",
+    "Selection:",
+    "  Shift+Up/Down  - Select range",
+    "  Ctrl+Space     - Toggle selection",
+    "  Mouse drag     - Select multiple",
+    "  Shift+Click    - Select range to clicked item",
+    "  Ctrl+A         - Select everything listed (after hidden/type filters)",
+    "  [ / ]          - Jump to first/last selected",
+    "  *              - Select all files with the cursor file's extension",
+    "  #              - Number the items and jump to one by typing it",
+    "",
+    "File Operations:",
+    "  Ctrl+C         - Copy",
+    "  Ctrl+X         - Cut",
+    "  Ctrl+V         - Paste",
+    "  Alt+V          - Paste into directory under cursor",
+    "  Ctrl+N         - Create new",
+    "  Alt+N          - Save clipboard text as a new file",
+    "  Alt+F          - Paste into a new directory and enter it",
+    "  Ctrl+R         - Rename",
+    "  Up (in prompt) - Recall the last cancelled rename/create input",
+    "  Alt+R          - Bulk rename selection in $EDITOR",
+    "  Alt+B          - Swap the names of the two selected items",
+    "  Alt+C          - Clean up selected names (spaces, case, non-ASCII)",
+    "  Ctrl+D/Delete  - Delete",
+    "  Ctrl+Alt+C/X   - Copy/cut only the item under the cursor",
+    "  Alt+Delete     - Delete only the item under the cursor",
+    "  dd / 3dd       - Delete item(s) from cursor",
+    "  Ctrl+Z         - Undo",
+    "  Ctrl+U         - Restore last deleted",
+    "  Alt+P          - Split file into .partNNN chunks",
+    "  Alt+J          - Join selected .partNNN files",
+    "  Alt+X          - Toggle the executable bits of selected files",
+    "  Alt+A          - Add or remove a tag (user.xdg.tags attribute)",
+    "",
+    "View Options:",
+    "  Ctrl+S         - Sort menu (name/date/size/extension/type/length/dir size)",
+    "  Ctrl+H         - Toggle hidden files",
+    "  Ctrl+T         - Cycle filter (All/Dirs/Files)",
+    "  Alt+D          - Show only items changed after now (again to clear)",
+    "  Ctrl+L         - Refresh display and reload the listing",
+    "  F2             - Cycle color theme",
+    "  F3             - Toggle age-colored timestamps",
+    "  F4             - Toggle directory stats in the title",
+    "  F5             - Toggle inline tree (Right/Left expand/collapse)",
+    "  F7             - Cycle date column (modified/created/accessed)",
+    "  F8             - Pin directories above the scrolling files",
+    "  F9             - Cycle density (compact/comfortable/detailed)",
+    "  F10            - Toggle ls -F type indicators (/ * @ | = #)",
+    "  F11            - Toggle the key hint line above the status bar",
+    "  F12            - Toggle tree view / flat list",
+    "",
+    "Other:",
+    "  Alt+M          - Mount/unmount block device or mount point",
+    "  Alt+O          - Edit the config file in $EDITOR and reload it",
+    "  Alt+W          - Open selection/current dir in new tmux panes",
+    "  Alt+L          - Export the listing (text/CSV) or subtree to file/clipboard",
+    "  Alt+Q          - Toggle queue mode (collect paste/delete/rename)",
+    "  Alt+E          - Run queued operations and show a summary",
+    "  Ctrl+P         - Command palette: find any command by name and run it",
+    "  F1             - Show/hide this help",
+    "  F6             - Show message log",
+    "  Ctrl+Q         - Quit",
+    "",
+    "Press F1 or Esc to close this help",
+];
+
+// Color palette used by the draw loop; cycled at runtime with F2
+#[derive(Clone, Copy, Debug)]
+struct Theme {
+    name: &'static str,
+    text_fg: Color,                // Cursor row text, help text
+    file_fg: Color,                // Regular files and selected rows
+    dir_fg: Color,                 // Directories
+    current_dir_fg: Color,         // Current directory line in the ancestor tree
+    hidden_dir_fg: Color,          // Dot-directories
+    hidden_file_fg: Color,         // Dotfiles
+    tree_prefix_fg: Color,         // Tree glyphs, icons and the title
+    timestamp_fg: Color,           // Permissions/date column
+    timestamp_highlight_fg: Color, // Permissions/date column on cursor or selected rows
+    cursor_bg: Color,
+    cursor_selected_bg: Color,
+    selected_bg: Color,
+    match_fg: Color,               // Matched characters in fuzzy find
+    status_fg: Color,              // Status bar text
+    background: Color,             // Status bar and help background
+    accent_fg: Color,              // Prompts and the rename cursor
+    message_fg: Color,             // Status message panel
+    warning_fg: Color,             // Confirmations
 }
 
-struct FileExplorer {
-    current_dir: PathBuf,
-    entries: Vec<DirEntry>,
-    cursor_index: usize,
-    selected_indices: HashSet<usize>,
-    selection_anchor: Option<usize>,
-    scroll_offset: usize,
-    dir_memory: HashMap<PathBuf, DirState>,
-    clipboard: Option<Clipboard>,
-    ui_mode: UIMode,
-    undo_stack: Vec<UndoAction>,
-    trash_dir: PathBuf,
-    drag_selection: Option<usize>, // Tracks drag start index when dragging
-    size_cache: HashMap<PathBuf, u64>, // Cache for file/directory sizes
-    current_item_size: Option<u64>, // Size of item currently under cursor
-    sort_mode: SortMode, // Current sort mode (by name or by date)
-    terminal_width: usize, // Cached terminal width for rendering
-    show_hidden: bool, // Whether to show hidden files/directories
-    status_message: Option<String>, // Temporary status message to show in status bar
-}
+impl Theme {
+    const NAMES: [&'static str; 3] = ["sage", "high-contrast", "light"];
 
-impl FileExplorer {
-    fn new() -> io::Result<Self> {
-        let current_dir = std::env::current_dir()?;
+    fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "sage" => Some(Self::sage()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
 
-        let trash_dir = if let Some(home) = std::env::var_os("HOME") {
-            PathBuf::from(home).join(".local/share/rusty_files/trash")
-        } else {
-            PathBuf::from("/tmp/rusty_files_trash")
-        };
+    fn next(&self) -> Self {
+        let index = Self::NAMES.iter().position(|&n| n == self.name).unwrap_or(0);
+        let next_name = Self::NAMES[(index + 1) % Self::NAMES.len()];
+        Self::by_name(next_name).unwrap_or_else(Self::sage)
+    }
 
-        fs::create_dir_all(&trash_dir)?;
+    // Linear mix of two RGB colors; t = 0.0 gives `from`, t = 1.0 gives `to`
+    fn blend(from: Color, to: Color, t: f64) -> Color {
+        match (from, to) {
+            (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+                let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+                Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+            }
+            _ => if t < 0.5 { from } else { to },
+        }
+    }
 
-        let mut explorer = FileExplorer {
-            current_dir: current_dir.clone(),
-            entries: Vec::new(),
-            cursor_index: 0,
-            selected_indices: HashSet::new(),
-            selection_anchor: None,
-            scroll_offset: 0,
-            dir_memory: HashMap::new(),
-            clipboard: None,
-            ui_mode: UIMode::Normal,
-            undo_stack: Vec::new(),
-            trash_dir,
-            drag_selection: None,
-            size_cache: HashMap::new(),
-            current_item_size: None,
-            sort_mode: SortMode::Name,
-            terminal_width: 100, // Default width, will be updated on first render
-            show_hidden: false, // Hidden files/directories are hidden by default
-            status_message: None, // No status message initially
-        };
-        explorer.load_directory()?;
-        Ok(explorer)
+    fn age_color(&self, modified: SystemTime) -> Color {
+        // Log scale from one minute (match color) to 30 days (dim), so recent hours stay distinguishable
+        const FRESH_SECS: f64 = 60.0;
+        const OLD_SECS: f64 = 30.0 * 24.0 * 3600.0;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0)
+            .max(FRESH_SECS);
+        let t = ((age / FRESH_SECS).ln() / (OLD_SECS / FRESH_SECS).ln()).min(1.0);
+        Self::blend(self.match_fg, self.hidden_file_fg, t)
+    }
+
+    fn sage() -> Self {
+        Theme {
+            name: "sage",
+            text_fg: Color::Rgb(165, 162, 157),
+            file_fg: Color::Rgb(190, 182, 165),
+            dir_fg: Color::Rgb(130, 125, 115),
+            current_dir_fg: Color::Rgb(160, 150, 135),
+            hidden_dir_fg: Color::Rgb(75, 75, 75),
+            hidden_file_fg: Color::Rgb(100, 100, 98),
+            tree_prefix_fg: Color::Rgb(65, 65, 65),
+            timestamp_fg: Color::Rgb(120, 120, 117),
+            timestamp_highlight_fg: Color::Rgb(130, 130, 126),
+            cursor_bg: Color::Rgb(50, 50, 50),
+            cursor_selected_bg: Color::Rgb(60, 60, 60),
+            selected_bg: Color::Rgb(45, 45, 45),
+            match_fg: Color::Rgb(140, 180, 120),
+            status_fg: Color::Rgb(150, 142, 130),
+            background: Color::Rgb(30, 30, 30),
+            accent_fg: Color::Rgb(175, 167, 150),
+            message_fg: Color::Rgb(170, 160, 145),
+            warning_fg: Color::Rgb(145, 135, 125),
+        }
+    }
+
+    fn high_contrast() -> Self {
+        Theme {
+            name: "high-contrast",
+            text_fg: Color::Rgb(255, 255, 255),
+            file_fg: Color::Rgb(255, 255, 255),
+            dir_fg: Color::Rgb(110, 190, 255),
+            current_dir_fg: Color::Rgb(255, 215, 0),
+            hidden_dir_fg: Color::Rgb(150, 150, 150),
+            hidden_file_fg: Color::Rgb(175, 175, 175),
+            tree_prefix_fg: Color::Rgb(140, 140, 140),
+            timestamp_fg: Color::Rgb(200, 200, 200),
+            timestamp_highlight_fg: Color::Rgb(255, 255, 255),
+            cursor_bg: Color::Rgb(0, 85, 170),
+            cursor_selected_bg: Color::Rgb(0, 115, 215),
+            selected_bg: Color::Rgb(80, 60, 0),
+            match_fg: Color::Rgb(0, 255, 120),
+            status_fg: Color::Rgb(255, 255, 255),
+            background: Color::Rgb(0, 0, 0),
+            accent_fg: Color::Rgb(255, 255, 0),
+            message_fg: Color::Rgb(255, 255, 255),
+            warning_fg: Color::Rgb(255, 110, 110),
+        }
+    }
+
+    fn light() -> Self {
+        // Meant for terminals with a light background
+        Theme {
+            name: "light",
+            text_fg: Color::Rgb(60, 56, 50),
+            file_fg: Color::Rgb(40, 38, 34),
+            dir_fg: Color::Rgb(110, 100, 85),
+            current_dir_fg: Color::Rgb(90, 80, 60),
+            hidden_dir_fg: Color::Rgb(175, 175, 175),
+            hidden_file_fg: Color::Rgb(150, 150, 148),
+            tree_prefix_fg: Color::Rgb(185, 185, 185),
+            timestamp_fg: Color::Rgb(130, 130, 126),
+            timestamp_highlight_fg: Color::Rgb(95, 95, 91),
+            cursor_bg: Color::Rgb(215, 213, 205),
+            cursor_selected_bg: Color::Rgb(200, 198, 190),
+            selected_bg: Color::Rgb(230, 228, 220),
+            match_fg: Color::Rgb(50, 125, 45),
+            status_fg: Color::Rgb(80, 74, 66),
+            background: Color::Rgb(240, 238, 232),
+            accent_fg: Color::Rgb(70, 64, 55),
+            message_fg: Color::Rgb(80, 72, 60),
+            warning_fg: Color::Rgb(160, 85, 55),
+        }
+    }
+}
+
+// Program configured to open files with a given extension
+#[derive(Clone, Debug)]
+struct Opener {
+    command: String, // Program and arguments; "{}" is replaced by the path, otherwise the path is appended
+    foreground: bool, // Run in this terminal with the UI suspended instead of detached
+}
+
+// User settings read from ~/.config/rusty_files/config.toml
+// Every option defaults to the built-in behavior so a missing file changes nothing
+#[derive(Clone, Debug)]
+struct Config {
+    confirm_rename_overwrite: bool, // Offer to replace an existing target on rename instead of refusing
+    sudo_keep_session: bool, // Reuse the sudo timestamp instead of asking for the password every time
+    theme: Theme, // Color theme active at startup
+    age_colored_timestamps: bool, // Start with the timestamp column colored by age
+    title_stats: bool, // Start with directory stats in the title
+    inline_tree: bool, // Start in the expandable inline tree mode
+    split_directories: bool, // Start with directories pinned above the scrolling files
+    type_indicators: bool, // Start with ls -F style '/', '*', '@' after names
+    key_hints: bool, // Start with the key hint line above the status bar
+    status_format: Option<String>, // Status bar template with {placeholders}; None keeps the built-in layout
+    persist_marks: bool, // Keep vim-style directory marks across sessions
+    restore_last_dir: bool, // Start in the directory the last session quit from
+    edit_new_files: bool, // Open files created with Ctrl+N in $EDITOR right away
+    bulk_rename_resolve_conflicts: bool, // Bulk rename onto taken names gets a " (1)" suffix instead of aborting
+    confirm_batch_conflicts: bool, // Ask once how to handle taken names before a paste or bulk rename
+    sanitize: SanitizeOptions, // Clean-ups the sanitize preview starts with
+    follow_paste: bool, // After pasting into another directory, go there with the pasted items selected
+    keep_selection_after_copy: bool, // A copy-paste leaves the source selection alone instead of selecting the copies
+    show_hidden_count: bool, // Mention how many dot-entries are hidden in the status bar
+    recursive_selection_size: bool, // Selected directories count with their recursive size, sized in the background
+    trash_expiry_days: u64, // Permanently delete trash older than this at startup (0 = never)
+    confirm_paste_items: usize, // Pastes of at least this many items list them and ask first (0 = never)
+    confirm_external_moves: bool, // Cuts pasted onto a network or removable drive ask first and always copy
+    highlight_broken_symlinks: bool, // Show dangling symlinks in the warning color with a "(broken)" label
+    openers: HashMap<String, Opener>, // Lowercase extension -> program used instead of the OS default
+    mount_points: Vec<PathBuf>, // fstab mount points that Alt+M mounts and unmounts
+    formatters: HashMap<String, String>, // Lowercase extension -> command run on files created with that extension
+    on_cd: Option<String>, // Command started in the background whenever the current directory changes
+    verify_copies: CopyVerification, // Check each pasted copy against its source
+    density: Density, // Listing density at startup
+    dirs_position: DirsPosition, // Directories before, after or among the files
+    enter_on_dir: DirEnterAction, // What Enter does on a directory
+    skip_single_child_dirs: bool, // Entering a directory descends through directories holding only one subdirectory
+    tree_export_depth: usize, // Directory levels written by the tree export
+    recent_files_count: usize, // Files listed by the recently modified view
+    min_name_width: usize, // Name columns kept before the permissions, then the date, are left out
+    view_mode: ViewMode, // Tree or flat listing at startup
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            confirm_rename_overwrite: false,
+            sudo_keep_session: false,
+            theme: Theme::sage(),
+            age_colored_timestamps: false,
+            title_stats: false,
+            inline_tree: false,
+            split_directories: false,
+            type_indicators: false,
+            key_hints: false,
+            status_format: None,
+            persist_marks: false,
+            restore_last_dir: false,
+            edit_new_files: false,
+            bulk_rename_resolve_conflicts: false,
+            confirm_batch_conflicts: false,
+            sanitize: SanitizeOptions { spaces: true, lowercase: false, ascii: true },
+            follow_paste: false,
+            keep_selection_after_copy: false,
+            show_hidden_count: true,
+            recursive_selection_size: false,
+            trash_expiry_days: 0,
+            confirm_paste_items: 0,
+            confirm_external_moves: false,
+            highlight_broken_symlinks: true,
+            openers: HashMap::new(),
+            mount_points: Vec::new(),
+            formatters: HashMap::new(),
+            on_cd: None,
+            verify_copies: CopyVerification::Off,
+            density: Density::Compact,
+            dirs_position: DirsPosition::First,
+            enter_on_dir: DirEnterAction::Enter,
+            skip_single_child_dirs: false,
+            tree_export_depth: 3,
+            recent_files_count: 50,
+            min_name_width: 16,
+            view_mode: ViewMode::Tree,
+        }
+    }
+}
+
+impl Config {
+    // Where session state like marks is kept, separate from user-edited settings
+    fn state_dir() -> Option<PathBuf> {
+        if let Some(state_home) = std::env::var_os("XDG_STATE_HOME") {
+            return Some(PathBuf::from(state_home).join("rusty_files"));
+        }
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state/rusty_files"))
+    }
+
+    fn config_path() -> Option<PathBuf> {
+        if let Some(config_home) = std::env::var_os("XDG_CONFIG_HOME") {
+            return Some(PathBuf::from(config_home).join("rusty_files/config.toml"));
+        }
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/rusty_files/config.toml"))
+    }
+
+    fn load() -> Result<Self, String> {
+        let path = match Self::config_path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        // Minimal TOML subset: `key = value` lines, optional [section] headers and # comments
+        let mut config = Config::default();
+        let mut section = String::new();
+
+        for (line_no, raw_line) in contents.lines().enumerate() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len() - 1].trim().to_string();
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", line_no + 1))?;
+            let key = key.trim();
+            let value = value.trim();
+
+            match (section.as_str(), key) {
+                ("", "confirm_rename_overwrite") => {
+                    config.confirm_rename_overwrite = Self::parse_bool(value, line_no)?;
+                }
+                ("", "sudo_keep_session") => {
+                    config.sudo_keep_session = Self::parse_bool(value, line_no)?;
+                }
+                ("", "theme") => {
+                    let name = Self::parse_string(value, line_no)?;
+                    config.theme = Theme::by_name(&name).ok_or_else(|| {
+                        format!("line {}: unknown theme `{}` (expected one of: {})", line_no + 1, name, Theme::NAMES.join(", "))
+                    })?;
+                }
+                ("", "trash_expiry_days") => {
+                    config.trash_expiry_days = value.parse().map_err(|_| {
+                        format!("line {}: expected a number of days, found `{}`", line_no + 1, value)
+                    })?;
+                }
+                ("", "confirm_paste_items") => {
+                    config.confirm_paste_items = value.parse().map_err(|_| {
+                        format!("line {}: expected a number of items, found `{}`", line_no + 1, value)
+                    })?;
+                }
+                ("", "confirm_external_moves") => {
+                    config.confirm_external_moves = Self::parse_bool(value, line_no)?;
+                }
+                ("", "show_hidden_count") => {
+                    config.show_hidden_count = Self::parse_bool(value, line_no)?;
+                }
+                ("", "edit_new_files") => {
+                    config.edit_new_files = Self::parse_bool(value, line_no)?;
+                }
+                ("", "follow_paste") => {
+                    config.follow_paste = Self::parse_bool(value, line_no)?;
+                }
+                ("", "tree_export_depth") => {
+                    config.tree_export_depth = value.parse().map_err(|_| {
+                        format!("line {}: tree_export_depth must be a whole number", line_no + 1)
+                    })?;
+                }
+                ("", "recent_files_count") => {
+                    // 0 would list nothing, which looks like an empty disk rather than a setting
+                    config.recent_files_count = value.parse().ok().filter(|&count| count > 0).ok_or_else(|| {
+                        format!("line {}: recent_files_count must be a whole number above 0", line_no + 1)
+                    })?;
+                }
+                ("", "min_name_width") => {
+                    config.min_name_width = value.parse().map_err(|_| {
+                        format!("line {}: min_name_width must be a whole number", line_no + 1)
+                    })?;
+                }
+                ("", "skip_single_child_dirs") => {
+                    config.skip_single_child_dirs = Self::parse_bool(value, line_no)?;
+                }
+                ("", "recursive_selection_size") => {
+                    config.recursive_selection_size = Self::parse_bool(value, line_no)?;
+                }
+                ("", "keep_selection_after_copy") => {
+                    config.keep_selection_after_copy = Self::parse_bool(value, line_no)?;
+                }
+                ("", "bulk_rename_resolve_conflicts") => {
+                    config.bulk_rename_resolve_conflicts = Self::parse_bool(value, line_no)?;
+                }
+                ("", "confirm_batch_conflicts") => {
+                    config.confirm_batch_conflicts = Self::parse_bool(value, line_no)?;
+                }
+                ("", "sanitize") => {
+                    let mut options = SanitizeOptions { spaces: false, lowercase: false, ascii: false };
+                    for flag in Self::parse_string(value, line_no)?.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+                        match flag {
+                            "spaces" => options.spaces = true,
+                            "lowercase" => options.lowercase = true,
+                            "ascii" => options.ascii = true,
+                            other => return Err(format!(
+                                "line {}: unknown sanitize flag `{}` (expected spaces, lowercase or ascii)",
+                                line_no + 1,
+                                other
+                            )),
+                        }
+                    }
+                    config.sanitize = options;
+                }
+                ("", "persist_marks") => {
+                    config.persist_marks = Self::parse_bool(value, line_no)?;
+                }
+                ("", "restore_last_dir") => {
+                    config.restore_last_dir = Self::parse_bool(value, line_no)?;
+                }
+                ("", "status_format") => {
+                    config.status_format = Some(Self::parse_string(value, line_no)?);
+                }
+                ("", "inline_tree") => {
+                    config.inline_tree = Self::parse_bool(value, line_no)?;
+                }
+                ("", "split_directories") => {
+                    config.split_directories = Self::parse_bool(value, line_no)?;
+                }
+                ("", "type_indicators") => {
+                    config.type_indicators = Self::parse_bool(value, line_no)?;
+                }
+                ("", "key_hints") => {
+                    config.key_hints = Self::parse_bool(value, line_no)?;
+                }
+                ("", "on_cd") => {
+                    config.on_cd = Some(Self::parse_string(value, line_no)?).filter(|command| !command.trim().is_empty());
+                }
+                ("", "title_stats") => {
+                    config.title_stats = Self::parse_bool(value, line_no)?;
+                }
+                ("", "age_colored_timestamps") => {
+                    config.age_colored_timestamps = Self::parse_bool(value, line_no)?;
+                }
+                ("", "highlight_broken_symlinks") => {
+                    config.highlight_broken_symlinks = Self::parse_bool(value, line_no)?;
+                }
+                ("open", extension) | ("open.terminal", extension) => {
+                    let command = Self::parse_string(value, line_no)?;
+                    config.openers.insert(extension.to_lowercase(), Opener {
+                        command,
+                        foreground: section == "open.terminal",
+                    });
+                }
+                ("", "dirs_position") => {
+                    config.dirs_position = match Self::parse_string(value, line_no)?.as_str() {
+                        "first" => DirsPosition::First,
+                        "last" => DirsPosition::Last,
+                        "mixed" => DirsPosition::Mixed,
+                        other => return Err(format!(
+                            "line {}: unknown dirs_position `{}` (expected first, last or mixed)",
+                            line_no + 1,
+                            other
+                        )),
+                    };
+                }
+                ("", "enter_on_dir") => {
+                    config.enter_on_dir = match Self::parse_string(value, line_no)?.as_str() {
+                        "enter" => DirEnterAction::Enter,
+                        "expand" => DirEnterAction::Expand,
+                        "open" => DirEnterAction::Open,
+                        other => return Err(format!(
+                            "line {}: unknown enter_on_dir `{}` (expected enter, expand or open)",
+                            line_no + 1,
+                            other
+                        )),
+                    };
+                }
+                ("", "view_mode") => {
+                    config.view_mode = match Self::parse_string(value, line_no)?.as_str() {
+                        "tree" => ViewMode::Tree,
+                        "flat" => ViewMode::Flat,
+                        other => return Err(format!(
+                            "line {}: unknown view_mode `{}` (expected tree or flat)",
+                            line_no + 1,
+                            other
+                        )),
+                    };
+                }
+                ("", "density") => {
+                    config.density = match Self::parse_string(value, line_no)?.as_str() {
+                        "compact" => Density::Compact,
+                        "comfortable" => Density::Comfortable,
+                        "detailed" => Density::Detailed,
+                        other => return Err(format!(
+                            "line {}: unknown density `{}` (expected compact, comfortable or detailed)",
+                            line_no + 1,
+                            other
+                        )),
+                    };
+                }
+                ("", "verify_copies") => {
+                    config.verify_copies = match Self::parse_string(value, line_no)?.as_str() {
+                        "off" => CopyVerification::Off,
+                        "size" => CopyVerification::Size,
+                        "content" => CopyVerification::Content,
+                        other => return Err(format!(
+                            "line {}: unknown verify_copies `{}` (expected off, size or content)",
+                            line_no + 1,
+                            other
+                        )),
+                    };
+                }
+                ("format_new", extension) => {
+                    config.formatters.insert(extension.to_lowercase(), Self::parse_string(value, line_no)?);
+                }
+                ("mounts", _) => {
+                    // The key is only a label; the value is the mount point
+                    config.mount_points.push(PathBuf::from(Self::parse_string(value, line_no)?));
+                }
+                _ => {} // Unknown keys are ignored so newer config files still load
+            }
+        }
+
+        Ok(config)
+    }
+
+    fn parse_string(value: &str, line_no: usize) -> Result<String, String> {
+        value.strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .map(|v| v.to_string())
+            .ok_or_else(|| format!("line {}: expected a quoted string, found `{}`", line_no + 1, value))
+    }
+
+    fn parse_bool(value: &str, line_no: usize) -> Result<bool, String> {
+        match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(format!("line {}: expected true or false, found `{}`", line_no + 1, value)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TaskKind {
+    Delete,
+    Move, // Cut pasted onto another filesystem; originals go to the trash once copied
+}
+
+// Messages sent from a background file operation to the UI loop
+enum TaskUpdate {
+    Progress {
+        done_bytes: u64,
+        total_bytes: u64,
+        done_files: u64,
+        total_files: u64,
+    },
+    Finished {
+        completed: Vec<(PathBuf, PathBuf)>, // (source, destination) pairs that were fully transferred
+        trashed: Vec<(PathBuf, PathBuf)>, // (source, trash path) of the originals a move kept
+        error: Option<(PathBuf, PathBuf, String)>, // Item the transfer stopped at, where it was going, and why
+    },
+}
+
+// A copy-then-remove transfer running on a worker thread, polled once per frame
+struct BackgroundTask {
+    kind: TaskKind,
+    label: String,
+    receiver: mpsc::Receiver<TaskUpdate>,
+    done_bytes: u64,
+    total_bytes: u64,
+    done_files: u64,
+    total_files: u64,
+}
+
+// Messages from a directory size walk
+enum SizeScanUpdate {
+    Progress(u64), // Bytes counted so far
+    Finished(u64),
+}
+
+// Recursive size of one directory, summed on a worker thread until finished or cancelled
+struct SizeScan {
+    path: PathBuf,
+    receiver: mpsc::Receiver<SizeScanUpdate>,
+    cancel: Arc<AtomicBool>,
+    bytes_so_far: u64,
+}
+
+// Walk of the current subtree for the most recently modified files, see start_recent_files
+struct RecentScan {
+    receiver: mpsc::Receiver<Vec<(PathBuf, SystemTime)>>,
+    cancel: Arc<AtomicBool>,
+}
+
+#[allow(dead_code)]
+struct TreeLine {
+    tree_prefix: String, // The indent + tree chars + icon part (styled dimly)
+    text: String,
+    timestamp: Option<String>, // Separate timestamp for styling
+    modified: Option<SystemTime>, // Drives the age-colored timestamp
+    entry_index: Option<usize>,
+    is_selected: bool,
+    is_cursor: bool,
+    is_dir: bool,
+    is_current_dir: bool,
+    is_hidden: bool, // Whether this is a hidden file/directory
+    is_cut: bool, // Pending a move from the clipboard
+    is_broken_link: bool, // Shown in the warning color when highlighting is enabled
+    is_detail: bool, // Second line of an entry in the detailed density
+    is_failed: bool, // Recently failed an operation; shown in the warning color
+    is_case_clash: bool, // Name equals a sibling's apart from case; shown in the warning color
+}
+
+struct FileExplorer {
+    current_dir: PathBuf,
+    entries: Vec<DirEntry>,
+    cursor_index: usize,
+    selected_indices: HashSet<usize>,
+    selection_anchor: Option<usize>,
+    scroll_offset: usize,
+    dir_memory: HashMap<PathBuf, DirState>,
+    clipboard: Option<Clipboard>,
+    ui_mode: UIMode,
+    undo_stack: Vec<UndoAction>,
+    trash_dir: PathBuf,
+    drag_selection: Option<usize>, // Tracks drag start index when dragging
+    size_cache: HashMap<PathBuf, u64>, // Cache for file/directory sizes
+    current_item_size: Option<u64>, // Size of item currently under cursor
+    media_info_cache: HashMap<PathBuf, Option<String>>, // Image dimensions read from file headers
+    current_item_media: Option<String>, // Media info of item currently under cursor, if recognized
+    sort_mode: SortMode, // Current sort key
+    sort_reverse: bool, // Reverse the natural order of the current sort key
+    terminal_width: usize, // Cached terminal width for rendering
+    show_hidden: bool, // Whether to show hidden files/directories
+    type_filter: TypeFilter, // Restrict the listing to directories or files
+    changed_since: Option<SystemTime>, // Reference time set with Alt+D; older items are hidden
+    status_message: Option<String>, // Temporary status message to show in status bar
+    message_log: Vec<(SystemTime, String)>, // Every status message of this session, oldest first
+    status_logged_last: bool, // The last log entry is the status bar's message, so amend_status may replace it
+    config: Config, // Settings loaded from the config file
+    theme: Theme, // Active color theme
+    age_colored_timestamps: bool, // Fade the timestamp column from bright (recent) to dim (old)
+    title_stats: bool, // Show item count, hidden count and total size in the title
+    inline_tree: bool, // Right/Left expand and collapse directories in place instead of entering them
+    time_field: TimeField, // Which timestamp the date column shows
+    split_directories: bool, // Keep the directory block in place while the files below it scroll
+    type_indicators: bool, // Append ls -F style type characters to entry names
+    key_hints: bool, // Reserve a line above the status bar for the keys of the current mode
+    density: Density, // Extra separator or detail lines in the listing
+    view_mode: ViewMode, // Tree with ancestors, or a flat list of the entries
+    dirs_position: DirsPosition, // Directories before, after or among the files
+    pinned_lines: usize, // Tree lines currently pinned above the scrolling region (0 = unified list)
+    read_only: bool, // --readonly: refuse every command that modifies files
+    log_file: Option<fs::File>, // --log: diagnostic log that actions and status messages are appended to
+    expanded_dirs: HashSet<PathBuf>, // Directories expanded in the inline tree
+    hidden_count: usize, // Dot-entries left out of the current listing
+    listing_size: u64, // Sum of the listed file sizes (directories count as 0)
+    free_space: Option<u64>, // Bytes available on the current filesystem, read on entering a directory when the status format uses it
+    task: Option<BackgroundTask>, // Long-running file operation in progress
+    size_scan: Option<SizeScan>, // Directory size being calculated in the background
+    dir_sizes: HashMap<PathBuf, Option<u64>>, // Sizes calculated on request this session; None when cancelled; dropped when something below changes
+    size_scan_queue: Vec<PathBuf>, // Directories of the listing still to be sized for the Directory Size sort
+    recent_scan: Option<RecentScan>, // Recently modified files being collected for Alt+U
+    failed_paths: HashMap<PathBuf, (std::time::Instant, String)>, // Items an operation just failed on, marked for a few seconds
+    listed_dir: Option<PathBuf>, // Directory the entries were last loaded from
+    case_clashes: HashSet<PathBuf>, // Entries whose name equals a sibling's apart from case
+    collapsed_chain: Option<(PathBuf, PathBuf)>, // Directory entered and where skip_single_child_dirs landed; kept while below it
+    text_clipboard: String, // Last text copied in the rename buffer, used when the system clipboard is unavailable
+    clipboard_unavailable_noted: bool, // The missing system clipboard has been reported once
+    foreground_command: Option<Command>, // Interactive program to run once the current event is handled
+    count_prefix: Option<usize>, // Digits typed before a motion, e.g. the 5 in "5j"
+    marks: HashMap<char, PathBuf>, // Directory marks set with m<letter>, jumped to with '<letter>
+    pinned: HashSet<PathBuf>, // Items listed first among their directory's directories or files, whatever the sort mode
+    last_rename_input: Option<String>, // Text of the last rename cancelled with Esc, recalled with Up
+    last_create_input: Option<String>, // Same for the create prompt
+    queue_mode: bool, // Collect paste/delete/rename into operation_queue instead of running them
+    operation_queue: Vec<QueuedOperation>,
+    pending_operator: Option<(char, usize)>, // First key of a two-key command like "dd", with its count
+}
+
+impl FileExplorer {
+    fn new(read_only: bool, log_file: Option<fs::File>, start_dir: Option<PathBuf>) -> io::Result<Self> {
+        let trash_dir = if let Some(home) = std::env::var_os("HOME") {
+            PathBuf::from(home).join(".local/share/rusty_files/trash")
+        } else {
+            PathBuf::from("/tmp/rusty_files_trash")
+        };
+
+        fs::create_dir_all(&trash_dir)?;
+
+        // A broken config falls back to defaults and is reported once the UI is up
+        let (config, config_error) = match Config::load() {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(format!("Config error: {}", e))),
+        };
+
+        // A directory given on the command line wins; a saved one that is gone falls back to the cwd
+        let current_dir = match start_dir {
+            Some(dir) => dir,
+            None => config.restore_last_dir
+                .then(Self::load_last_dir)
+                .flatten()
+                .map_or_else(std::env::current_dir, Ok)?,
+        };
+
+        let mut explorer = Self::with_config(config, trash_dir, current_dir, read_only, log_file);
+        explorer.pinned = Self::load_pinned();
+        if let Some(error) = config_error {
+            explorer.show_status(error);
+        }
+        if explorer.config.persist_marks {
+            explorer.marks = Self::load_marks();
+        }
+        if explorer.config.trash_expiry_days > 0 && !read_only {
+            explorer.purge_expired_trash();
+        }
+        explorer.load_directory()?;
+        Ok(explorer)
+    }
+
+    // The explorer's initial state; nothing is read from disk, so new() loads pins, marks and the listing
+    fn with_config(config: Config, trash_dir: PathBuf, current_dir: PathBuf, read_only: bool, log_file: Option<fs::File>) -> Self {
+        FileExplorer {
+            current_dir,
+            entries: Vec::new(),
+            cursor_index: 0,
+            selected_indices: HashSet::new(),
+            selection_anchor: None,
+            scroll_offset: 0,
+            dir_memory: HashMap::new(),
+            clipboard: None,
+            ui_mode: UIMode::Normal,
+            undo_stack: Vec::new(),
+            trash_dir,
+            drag_selection: None,
+            size_cache: HashMap::new(),
+            current_item_size: None,
+            media_info_cache: HashMap::new(),
+            current_item_media: None,
+            sort_mode: SortMode::Name,
+            sort_reverse: false,
+            terminal_width: 100, // Default width, will be updated on first render
+            show_hidden: false, // Hidden files/directories are hidden by default
+            type_filter: TypeFilter::All,
+            changed_since: None,
+            status_message: None,
+            message_log: Vec::new(),
+            status_logged_last: false,
+            theme: config.theme,
+            age_colored_timestamps: config.age_colored_timestamps,
+            title_stats: config.title_stats,
+            inline_tree: config.inline_tree,
+            time_field: TimeField::Modified,
+            split_directories: config.split_directories,
+            type_indicators: config.type_indicators,
+            key_hints: config.key_hints,
+            density: config.density,
+            view_mode: config.view_mode,
+            dirs_position: config.dirs_position,
+            pinned_lines: 0,
+            read_only,
+            log_file,
+            expanded_dirs: HashSet::new(),
+            hidden_count: 0,
+            listing_size: 0,
+            free_space: None,
+            config,
+            task: None,
+            size_scan: None,
+            dir_sizes: HashMap::new(),
+            size_scan_queue: Vec::new(),
+            recent_scan: None,
+            failed_paths: HashMap::new(),
+            listed_dir: None,
+            case_clashes: HashSet::new(),
+            collapsed_chain: None,
+            text_clipboard: String::new(),
+            clipboard_unavailable_noted: false,
+            foreground_command: None,
+            count_prefix: None,
+            marks: HashMap::new(),
+            pinned: HashSet::new(),
+            last_rename_input: None,
+            last_create_input: None,
+            queue_mode: false,
+            operation_queue: Vec::new(),
+            pending_operator: None,
+        }
     }
 
     fn load_directory(&mut self) -> io::Result<()> {
+        self.log(&format!("load {}", self.current_dir.display()));
         self.entries.clear();
+        self.hidden_count = 0;
+
+        let read_dir = match fs::read_dir(&self.current_dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                // Show an empty listing with an explanation, then offer to list it with sudo
+                self.set_entries(Vec::new());
+                let dir_name = self.current_dir.display().to_string();
+                self.show_status(format!("Permission denied: cannot list {}", dir_name));
+                if matches!(self.ui_mode, UIMode::Normal) {
+                    self.request_sudo(
+                        format!("Permission denied listing {}. Enter sudo password to list it:", dir_name),
+                        PendingOperation {
+                            items: vec![self.current_dir.clone()],
+                            destination: None,
+                            operation: OperationType::ListDirectory,
+                            undo_action: None,
+                        },
+                    )?;
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                self.set_entries(Vec::new());
+                self.show_status(format!("Cannot list directory: {}", e));
+                return Ok(());
+            }
+        };
+
+        let (mut entries, hidden_count) = self.read_entries(read_dir, 0);
+        self.sort_entries(&mut entries);
+        if self.inline_tree {
+            entries = self.expand_entries(entries);
+        }
+
+        self.hidden_count = hidden_count;
+        self.set_entries(entries);
+        Ok(())
+    }
+
+    // Visible entries of one directory plus the number of dot-entries left out
+    fn read_entries(&self, read_dir: fs::ReadDir, depth: usize) -> (Vec<DirEntry>, usize) {
+        let mut entries = Vec::new();
+        let mut hidden_count = 0;
+        for entry in read_dir.flatten() {
+            if let Ok(metadata) = entry.metadata() {
+                let path = entry.path();
+                let file_name = entry.file_name();
+                let lossy_name = file_name.to_str().is_none();
+                let name = file_name.to_string_lossy().to_string();
+
+                // entry.metadata() describes the link itself; describe working links by their target
+                // path.exists() follows the link, so it is false exactly when the target is gone
+                let is_symlink = metadata.file_type().is_symlink();
+                let is_broken_link = is_symlink && !path.exists();
+                let metadata = if is_symlink {
+                    fs::metadata(&path).unwrap_or(metadata)
+                } else {
+                    metadata
+                };
+                let is_dir = metadata.is_dir();
+                let special = SpecialKind::of(&metadata.file_type());
+
+                if !self.is_entry_visible(&name, is_dir) {
+                    if name.starts_with('.') {
+                        hidden_count += 1;
+                    }
+                    continue;
+                }
+
+                let modified = if is_dir {
+                    // For directories, get max modified time from contents (depth limit 1)
+                    Self::get_dir_max_modified(&path, 1)
+                } else {
+                    // For files, use the file's modified time
+                    metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)
+                };
+                if self.changed_since.is_some_and(|since| modified <= since) {
+                    continue;
+                }
+                // Not every filesystem records creation or access times; show the modified time instead
+                let time = match self.time_field {
+                    TimeField::Modified => modified,
+                    TimeField::Created => metadata.created().unwrap_or(modified),
+                    TimeField::Accessed => metadata.accessed().unwrap_or(modified),
+                };
+
+                // Get permissions
+                let permissions = metadata.permissions().mode();
+                let size = if is_dir { 0 } else { metadata.len() };
+
+                entries.push(DirEntry {
+                    path,
+                    name,
+                    is_dir,
+                    time,
+                    permissions,
+                    size,
+                    is_symlink,
+                    is_broken_link,
+                    special,
+                    lossy_name,
+                    depth,
+                });
+            }
+        }
+
+        (entries, hidden_count)
+    }
+
+    // Insert the sorted children of every expanded directory right after it, recursively
+    fn expand_entries(&self, entries: Vec<DirEntry>) -> Vec<DirEntry> {
+        let mut expanded = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let children = if entry.is_dir && self.expanded_dirs.contains(&entry.path) {
+                fs::read_dir(&entry.path).ok().map(|read_dir| {
+                    let (mut children, _) = self.read_entries(read_dir, entry.depth + 1);
+                    self.sort_entries(&mut children);
+                    self.expand_entries(children)
+                })
+            } else {
+                None
+            };
+            expanded.push(entry);
+            expanded.extend(children.unwrap_or_default());
+        }
+        expanded
+    }
+
+    // Hidden-file and directories-only / files-only filtering shared by all listing paths
+    fn is_entry_visible(&self, name: &str, is_dir: bool) -> bool {
+        if !self.show_hidden && name.starts_with('.') {
+            return false;
+        }
+        match self.type_filter {
+            TypeFilter::DirsOnly => is_dir,
+            TypeFilter::FilesOnly => !is_dir,
+            TypeFilter::All => true,
+        }
+    }
+
+    fn sort_entries(&self, entries: &mut [DirEntry]) {
+        // Group directories as configured, then sort based on current sort mode with pinned items first in each group
+        let sort_mode = self.sort_mode;
+        let dirs_position = self.dirs_position;
+        let reverse = self.sort_reverse;
+        let pinned = &self.pinned;
+        let dir_sizes = &self.dir_sizes;
+        entries.sort_by(|a, b| {
+            // Pins stay inside their group, so the directory block (split view, density separator) remains whole
+            let group_order = match dirs_position {
+                DirsPosition::First => b.is_dir.cmp(&a.is_dir),
+                DirsPosition::Last => a.is_dir.cmp(&b.is_dir),
+                DirsPosition::Mixed => std::cmp::Ordering::Equal,
+            };
+            if group_order.is_ne() {
+                return group_order;
+            }
+            let pin_order = pinned.contains(&b.path).cmp(&pinned.contains(&a.path));
+            if pin_order.is_ne() {
+                return pin_order;
+            }
+            match (a.is_dir, b.is_dir) {
+                // Largest first; directories whose size isn't known yet go last
+                (true, true) if sort_mode == SortMode::DirSize => {
+                    let size = |entry: &DirEntry| dir_sizes.get(&entry.path).copied().flatten();
+                    let ordering = size(b).cmp(&size(a))
+                        .then_with(|| Self::compare_entries(sort_mode, a, b));
+                    if reverse { ordering.reverse() } else { ordering }
+                }
+                _ => {
+                    let ordering = Self::compare_entries(sort_mode, a, b);
+                    if reverse { ordering.reverse() } else { ordering }
+                }
+            }
+        });
+    }
+
+    // Starts the on_cd command for the new directory without waiting for it; problems only reach the message log
+    fn run_cd_hook(&mut self) {
+        let Some(template) = self.config.on_cd.clone() else {
+            return;
+        };
+        let Some(mut command) = Self::template_command(&template, &self.current_dir) else {
+            return;
+        };
+
+        use std::os::unix::process::CommandExt;
+        let result = command
+            .current_dir(&self.current_dir)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .process_group(0) // Keep terminal signals (Ctrl+C) away from the hook
+            .spawn();
+        match result {
+            Ok(mut child) => {
+                // Reaped on a thread so hooks left running don't pile up as zombies
+                std::thread::spawn(move || child.wait());
+            }
+            Err(e) => self.log_message(format!("on_cd hook `{}` failed to start: {}", template, e)),
+        }
+    }
+
+    fn set_entries(&mut self, entries: Vec<DirEntry>) {
+        self.prune_clipboard();
+        self.entries = entries;
+        self.listing_size = self.entries.iter().map(|entry| entry.size).sum();
+        self.find_case_clashes();
+
+        // Only on arriving in a directory; reloads of the same listing keep quiet
+        let entering = self.listed_dir.as_ref() != Some(&self.current_dir);
+        self.listed_dir = Some(self.current_dir.clone());
+        if entering {
+            self.run_cd_hook();
+            // Anywhere outside the landed directory's subtree, the chain no longer applies
+            if self.collapsed_chain.as_ref().is_some_and(|(_, landed)| !self.current_dir.starts_with(landed)) {
+                self.collapsed_chain = None;
+            }
+            // df runs a process, too slow for every refresh and keystroke-driven reload
+            if self.config.status_format.as_deref().is_some_and(|format| format.contains("{free}")) {
+                self.free_space = Self::available_space(&self.current_dir);
+            }
+        }
+
+        if let Some(state) = self.dir_memory.get(&self.current_dir) {
+            let entry_count = self.entries.len();
+            self.cursor_index = state.cursor_index.min(entry_count.saturating_sub(1));
+            self.selected_indices = state.selected_indices.iter().copied().filter(|&i| i < entry_count).collect();
+            self.scroll_offset = state.scroll_offset;
+            if entering && !self.selected_indices.is_empty() && self.status_message.is_none() {
+                self.show_status(format!("Restored selection of {} item(s)", self.selected_indices.len()));
+            }
+        } else {
+            self.cursor_index = 0;
+            self.selected_indices.clear();
+            self.scroll_offset = 0;
+        }
+
+        self.selection_anchor = None;
+
+        // Clear size cache for new directory and update current item size
+        self.size_cache.clear();
+        self.media_info_cache.clear();
+        self.update_current_item_size();
+
+        if self.sort_mode == SortMode::DirSize {
+            self.queue_dir_size_scans();
+        }
+    }
+
+    fn load_directory_sudo(&mut self, password: &str) -> io::Result<usize> {
+        self.validate_sudo_password(password)?;
+
+        let dir_str = self.current_dir.to_str().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
+        })?;
+
+        // One NUL-terminated record per entry: type, type following links, chosen time, mtime, mode, size, name
+        let time_directive = match self.time_field {
+            TimeField::Modified => "%T@",
+            TimeField::Created => "%B@",
+            TimeField::Accessed => "%A@",
+        };
+        let format = format!("%y\\t%Y\\t{}\\t%T@\\t%m\\t%s\\t%f\\0", time_directive);
+        let mut child = Command::new("sudo")
+            .arg("-S")
+            .args(["find", dir_str, "-mindepth", "1", "-maxdepth", "1"])
+            .args(["-printf", &format])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{}", password)?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(error_msg.to_string()));
+        }
+
+        let mut entries = Vec::new();
+        let mut hidden_count = 0;
+        for record in output.stdout.split(|b| *b == 0).filter(|r| !r.is_empty()) {
+            use std::os::unix::ffi::OsStrExt;
+
+            // The name comes last and may not be UTF-8; its raw bytes build the path
+            let fields: Vec<&[u8]> = record.splitn(7, |b| *b == b'\t').collect();
+            let [link_type, file_type, time, mtime, mode, size, raw_name] = fields[..] else {
+                continue;
+            };
+            let [link_type, file_type, time, mtime, mode, size] =
+                [link_type, file_type, time, mtime, mode, size].map(|field| String::from_utf8_lossy(field).to_string());
+            let file_name = std::ffi::OsStr::from_bytes(raw_name);
+            let lossy_name = file_name.to_str().is_none();
+            let name = file_name.to_string_lossy().to_string();
+
+            // find reports N for a missing link target and L for a link loop
+            let is_dir = file_type == "d";
+            let is_symlink = link_type == "l";
+            let is_broken_link = is_symlink && (file_type == "N" || file_type == "L");
+            if !self.is_entry_visible(&name, is_dir) {
+                if name.starts_with('.') {
+                    hidden_count += 1;
+                }
+                continue;
+            }
+
+            // find prints an unknown birth time as 0 or -1
+            let parse_secs = |field: &str| field.split('.').next().and_then(|s| s.parse::<u64>().ok()).filter(|&secs| secs > 0);
+            let modified_secs = parse_secs(&mtime).unwrap_or(0);
+            if self.changed_since.is_some_and(|since| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(modified_secs) <= since) {
+                continue;
+            }
+            let secs = parse_secs(&time).unwrap_or(modified_secs);
+            entries.push(DirEntry {
+                path: self.current_dir.join(file_name),
+                name,
+                is_dir,
+                time: SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs),
+                permissions: u32::from_str_radix(&mode, 8).unwrap_or(0),
+                size: if is_dir { 0 } else { size.parse().unwrap_or(0) },
+                is_symlink,
+                is_broken_link,
+                special: SpecialKind::from_find_type(&file_type),
+                lossy_name,
+                depth: 0,
+            });
+        }
+
+        let count = entries.len();
+        self.sort_entries(&mut entries);
+        self.hidden_count = hidden_count;
+        self.set_entries(entries);
+        Ok(count)
+    }
+
+    // Siblings whose names only differ by case would collide on a case-insensitive filesystem
+    fn find_case_clashes(&mut self) {
+        let mut groups: HashMap<(Option<&Path>, String), Vec<&PathBuf>> = HashMap::new();
+        for entry in &self.entries {
+            groups.entry((entry.path.parent(), entry.name.to_lowercase()))
+                .or_default()
+                .push(&entry.path);
+        }
+        self.case_clashes = groups.into_values()
+            .filter(|paths| paths.len() > 1)
+            .flatten()
+            .cloned()
+            .collect();
+    }
+
+    fn build_tree_lines(&self, terminal_width: usize) -> Vec<TreeLine> {
+        let mut lines = Vec::new();
+        let ancestors = self.get_ancestors();
+        let highlight_broken = self.config.highlight_broken_symlinks;
+        let flat = self.view_mode == ViewMode::Flat;
+
+        for (depth, path) in ancestors.iter().enumerate() {
+            // Simple indentation without vertical pipes
+            let indent = "  ".repeat(depth);
+
+            let name = path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("/")
+                .to_string();
+
+            let is_current = path == &self.current_dir;
+            // Use corner pipe (└─) for directories on path to current
+            let marker = if depth == ancestors.len() - 1 {
+                "└─"  // Current directory (last in ancestors list)
+            } else if depth > 0 {
+                "└─"  // Intermediate directories on path
+            } else {
+                "─ "  // Root directory
+            };
+
+            if !flat {
+                lines.push(TreeLine {
+                    tree_prefix: format!("{}{}", indent, marker),
+                    text: name,
+                    timestamp: None,
+                    modified: None,
+                    entry_index: None,
+                    is_selected: false,
+                    is_cursor: false,
+                    is_dir: true,
+                    is_current_dir: is_current,
+                    is_hidden: false,
+                    is_cut: false,
+                    is_broken_link: false,
+                    is_detail: false,
+                    is_failed: false,
+                    is_case_clash: false,
+                });
+            }
+
+            if is_current && !self.entries.is_empty() {
+                // Child items should be indented one level more than the current directory
+                // Add one extra space after the base indentation
+                let child_indent = if flat { String::new() } else { format!("{}  ", "  ".repeat(depth)) };
+
+                let cut_items: HashSet<&PathBuf> = match &self.clipboard {
+                    Some(Clipboard { items, operation: ClipboardOp::Cut }) => items.iter().collect(),
+                    _ => HashSet::new(),
+                };
+
+                // Numbers to type while jumping; other lines get blanks so the tree stays aligned
+                let gutter_width = if matches!(self.ui_mode, UIMode::JumpToNumber { .. }) {
+                    self.entries.len().to_string().len() + 1
+                } else {
+                    0
+                };
+                let blank_gutter = " ".repeat(gutter_width);
+
+                let separate_groups = self.density == Density::Comfortable && self.dirs_position != DirsPosition::Mixed;
+                let mut previous_top_level_dir = None;
+                let mut separated = false;
+                for (i, entry) in self.entries.iter().enumerate() {
+                    // Set the files apart from the directories, whichever group comes first
+                    if entry.depth == 0 {
+                        if separate_groups && !separated && previous_top_level_dir == Some(!entry.is_dir) {
+                            separated = true;
+                            lines.push(TreeLine {
+                                tree_prefix: if flat { String::new() } else { format!("{}{}│", blank_gutter, child_indent) },
+                                text: String::new(),
+                                timestamp: None,
+                                modified: None,
+                                entry_index: None,
+                                is_selected: false,
+                                is_cursor: false,
+                                is_dir: false,
+                                is_current_dir: false,
+                                is_hidden: false,
+                                is_cut: false,
+                                is_broken_link: false,
+                                is_detail: false,
+                                is_failed: false,
+                                is_case_clash: false,
+                            });
+                        }
+                        previous_top_level_dir = Some(entry.is_dir);
+                    }
+
+                    // Last among its siblings: no later entry at the same depth before the tree climbs back out
+                    let is_last = self.entries[i + 1..]
+                        .iter()
+                        .find(|next| next.depth <= entry.depth)
+                        .is_none_or(|next| next.depth < entry.depth);
+                    let tree_char = if flat {
+                        ""
+                    } else if is_last {
+                        "└─"
+                    } else {
+                        "├─"
+                    };
+                    let entry_indent = format!("{}{}", child_indent, "  ".repeat(entry.depth));
+                    let icon = match entry.special {
+                        Some(kind) => kind.glyph(),
+                        None => Self::get_file_icon(&entry.name, entry.is_dir, entry.permissions),
+                    };
+                    let mut perms_str = Self::format_permissions(entry.permissions, entry.is_dir);
+                    if entry.is_symlink {
+                        // Like ls -l, mark links with an 'l' type character
+                        perms_str.replace_range(0..1, "l");
+                    } else if let Some(kind) = entry.special {
+                        perms_str.replace_range(0..1, kind.type_char());
+                    }
+                    let date_str = Self::format_date(entry.time);
+
+                    // Check if this is a hidden file/directory (starts with .)
+                    let is_hidden = entry.name.starts_with('.');
+
+                    let buffer = 1; // Space between filename and timestamp (reduced to move timestamp left)
+
+                    // tree_char "├─" or "└─" is 2 chars
+                    // icon "" or "" is 1 char + space = 2 chars
+                    let tree_char_width = tree_char.chars().count();
+                    let icon_display_width = 2; // " " or " "
+                    let prefix_len = gutter_width + entry_indent.len() + tree_char_width + icon_display_width;
+
+                    // Timestamp format is "drwxr-xr-x   YYYY-MM-DD HH:mm" (29 chars: 10 for perms + 3 spaces + 16 for date).
+                    // On narrow terminals the permissions go first, then the date, so the name keeps min_name_width columns
+                    let room = terminal_width.saturating_sub(prefix_len + buffer); // No border anymore
+                    let timestamp_str = [format!("{}   {}", perms_str, date_str), date_str]
+                        .into_iter()
+                        .find(|timestamp| room >= timestamp.width() + self.config.min_name_width);
+                    let date_width = timestamp_str.as_ref().map_or(0, |timestamp| timestamp.width());
+
+                    // Available width for filename
+                    let available_width = room - date_width;
+
+                    // Part of the name, so truncation and padding below measure it too
+                    let name = if self.type_indicators {
+                        format!("{}{}", entry.name, Self::type_indicator(entry))
+                    } else {
+                        entry.name.clone()
+                    };
+                    let full_name = if highlight_broken && entry.is_broken_link {
+                        format!("{} (broken)", name)
+                    } else if let Some(kind) = entry.special {
+                        format!("{} ({})", name, kind.label())
+                    } else if entry.lossy_name {
+                        // Undecodable bytes show as U+FFFD; the real name differs
+                        format!("{} (non-UTF-8 name)", name)
+                    } else {
+                        name
+                    };
+                    let full_name = if self.pinned.contains(&entry.path) {
+                        format!("{} (pinned)", full_name)
+                    } else {
+                        full_name
+                    };
+                    let is_case_clash = self.case_clashes.contains(&entry.path);
+                    let full_name = if is_case_clash {
+                        format!("{} (case clash)", full_name)
+                    } else {
+                        full_name
+                    };
+                    let failure = self.failed_paths.get(&entry.path);
+                    let full_name = match failure {
+                        Some((_, reason)) => format!("{} (failed: {})", full_name, reason),
+                        None => full_name,
+                    };
+
+                    // Truncate filename if needed and pad to fixed width
+                    let display_name = Self::truncate_to_width(&full_name, available_width);
+
+                    // Pad filename to fill available_width so timestamp stays at fixed position
+                    // Measured in terminal columns: CJK and emoji take two
+                    let name_len = display_name.width();
+                    let padding_for_name = available_width.saturating_sub(name_len);
+                    let padding = " ".repeat(padding_for_name);
+
+                    let gutter = if gutter_width > 0 {
+                        format!("{:>width$} ", i + 1, width = gutter_width - 1)
+                    } else {
+                        String::new()
+                    };
+                    lines.push(TreeLine {
+                        tree_prefix: format!("{}{}{} {} ", gutter, entry_indent, tree_char, icon),
+                        text: format!("{}{}", display_name, padding),
+                        timestamp: timestamp_str,
+                        modified: Some(entry.time),
+                        entry_index: Some(i),
+                        is_selected: self.selected_indices.contains(&i),
+                        is_cursor: i == self.cursor_index,
+                        is_dir: entry.is_dir,
+                        is_current_dir: false,
+                        is_hidden,
+                        is_cut: cut_items.contains(&entry.path),
+                        is_broken_link: highlight_broken && entry.is_broken_link,
+                        is_detail: false,
+                        is_failed: failure.is_some(),
+                        is_case_clash,
+                    });
+
+                    if self.density == Density::Detailed {
+                        // Belongs to the same entry, so clicks and the cursor highlight cover both lines
+                        let continuation = if flat || is_last { "   " } else { "│  " };
+                        let detail = Self::entry_detail(entry);
+                        let detail_width = available_width + date_width;
+                        let detail = Self::truncate_to_width(&detail, detail_width);
+                        let padding = " ".repeat(detail_width.saturating_sub(detail.width()));
+                        lines.push(TreeLine {
+                            tree_prefix: format!("{}{}{} ", blank_gutter, entry_indent, continuation),
+                            text: format!("{}{}", detail, padding),
+                            timestamp: None,
+                            modified: None,
+                            entry_index: Some(i),
+                            is_selected: self.selected_indices.contains(&i),
+                            is_cursor: i == self.cursor_index,
+                            is_dir: entry.is_dir,
+                            is_current_dir: false,
+                            is_hidden,
+                            is_cut: false,
+                            is_broken_link: false,
+                            is_detail: true,
+                            is_failed: false,
+                            is_case_clash: false,
+                        });
+                    }
+                }
+            }
+        }
+
+        lines
+    }
+
+    // Second line of an entry in the detailed density
+    fn entry_detail(entry: &DirEntry) -> String {
+        let mut detail = if let Some(kind) = entry.special {
+            kind.label().to_string()
+        } else if entry.is_dir {
+            "directory".to_string()
+        } else {
+            Self::format_file_size(entry.size)
+        };
+        if entry.is_symlink {
+            let target = fs::read_link(&entry.path)
+                .map(|target| target.display().to_string())
+                .unwrap_or_else(|_| "?".to_string());
+            detail.push_str(&format!(" -> {}", target));
+        }
+        detail
+    }
+
+    fn truncate_to_width(name: &str, max_width: usize) -> String {
+        if name.width() <= max_width {
+            return name.to_string();
+        }
+
+        // Cut on grapheme boundaries so combining marks and emoji sequences stay whole
+        let ellipsis = if max_width > 3 { "..." } else { "" }; // Too narrow for "..." to fit as well
+        let budget = max_width - ellipsis.len();
+        let mut truncated = String::new();
+        let mut used = 0;
+        for grapheme in name.graphemes(true) {
+            let grapheme_width = grapheme.width();
+            if used + grapheme_width > budget {
+                break;
+            }
+            truncated.push_str(grapheme);
+            used += grapheme_width;
+        }
+        format!("{}{}", truncated, ellipsis)
+    }
+
+    // Leading directory lines (ancestors and the directories sorted first) to pin in split mode
+    fn pinned_line_count(&self, visible_height: usize, tree_lines: &[TreeLine]) -> usize {
+        if !self.split_directories {
+            return 0;
+        }
+        let count = tree_lines.iter().take_while(|line| line.is_dir).count();
+        // Nothing to scroll beneath, or too many directories to leave room for files: keep one list
+        if count == tree_lines.len() || count > visible_height / 2 {
+            0
+        } else {
+            count
+        }
+    }
+
+    // Map a terminal row inside the explorer area to its tree line, accounting for the pinned block
+    fn line_at_row(&self, row: u16, area_top: u16) -> usize {
+        let row = (row as usize).saturating_sub(area_top as usize + 1);
+        if row < self.pinned_lines {
+            row
+        } else {
+            row.saturating_add(self.scroll_offset)
+        }
+    }
+
+    fn calculate_scroll_offset(&mut self, visible_height: usize, tree_lines: &[TreeLine]) {
+        let scrolloff = 1;
+
+        // In split mode only the lines below the pinned directories scroll
+        self.pinned_lines = self.pinned_line_count(visible_height, tree_lines);
+        let tree_lines = &tree_lines[self.pinned_lines..];
+        let visible_height = visible_height.saturating_sub(self.pinned_lines);
+
+        if visible_height == 0 {
+            return;
+        }
+
+        let Some(cursor_line_idx) = tree_lines.iter().position(|line| line.is_cursor) else {
+            // Cursor is in the pinned block; leave the files where they are
+            self.scroll_offset = self.scroll_offset.min(tree_lines.len().saturating_sub(visible_height));
+            return;
+        };
+
+        if cursor_line_idx < self.scroll_offset + scrolloff {
+            self.scroll_offset = cursor_line_idx.saturating_sub(scrolloff);
+        } else if cursor_line_idx >= self.scroll_offset + visible_height - scrolloff {
+            self.scroll_offset = cursor_line_idx + scrolloff + 1 - visible_height.min(tree_lines.len());
+        }
+
+        self.scroll_offset = self.scroll_offset.min(tree_lines.len().saturating_sub(visible_height));
+    }
+
+    fn save_state(&mut self) {
+        self.dir_memory.insert(
+            self.current_dir.clone(),
+            DirState {
+                cursor_index: self.cursor_index,
+                selected_indices: self.selected_indices.clone(),
+                scroll_offset: self.scroll_offset,
+            },
+        );
+    }
+
+    fn move_up(&mut self, shift: bool) {
+        if self.cursor_index > 0 {
+            if shift {
+                if self.selection_anchor.is_none() {
+                    self.selection_anchor = Some(self.cursor_index);
+                }
+            } else {
+                self.selected_indices.clear();
+                self.selection_anchor = None;
+            }
+
+            self.cursor_index -= 1;
+
+            if shift {
+                self.update_selection_range();
+            }
+
+            self.save_state();
+            self.update_current_item_size();
+        }
+    }
+
+    fn move_down(&mut self, shift: bool) {
+        if self.cursor_index < self.entries.len().saturating_sub(1) {
+            if shift {
+                if self.selection_anchor.is_none() {
+                    self.selection_anchor = Some(self.cursor_index);
+                }
+            } else {
+                self.selected_indices.clear();
+                self.selection_anchor = None;
+            }
+
+            self.cursor_index += 1;
+
+            if shift {
+                self.update_selection_range();
+            }
+
+            self.save_state();
+            self.update_current_item_size();
+        }
+    }
+
+    fn move_up_by(&mut self, count: usize, shift: bool) {
+        for _ in 0..count.min(self.entries.len()) {
+            self.move_up(shift);
+        }
+    }
+
+    fn move_down_by(&mut self, count: usize, shift: bool) {
+        for _ in 0..count.min(self.entries.len()) {
+            self.move_down(shift);
+        }
+    }
+
+    fn delete_lines(&mut self, count: usize) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        // Select `count` items starting at the cursor, then confirm as a normal delete
+        let end = self.cursor_index.saturating_add(count).min(self.entries.len());
+        self.selected_indices = (self.cursor_index..end).collect();
+        self.selection_anchor = None;
+        self.save_state();
+        self.delete_selected(false);
+    }
+
+    fn update_selection_range(&mut self) {
+        if let Some(anchor) = self.selection_anchor {
+            self.selected_indices.clear();
+            let start = anchor.min(self.cursor_index);
+            let end = anchor.max(self.cursor_index);
+            for i in start..=end {
+                self.selected_indices.insert(i);
+            }
+        }
+    }
+
+    fn toggle_selection(&mut self) {
+        if self.selected_indices.contains(&self.cursor_index) {
+            self.selected_indices.remove(&self.cursor_index);
+        } else {
+            self.selected_indices.insert(self.cursor_index);
+        }
+        self.selection_anchor = None;
+        self.save_state();
+    }
+
+    // Everything the hidden and type filters leave in the listing, ready for one copy, cut or delete
+    fn select_all_listed(&mut self) {
+        if self.entries.is_empty() {
+            self.show_status("Nothing to select".to_string());
+            return;
+        }
+        self.selected_indices = (0..self.entries.len()).collect();
+        self.selection_anchor = None;
+        self.save_state();
+        self.cache_selected_sizes();
+
+        let filter = match self.type_filter {
+            TypeFilter::All => "",
+            TypeFilter::DirsOnly => " (directories only)",
+            TypeFilter::FilesOnly => " (files only)",
+        };
+        self.show_status(format!("Selected all {} listed item(s){}", self.entries.len(), filter));
+    }
+
+    // Files only, case-insensitively, like the extension sort; extensionless files (dotfiles too) go together
+    fn select_same_extension(&mut self) {
+        let Some(cursor_entry) = self.entries.get(self.cursor_index) else {
+            self.show_status("Nothing to select".to_string());
+            return;
+        };
+        if cursor_entry.is_dir {
+            self.show_status("Put the cursor on a file to select others with its extension".to_string());
+            return;
+        }
+        let extension = |entry: &DirEntry| entry.path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+        let wanted = extension(cursor_entry);
+
+        self.selected_indices = self.entries.iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.is_dir && extension(entry) == wanted)
+            .map(|(i, _)| i)
+            .collect();
+        self.selection_anchor = None;
+        self.save_state();
+        self.cache_selected_sizes();
+
+        let count = self.selected_indices.len();
+        match wanted {
+            Some(ext) => self.show_status(format!("Selected {} .{} file(s)", count, ext)),
+            None => self.show_status(format!("Selected {} file(s) without an extension", count)),
+        }
+    }
+
+    fn jump_to_selected(&mut self, last: bool) {
+        let target = if last {
+            self.selected_indices.iter().max()
+        } else {
+            self.selected_indices.iter().min()
+        };
+
+        if let Some(&index) = target {
+            self.cursor_index = index;
+            self.save_state();
+            self.update_current_item_size();
+        } else {
+            self.show_status("No items selected".to_string());
+        }
+    }
+
+    // Plain text lines up like the listing; CSV adds a header row and exact byte counts
+    fn format_listing(&self, csv: bool) -> String {
+        let rows: Vec<(String, &DirEntry)> = self.entries.iter()
+            .map(|entry| {
+                // Expanded tree entries are named relative to the listed directory
+                let name = entry.path.strip_prefix(&self.current_dir)
+                    .map(|relative| relative.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| entry.name.clone());
+                (name, entry)
+            })
+            .collect();
+        let size_of = |entry: &DirEntry| if entry.is_dir { String::new() } else { Self::format_file_size(entry.size) };
+
+        let mut output = String::new();
+        if csv {
+            let quote = |field: &str| {
+                if field.contains([',', '"', '\n']) {
+                    format!("\"{}\"", field.replace('"', "\"\""))
+                } else {
+                    field.to_string()
+                }
+            };
+            output.push_str(&format!("name,type,size,bytes,{}\n", self.time_field.label()));
+            for (name, entry) in &rows {
+                let kind = if entry.is_dir { "directory" } else { entry.special.map(SpecialKind::label).unwrap_or("file") };
+                let bytes = if entry.is_dir { String::new() } else { entry.size.to_string() };
+                output.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    quote(name),
+                    kind,
+                    size_of(entry),
+                    bytes,
+                    Self::format_date(entry.time)
+                ));
+            }
+        } else {
+            let names: Vec<String> = rows.iter()
+                .map(|(name, entry)| if entry.is_dir { format!("{}/", name) } else { name.clone() })
+                .collect();
+            let name_width = names.iter().map(|name| name.width()).max().unwrap_or(0);
+            for (name, (_, entry)) in names.iter().zip(&rows) {
+                let padding = " ".repeat(name_width - name.width());
+                output.push_str(&format!("{}{}  {:>10}  {}\n", name, padding, size_of(entry), Self::format_date(entry.time)));
+            }
+        }
+        output
+    }
+
+    // The current directory's subtree with the listing's glyphs; hidden entries only while they are shown
+    fn format_tree(&self) -> (String, usize) {
+        const MAX_ITEMS: usize = 5000;
+
+        let root_name = self.current_dir.file_name()
+            .map(|name| format!("{}/", name.to_string_lossy()))
+            .unwrap_or_else(|| "/".to_string());
+        let mut output = format!("{}\n", root_name);
+        let mut count = 0;
+        if !self.write_subtree(&self.current_dir, "", 1, &mut output, &mut count, MAX_ITEMS) {
+            output.push_str(&format!("… stopped at {} items\n", MAX_ITEMS));
+        }
+        (output, count)
+    }
+
+    // False once max_items lines are written and the rest is left out
+    fn write_subtree(&self, dir: &Path, prefix: &str, depth: usize, output: &mut String, count: &mut usize, max_items: usize) -> bool {
+        let mut children: Vec<(String, bool, PathBuf)> = fs::read_dir(dir).into_iter().flatten().flatten()
+            .map(|entry| {
+                // file_type does not follow symlinks, so linked directories are listed but not walked
+                let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+                (entry.file_name().to_string_lossy().to_string(), is_dir, entry.path())
+            })
+            .filter(|(name, ..)| self.show_hidden || !name.starts_with('.'))
+            .collect();
+        children.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase())));
+
+        let last = children.len().saturating_sub(1);
+        for (i, (name, is_dir, path)) in children.into_iter().enumerate() {
+            if *count == max_items {
+                return false;
+            }
+            *count += 1;
+            let (branch, continuation) = if i == last { ("└─ ", "   ") } else { ("├─ ", "│  ") };
+            let suffix = if is_dir { "/" } else { "" };
+            output.push_str(&format!("{}{}{}{}\n", prefix, branch, name, suffix));
+            if is_dir
+                && depth < self.config.tree_export_depth
+                && !self.write_subtree(&path, &format!("{}{}", prefix, continuation), depth + 1, output, count, max_items)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn start_export_listing(&mut self) {
+        if self.entries.is_empty() {
+            self.show_status("Nothing to export".to_string());
+        } else {
+            self.ui_mode = UIMode::ExportListing { format: None, name: String::new() };
+        }
+    }
+
+    fn export_listing(&mut self, format: ExportFormat, name: &str) -> io::Result<()> {
+        let (listing, count) = match format {
+            ExportFormat::Tree => self.format_tree(),
+            _ => (self.format_listing(format == ExportFormat::Csv), self.entries.len()),
+        };
+        let format = format.label();
+
+        let name = name.trim();
+        if name.is_empty() {
+            match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(listing)) {
+                Ok(()) => self.show_status(format!("Copied the listing of {} item(s) as {}", count, format)),
+                Err(e) => self.show_status(format!("Failed to copy the listing: {}", e)),
+            }
+            return Ok(());
+        }
+
+        if self.read_only {
+            self.show_status("Read-only mode: leave the name empty to copy the listing instead".to_string());
+            return Ok(());
+        }
+        let path = self.current_dir.join(name);
+        // create_new checks and creates in one step, so nothing that appears meanwhile is overwritten
+        let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                self.show_status(format!("'{}' already exists; nothing written", name));
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        if let Err(e) = file.write_all(listing.as_bytes()) {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+        self.log(&format!("export {}", path.display()));
+        self.undo_stack.push(UndoAction::Copy { copied_files: vec![path] });
+        self.reload_keeping_position()?;
+        self.show_status(format!("Exported the listing of {} item(s) as {} to '{}'", count, format, name));
+        Ok(())
+    }
+
+    fn start_jump_to_number(&mut self) {
+        if self.entries.is_empty() {
+            self.show_status("Nothing to jump to".to_string());
+        } else {
+            self.ui_mode = UIMode::JumpToNumber { buffer: String::new() };
+        }
+    }
+
+    fn jump_to_number(&mut self, buffer: &str) {
+        let Ok(number) = buffer.parse::<usize>() else {
+            return;
+        };
+        // Numbers shown start at 1; anything past the end lands on the last entry
+        self.cursor_index = number.clamp(1, self.entries.len().max(1)) - 1;
+        self.selection_anchor = None;
+        self.save_state();
+        self.update_current_item_size();
+    }
+
+    fn enter_directory(&mut self) -> io::Result<()> {
+        if let Some(entry) = self.entries.get(self.cursor_index) {
+            if entry.is_dir {
+                self.enter_dir_path(entry.path.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn enter_dir_path(&mut self, dir: PathBuf) -> io::Result<()> {
+        let target = if self.config.skip_single_child_dirs {
+            Self::single_child_chain_end(&dir)
+        } else {
+            dir.clone()
+        };
+
+        // Going deeper from where a chain landed keeps it, so coming back and pressing Left still leaves it whole
+        let skipped = target != dir;
+        if skipped {
+            self.collapsed_chain = Some((dir.clone(), target.clone()));
+        }
+        self.current_dir = target;
+        self.load_directory()?;
+        if skipped {
+            let relative = self.current_dir.strip_prefix(&dir).unwrap_or(&self.current_dir);
+            self.show_status(format!("Skipped into {}/{}", dir.file_name().unwrap_or_default().to_string_lossy(), relative.display()));
+        }
+        Ok(())
+    }
+
+    // Deepest directory reached by following lone subdirectories, e.g. pkg/ -> pkg/pkg-1.0/
+    fn single_child_chain_end(dir: &Path) -> PathBuf {
+        let mut dir = dir.to_path_buf();
+        for _ in 0..64 {
+            let Ok(mut children) = fs::read_dir(&dir) else {
+                break;
+            };
+            let (Some(Ok(only)), None) = (children.next(), children.next()) else {
+                break;
+            };
+            // file_type does not follow symlinks, so a linked directory stops the chain
+            if !only.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                break;
+            }
+            dir = only.path();
+        }
+        dir
+    }
+
+    fn open_file(&mut self, path: &PathBuf) -> io::Result<()> {
+        self.log(&format!("open {}", path.display()));
+        let path_str = path.to_str().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
+        })?;
+
+        if cfg!(target_os = "windows") {
+            Command::new("cmd")
+                .args(&["/c", "start", "", path_str])
+                .spawn()?;
+        } else if cfg!(target_os = "macos") {
+            Command::new("open")
+                .arg(path_str)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()?;
+        } else {
+            // Linux - use sh -c with setsid and full redirection to suppress all output
+            let command = format!("setsid -f xdg-open '{}' >/dev/null 2>&1 &", path_str);
+            Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()?;
+        }
+
+        Ok(())
+    }
+
+    // Program and arguments from a configured command, "{}" standing for the path or the path appended
+    fn template_command(template: &str, path: &Path) -> Option<Command> {
+        let path_str = path.to_string_lossy();
+        let mut parts = template.split_whitespace();
+        let mut command = Command::new(parts.next()?);
+        let mut has_placeholder = false;
+        for part in parts {
+            if part.contains("{}") {
+                has_placeholder = true;
+                command.arg(part.replace("{}", &path_str));
+            } else {
+                command.arg(part);
+            }
+        }
+        if !has_placeholder {
+            command.arg(path);
+        }
+        Some(command)
+    }
+
+    fn open_with_configured(&mut self, path: &Path, name: &str) -> Option<io::Result<()>> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        let opener = self.config.openers.get(&extension)?.clone();
+        let Some(mut command) = Self::template_command(&opener.command, path) else {
+            return Some(Err(io::Error::new(io::ErrorKind::InvalidInput, "Empty opener command")));
+        };
+
+        if opener.foreground {
+            // run_app hands the terminal over once the current key has been handled
+            self.foreground_command = Some(command);
+            return Some(Ok(()));
+        }
+
+        use std::os::unix::process::CommandExt;
+        let result = command
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .process_group(0) // Keep terminal signals (Ctrl+C) away from the viewer
+            .spawn()
+            .map(|_| self.show_status(format!("Opening '{}' with {}", name, opener.command)));
+        Some(result)
+    }
+
+    fn open_or_enter(&mut self) -> io::Result<()> {
+        if let Some(entry) = self.entries.get(self.cursor_index) {
+            if let Some(kind) = entry.special {
+                // A viewer reading a pipe or device would never finish
+                self.show_status(format!("'{}' is a {}; not opening it", entry.name, kind.label()));
+            } else if entry.is_dir {
+                match self.config.enter_on_dir {
+                    DirEnterAction::Expand if self.inline_tree => {
+                        let path = entry.path.clone();
+                        if !self.expanded_dirs.remove(&path) {
+                            self.expanded_dirs.insert(path);
+                        }
+                        self.reload_keeping_position()?;
+                    }
+                    DirEnterAction::Open => {
+                        let path = entry.path.clone();
+                        let name = entry.name.clone();
+                        match self.open_file(&path) {
+                            Ok(()) => self.show_status(format!("Opening '{}' in the file manager", name)),
+                            Err(e) => self.show_status(format!("Failed to open directory: {}", e)),
+                        }
+                    }
+                    _ => {
+                        self.enter_dir_path(entry.path.clone())?;
+                    }
+                }
+            } else {
+                let path = entry.path.clone();
+                let name = entry.name.clone();
+                // A configured program for the extension takes precedence over the OS default
+                if let Some(result) = self.open_with_configured(&path, &name) {
+                    if let Err(e) = result {
+                        self.show_status(format!("Failed to open file: {}", e));
+                    }
+                } else if let Err(e) = self.open_file(&path) {
+                    self.show_status(format!("Failed to open file: {}", e));
+                } else {
+                    self.show_status(format!("Opening '{}'", name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn cycle_time_field(&mut self) -> io::Result<()> {
+        self.time_field = self.time_field.next();
+        self.show_status(format!("Date column: {} time", self.time_field.label()));
+        self.reload_keeping_position()
+    }
+
+    fn toggle_view_mode(&mut self) {
+        self.view_mode = self.view_mode.next();
+        self.show_status(format!("View: {}", self.view_mode.label()));
+    }
+
+    fn cycle_density(&mut self) {
+        self.density = self.density.next();
+        self.show_status(format!("Density: {}", self.density.label()));
+    }
+
+    // The character ls -F appends to show an entry's type
+    fn type_indicator(entry: &DirEntry) -> &'static str {
+        if entry.is_symlink {
+            "@"
+        } else if let Some(kind) = entry.special {
+            kind.glyph()
+        } else if entry.is_dir {
+            "/"
+        } else if entry.permissions & 0o111 != 0 {
+            "*"
+        } else {
+            ""
+        }
+    }
+
+    fn toggle_type_indicators(&mut self) {
+        self.type_indicators = !self.type_indicators;
+        let state = if self.type_indicators { "on" } else { "off" };
+        self.show_status(format!("Type indicators: {}", state));
+    }
+
+    fn toggle_split_directories(&mut self) {
+        self.split_directories = !self.split_directories;
+        let state = if self.split_directories { "on" } else { "off" };
+        self.show_status(format!("Pinned directories: {}", state));
+    }
+
+    fn toggle_inline_tree(&mut self) -> io::Result<()> {
+        self.inline_tree = !self.inline_tree;
+        let state = if self.inline_tree { "on" } else { "off" };
+        self.show_status(format!("Inline tree: {}", state));
+        self.reload_keeping_position()
+    }
+
+    // Reload after expanding or collapsing, following the cursor and selection by path since indices shift
+    fn reload_keeping_position(&mut self) -> io::Result<()> {
+        let cursor_path = self.entries.get(self.cursor_index).map(|entry| entry.path.clone());
+        let selected_paths: HashSet<PathBuf> = self.selected_indices.iter()
+            .filter_map(|&i| self.entries.get(i))
+            .map(|entry| entry.path.clone())
+            .collect();
+
+        self.load_directory()?;
+
+        if let Some(index) = cursor_path.and_then(|path| self.entries.iter().position(|entry| entry.path == path)) {
+            self.cursor_index = index;
+        }
+        self.selected_indices = self.entries.iter()
+            .enumerate()
+            .filter(|(_, entry)| selected_paths.contains(&entry.path))
+            .map(|(i, _)| i)
+            .collect();
+        self.save_state();
+        self.update_current_item_size();
+        Ok(())
+    }
+
+    fn tree_expand(&mut self) -> io::Result<()> {
+        if !self.inline_tree {
+            return self.enter_directory();
+        }
+        let Some(entry) = self.entries.get(self.cursor_index) else {
+            return Ok(());
+        };
+        if !entry.is_dir {
+            return Ok(());
+        }
+
+        if self.expanded_dirs.insert(entry.path.clone()) {
+            self.reload_keeping_position()
+        } else {
+            // Already open: step onto its first child
+            let depth = entry.depth;
+            if self.entries.get(self.cursor_index + 1).is_some_and(|next| next.depth > depth) {
+                self.cursor_index += 1;
+                self.selection_anchor = None;
+                self.update_current_item_size();
+            }
+            Ok(())
+        }
+    }
+
+    fn tree_collapse(&mut self) -> io::Result<()> {
+        if !self.inline_tree {
+            return self.go_to_parent();
+        }
+        let Some(entry) = self.entries.get(self.cursor_index) else {
+            return self.go_to_parent();
+        };
+
+        if entry.is_dir && self.expanded_dirs.remove(&entry.path) {
+            self.reload_keeping_position()
+        } else if entry.depth > 0 {
+            // Step back onto the directory this entry was expanded from
+            let depth = entry.depth;
+            if let Some(parent_index) = self.entries[..self.cursor_index].iter().rposition(|e| e.depth < depth) {
+                self.cursor_index = parent_index;
+                self.selection_anchor = None;
+                self.update_current_item_size();
+            }
+            Ok(())
+        } else {
+            self.go_to_parent()
+        }
+    }
+
+    fn go_to_parent(&mut self) -> io::Result<()> {
+        // Back out of a skipped chain in one step, onto the directory that was entered
+        if let Some((entered, landed)) = &self.collapsed_chain
+            && *landed == self.current_dir
+        {
+            self.current_dir = entered.clone();
+            self.collapsed_chain = None;
+        }
+        if let Some(parent) = self.current_dir.parent() {
+            let current_dir_name = self.current_dir.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string();
+
+            self.current_dir = parent.to_path_buf();
+            self.load_directory()?;
+
+            for (i, entry) in self.entries.iter().enumerate() {
+                if entry.name == current_dir_name {
+                    self.cursor_index = i;
+                    self.save_state();
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn marks_path() -> Option<PathBuf> {
+        Config::state_dir().map(|dir| dir.join("marks"))
+    }
+
+    fn load_marks() -> HashMap<char, PathBuf> {
+        // One "<letter> <path>" per line
+        let contents = Self::marks_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        contents.lines()
+            .filter_map(|line| {
+                let mut chars = line.chars();
+                let letter = chars.next()?;
+                let path = chars.as_str().strip_prefix(' ')?;
+                Some((letter, PathBuf::from(path)))
+            })
+            .collect()
+    }
+
+    fn save_marks(&self) -> io::Result<()> {
+        let Some(path) = Self::marks_path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut letters: Vec<&char> = self.marks.keys().collect();
+        letters.sort();
+        let contents: String = letters.into_iter()
+            .map(|letter| format!("{} {}\n", letter, self.marks[letter].display()))
+            .collect();
+        fs::write(path, contents)
+    }
+
+    fn last_dir_path() -> Option<PathBuf> {
+        Config::state_dir().map(|dir| dir.join("last_dir"))
+    }
+
+    fn load_last_dir() -> Option<PathBuf> {
+        let contents = fs::read_to_string(Self::last_dir_path()?).ok()?;
+        let dir = PathBuf::from(contents.trim_end_matches('\n'));
+        dir.is_dir().then_some(dir)
+    }
+
+    // Called on quit; a failure is only logged since the UI is about to go away
+    fn save_last_dir(&mut self) {
+        if !self.config.restore_last_dir {
+            return;
+        }
+        let Some(path) = Self::last_dir_path() else {
+            return;
+        };
+        let result = path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, format!("{}\n", self.current_dir.display())));
+        if let Err(e) = result {
+            self.log(&format!("saving last directory failed: {}", e));
+        }
+    }
+
+    // Next to config.toml, since pins are settings the user may want to edit or share
+    fn pinned_path() -> Option<PathBuf> {
+        Config::config_path().and_then(|path| path.parent().map(|dir| dir.join("pinned")))
+    }
+
+    fn load_pinned() -> HashSet<PathBuf> {
+        // One absolute path per line
+        Self::pinned_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| line.starts_with('/'))
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    fn save_pinned(&self) -> io::Result<()> {
+        let Some(path) = Self::pinned_path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut paths: Vec<&PathBuf> = self.pinned.iter().collect();
+        paths.sort();
+        let contents: String = paths.into_iter().map(|path| format!("{}\n", path.display())).collect();
+        fs::write(path, contents)
+    }
+
+    fn toggle_pin(&mut self) -> io::Result<()> {
+        let Some(entry) = self.entries.get(self.cursor_index) else {
+            return Ok(());
+        };
+        let path = entry.path.clone();
+        let name = entry.name.clone();
+        let pinned = if self.pinned.remove(&path) {
+            false
+        } else {
+            self.pinned.insert(path);
+            true
+        };
+
+        // Re-sort so the item moves to (or leaves) the top, keeping the cursor on it
+        self.reload_keeping_position()?;
+        match self.save_pinned() {
+            Err(e) => self.show_status(format!("Pin changed, but saving pins failed: {}", e)),
+            Ok(()) if pinned => self.show_status(format!("Pinned '{}'", name)),
+            Ok(()) => self.show_status(format!("Unpinned '{}'", name)),
+        }
+        Ok(())
+    }
+
+    fn set_mark(&mut self, letter: char) {
+        self.marks.insert(letter, self.current_dir.clone());
+        if self.config.persist_marks
+            && let Err(e) = self.save_marks()
+        {
+            self.show_status(format!("Mark '{}' set, but saving marks failed: {}", letter, e));
+            return;
+        }
+        self.show_status(format!("Mark '{}' set to {}", letter, self.current_dir.display()));
+    }
+
+    fn jump_to_mark(&mut self, letter: char) -> io::Result<()> {
+        match self.marks.get(&letter).cloned() {
+            Some(dir) if dir.is_dir() => {
+                self.save_state();
+                self.current_dir = dir;
+                self.load_directory()?;
+            }
+            Some(dir) => {
+                self.show_status(format!("Mark '{}' points to a missing directory: {}", letter, dir.display()));
+            }
+            None => {
+                self.show_status(format!("Mark '{}' is not set", letter));
+            }
+        }
+        Ok(())
+    }
+
+    fn go_to_git_root(&mut self) -> io::Result<()> {
+        // Nearest ancestor (or the current directory itself) containing .git; a .git file marks worktrees and submodules
+        let repo_root = self.get_ancestors()
+            .into_iter()
+            .rev()
+            .find(|dir| dir.join(".git").exists());
+
+        match repo_root {
+            Some(root) if root == self.current_dir => {
+                self.show_status("Already at the repository root".to_string());
+            }
+            Some(root) => {
+                self.save_state();
+                self.current_dir = root;
+                self.load_directory()?;
+                self.show_status(format!("Repository root: {}", self.current_dir.display()));
+            }
+            None => {
+                self.show_status("Not inside a git repository".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn go_to_trash(&mut self) -> io::Result<()> {
+        if self.current_dir == self.trash_dir {
+            self.show_status("Already in the trash directory".to_string());
+            return Ok(());
+        }
+        self.save_state();
+        self.current_dir = self.trash_dir.clone();
+        self.load_directory()?;
+        self.show_status(format!("Trash: {}", self.current_dir.display()));
+        Ok(())
+    }
+
+    // Splits a trash entry name back into the delete time and original name, see trash_path_in
+    fn decode_trash_name(name: &str) -> Option<(SystemTime, &str)> {
+        let (secs, original) = name.split_once('_')?;
+        let secs = secs.parse::<u64>().ok()?;
+        (!original.is_empty()).then_some((SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs), original))
+    }
+
+    fn go_to_clipboard_path(&mut self) -> io::Result<()> {
+        let text = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => text,
+            Err(_) => {
+                self.show_status("Clipboard does not contain text".to_string());
+                return Ok(());
+            }
+        };
+
+        // Accept a plain path, ~/path, or a file:// URI; only the first line counts
+        let line = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+        let path = if line.starts_with("file://") {
+            Self::file_uri_to_path(line)
+        } else if let Some(rest) = line.strip_prefix('~') {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(rest.trim_start_matches('/')))
+        } else if line.is_empty() {
+            None
+        } else {
+            Some(self.current_dir.join(line))
+        };
+
+        let Some(path) = path.filter(|path| path.exists()) else {
+            self.show_status(format!("Clipboard is not an existing path: {}", Self::truncate_to_width(line, 60)));
+            return Ok(());
+        };
+        self.reveal_path(path)
+    }
+
+    // Enters a directory, or lists a file's directory with the cursor on it
+    fn reveal_path(&mut self, path: PathBuf) -> io::Result<()> {
+        self.save_state();
+        if path.is_dir() {
+            self.current_dir = path;
+            self.load_directory()?;
+        } else if let Some(parent) = path.parent() {
+            self.current_dir = parent.to_path_buf();
+            self.load_directory()?;
+            match self.entries.iter().position(|entry| entry.path == path) {
+                Some(index) => {
+                    self.cursor_index = index;
+                    self.save_state();
+                }
+                None => {
+                    self.show_status(format!("{} is hidden by the current filters", path.display()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_mouse_down(&mut self, row: u16, _col: u16, modifiers: KeyModifiers, area_top: u16) {
+        let tree_lines = self.build_tree_lines(self.terminal_width);
+        let clicked_line = self.line_at_row(row, area_top);
+
+        if clicked_line < tree_lines.len() {
+            if let Some(entry_index) = tree_lines[clicked_line].entry_index {
+                if modifiers.contains(KeyModifiers::CONTROL) {
+                    // Ctrl+click: toggle individual item
+                    self.cursor_index = entry_index;
+                    self.toggle_selection();
+                    self.update_current_item_size();
+                } else if modifiers.contains(KeyModifiers::SHIFT) {
+                    // Shift+click: select from the anchor (or cursor) to the clicked item
+                    let anchor = self.selection_anchor.unwrap_or(self.cursor_index);
+                    self.selection_anchor = Some(anchor);
+                    self.cursor_index = entry_index;
+                    self.drag_selection = Some(anchor); // Dragging on from here keeps extending the range
+                    self.update_selection_range();
+                    self.save_state();
+                    self.update_current_item_size();
+                    self.cache_selected_sizes();
+                } else {
+                    // Regular click: start drag selection
+                    self.cursor_index = entry_index;
+                    self.drag_selection = Some(entry_index);
+                    self.selected_indices.clear();
+                    self.selected_indices.insert(entry_index);
+                    self.selection_anchor = Some(entry_index);
+                    self.save_state();
+                    self.update_current_item_size();
+                }
+            }
+        }
+    }
+
+    fn handle_mouse_drag(&mut self, row: u16, _col: u16, area_top: u16) {
+        if self.drag_selection.is_none() {
+            return;
+        }
+
+        let tree_lines = self.build_tree_lines(self.terminal_width);
+        let dragged_line = self.line_at_row(row, area_top);
+
+        if dragged_line < tree_lines.len() {
+            if let Some(entry_index) = tree_lines[dragged_line].entry_index {
+                self.cursor_index = entry_index;
+                self.update_selection_range();
+                self.save_state();
+                self.update_current_item_size();
+                self.cache_selected_sizes();
+                // Drop any lingering message so the live count/size is visible while dragging
+                self.status_message = None;
+            }
+        }
+    }
+
+    fn handle_mouse_up(&mut self) {
+        self.drag_selection = None;
+    }
+
+    fn open_context_menu(&mut self, row: u16, column: u16, area_top: u16) {
+        let tree_lines = self.build_tree_lines(self.terminal_width);
+        let Some(entry_index) = tree_lines.get(self.line_at_row(row, area_top)).and_then(|line| line.entry_index) else {
+            return;
+        };
+        // Like other file managers: right-clicking outside the selection acts on that item alone
+        if !self.selected_indices.contains(&entry_index) {
+            self.selected_indices.clear();
+            self.selection_anchor = None;
+        }
+        self.cursor_index = entry_index;
+        self.save_state();
+        self.update_current_item_size();
+        self.ui_mode = UIMode::ContextMenu { row, column, selected: 0 };
+    }
+
+    // Below and right of the click, moved back inside the screen near the edges
+    fn context_menu_area(row: u16, column: u16, screen: Rect) -> Rect {
+        let width = ContextAction::ALL.iter().map(|action| action.label().len() as u16).max().unwrap_or(0) + 4;
+        let height = ContextAction::ALL.len() as u16 + 1; // Title line
+        let x = column.min(screen.width.saturating_sub(width));
+        let y = (row + 1).min(screen.height.saturating_sub(height));
+        Rect::new(x, y, width.min(screen.width), height.min(screen.height))
+    }
+
+    fn run_context_action(&mut self, action: ContextAction) -> io::Result<()> {
+        self.ui_mode = UIMode::Normal;
+        if self.read_only && action.modifies_files() {
+            self.refuse_in_read_only();
+            return Ok(());
+        }
+        match action {
+            ContextAction::Open => self.open_or_enter()?,
+            ContextAction::Copy => self.copy_selected(false),
+            ContextAction::Cut => self.cut_selected(false),
+            ContextAction::Rename => self.start_rename(),
+            ContextAction::Delete => self.delete_selected(false),
+            ContextAction::Properties => self.show_item_info(),
+        }
+        Ok(())
+    }
+
+    fn open_command_palette(&mut self) {
+        self.ui_mode = UIMode::CommandPalette {
+            query: String::new(),
+            filtered: PaletteCommand::ALL.to_vec(),
+            selected: 0,
+        };
+    }
+
+    // Commands whose name or key contains the query, ignoring case
+    fn filter_palette(query: &str) -> Vec<PaletteCommand> {
+        let query = query.to_lowercase();
+        PaletteCommand::ALL
+            .into_iter()
+            .filter(|command| {
+                command.label().to_lowercase().contains(&query) || command.key().to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    fn run_palette_command(&mut self, command: PaletteCommand) -> io::Result<()> {
+        self.ui_mode = UIMode::Normal;
+        if self.read_only && command.modifies_files() {
+            self.refuse_in_read_only();
+            return Ok(());
+        }
+        match command {
+            PaletteCommand::Open => self.open_or_enter()?,
+            PaletteCommand::GoToParent => self.go_to_parent()?,
+            PaletteCommand::Copy => self.copy_selected(false),
+            PaletteCommand::Cut => self.cut_selected(false),
+            PaletteCommand::Paste => self.paste()?,
+            PaletteCommand::PasteIntoCursorDir => self.paste_into_cursor_dir()?,
+            PaletteCommand::PasteIntoNewDir => self.start_paste_into_new_dir(),
+            PaletteCommand::CreateNew => self.start_create_new(),
+            PaletteCommand::CreateFromClipboard => self.start_create_from_clipboard(),
+            PaletteCommand::Rename => self.start_rename(),
+            PaletteCommand::SwapNames => self.swap_selected_names()?,
+            PaletteCommand::SanitizeNames => self.start_sanitize_names(),
+            // run_app opens the editor for these, since it holds the terminal
+            PaletteCommand::BulkRename | PaletteCommand::EditConfig => {}
+            PaletteCommand::Delete => self.delete_selected(false),
+            PaletteCommand::Undo => self.undo()?,
+            PaletteCommand::RestoreLastDelete => self.undo_last_delete()?,
+            PaletteCommand::ToggleExecutable => self.toggle_executable()?,
+            PaletteCommand::EditTags => self.start_edit_tag(),
+            PaletteCommand::SplitFile => self.start_split(),
+            PaletteCommand::JoinParts => {
+                if let Err(e) = self.join_parts() {
+                    self.show_status(format!("Join failed: {}", e));
+                }
+            }
+            PaletteCommand::CopyPath => self.copy_cursor_path(),
+            PaletteCommand::SelectAll => self.select_all_listed(),
+            PaletteCommand::SelectSameExtension => self.select_same_extension(),
+            PaletteCommand::ToggleSelection => self.toggle_selection(),
+            PaletteCommand::JumpToNumber => self.start_jump_to_number(),
+            // The next letter key completes these, as after typing m or '
+            PaletteCommand::SetMark => self.pending_operator = Some(('m', 1)),
+            PaletteCommand::JumpToMark => self.pending_operator = Some(('\'', 1)),
+            PaletteCommand::ItemInfo => self.show_item_info(),
+            PaletteCommand::ViewInPager => self.view_in_pager(),
+            PaletteCommand::TogglePin => self.toggle_pin()?,
+            PaletteCommand::FuzzyFind => self.start_fuzzy_find(),
+            PaletteCommand::RecentFiles => self.start_recent_files(),
+            PaletteCommand::GoToGitRoot => self.go_to_git_root()?,
+            PaletteCommand::GoToClipboardPath => self.go_to_clipboard_path()?,
+            PaletteCommand::GoToTrash => self.go_to_trash()?,
+            PaletteCommand::SizeScan => self.start_size_scan(),
+            PaletteCommand::ExportListing => self.start_export_listing(),
+            PaletteCommand::TmuxPanes => self.open_in_tmux_panes(),
+            PaletteCommand::ToggleMount => {
+                if let Err(e) = self.toggle_mount() {
+                    self.show_status(format!("Mount or unmount failed: {}", e));
+                }
+            }
+            PaletteCommand::ToggleQueueMode => self.toggle_queue_mode(),
+            PaletteCommand::RunQueue => self.run_queue()?,
+            PaletteCommand::SortMenu => self.open_sort_menu(),
+            PaletteCommand::ToggleHidden => self.toggle_hidden()?,
+            PaletteCommand::CycleTypeFilter => self.cycle_type_filter()?,
+            PaletteCommand::ChangedSince => self.toggle_changed_since()?,
+            PaletteCommand::Help => self.toggle_help(),
+            PaletteCommand::CycleTheme => self.cycle_theme(),
+            PaletteCommand::ToggleAgeColors => self.toggle_age_colors(),
+            PaletteCommand::ToggleTitleStats => self.toggle_title_stats(),
+            PaletteCommand::ToggleInlineTree => self.toggle_inline_tree()?,
+            PaletteCommand::MessageLog => self.toggle_message_log(),
+            PaletteCommand::CycleTimeField => self.cycle_time_field()?,
+            PaletteCommand::ToggleSplitDirectories => self.toggle_split_directories(),
+            PaletteCommand::CycleDensity => self.cycle_density(),
+            PaletteCommand::ToggleTypeIndicators => self.toggle_type_indicators(),
+            PaletteCommand::ToggleKeyHints => self.toggle_key_hints(),
+            PaletteCommand::ToggleViewMode => self.toggle_view_mode(),
+        }
+        Ok(())
+    }
+
+    // Middle-click pastes into the directory under the pointer, or the current one elsewhere
+    fn handle_middle_click(&mut self, row: u16, area_top: u16) -> io::Result<()> {
+        let tree_lines = self.build_tree_lines(self.terminal_width);
+        let clicked_dir = tree_lines.get(self.line_at_row(row, area_top))
+            .and_then(|line| line.entry_index)
+            .and_then(|index| self.entries.get(index))
+            .filter(|entry| entry.is_dir && entry.path.is_dir())
+            .map(|entry| entry.path.clone());
+        if self.read_only {
+            self.refuse_in_read_only();
+            return Ok(());
+        }
+        match clicked_dir {
+            Some(destination) => self.paste_into(destination),
+            None => self.paste(),
+        }
+    }
+
+    // Runs before the next frame, so a click arriving in between is mapped with the new width
+    fn handle_resize(&mut self, width: u16) {
+        self.terminal_width = width as usize;
+        // The rows under a drag in progress no longer hold the same lines
+        self.drag_selection = None;
+    }
+
+    fn copy_selected(&mut self, cursor_only: bool) {
+        let items = self.target_paths(cursor_only);
+        if !items.is_empty() {
+            Self::export_desktop_clipboard(&items);
+            self.clipboard = Some(Clipboard {
+                items,
+                operation: ClipboardOp::Copy,
+            });
+            self.show_status(format!("Copied {} item(s)", self.clipboard.as_ref().unwrap().items.len()));
+        }
+    }
+
+    // The trash and config directories, or a directory holding them, would break undo and settings if moved away
+    fn refuse_protected(&mut self, items: &[PathBuf]) -> bool {
+        let config_dir = Config::config_path().and_then(|path| path.parent().map(Path::to_path_buf));
+        let protected: Vec<&Path> = [Some(self.trash_dir.as_path()), config_dir.as_deref()].into_iter().flatten().collect();
+        let Some(item) = items.iter().find(|item| protected.iter().any(|dir| dir.starts_with(item))) else {
+            return false;
+        };
+        self.show_status(format!(
+            "Refusing to touch '{}': it holds rusty_files' own trash or config",
+            item.display()
+        ));
+        true
+    }
+
+    // An item replaced since it was listed (a directory now a file, a file now a symlink...) is not what the user chose
+    fn ensure_types_unchanged(&self, items: &[PathBuf]) -> io::Result<()> {
+        let kind = |is_symlink: bool, is_dir: bool| match (is_symlink, is_dir) {
+            (true, _) => "symlink",
+            (false, true) => "directory",
+            (false, false) => "file",
+        };
+        for item in items {
+            // Items from another listing (queued, clipboard) have nothing to compare with
+            let Some(entry) = self.entries.iter().find(|entry| &entry.path == item) else {
+                continue;
+            };
+            let Ok(metadata) = fs::symlink_metadata(item) else {
+                continue;
+            };
+            let listed = kind(entry.is_symlink, entry.is_dir);
+            let current = kind(metadata.file_type().is_symlink(), metadata.is_dir());
+            if listed != current {
+                return Err(io::Error::other(
+                    format!("'{}' was a {} when listed but is now a {}; nothing was done (Ctrl+L reloads)", entry.name, listed, current),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn cut_selected(&mut self, cursor_only: bool) {
+        let items = self.target_paths(cursor_only);
+        if self.refuse_protected(&items) {
+            return;
+        }
+        if !items.is_empty() {
+            Self::export_desktop_clipboard(&items);
+            self.clipboard = Some(Clipboard {
+                items,
+                operation: ClipboardOp::Cut,
+            });
+            self.show_status(format!("Cut {} item(s)", self.clipboard.as_ref().unwrap().items.len()));
+        }
+    }
+
+    fn path_to_file_uri(path: &Path) -> String {
+        use std::os::unix::ffi::OsStrExt;
+
+        // Percent-encode everything outside the unreserved set so any byte sequence round-trips
+        let mut uri = String::from("file://");
+        for &byte in path.as_os_str().as_bytes() {
+            if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+                uri.push(byte as char);
+            } else {
+                uri.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        uri
+    }
+
+    fn file_uri_to_path(uri: &str) -> Option<PathBuf> {
+        use std::os::unix::ffi::OsStringExt;
+
+        let rest = uri.strip_prefix("file://")?;
+        // Skip an optional host part ("file://localhost/...")
+        let encoded = &rest[rest.find('/')?..];
+
+        let bytes = encoded.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' && i + 2 < bytes.len() {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            } else {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+
+        Some(PathBuf::from(std::ffi::OsString::from_vec(decoded)))
+    }
+
+    fn export_desktop_clipboard(items: &[PathBuf]) {
+        // Offer the files as text/uri-list so file managers can paste them
+        let uri_list: String = items.iter()
+            .map(|p| format!("{}\r\n", Self::path_to_file_uri(p)))
+            .collect();
+
+        let helper = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            Some(("wl-copy", vec!["--type", "text/uri-list"]))
+        } else if std::env::var_os("DISPLAY").is_some() {
+            Some(("xclip", vec!["-selection", "clipboard", "-t", "text/uri-list", "-i"]))
+        } else {
+            None
+        };
+
+        if let Some((program, args)) = helper {
+            let exported = Command::new(program)
+                .args(&args)
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .and_then(|mut child| {
+                    if let Some(mut stdin) = child.stdin.take() {
+                        stdin.write_all(uri_list.as_bytes())?;
+                    }
+                    child.wait()
+                })
+                .map(|status| status.success())
+                .unwrap_or(false);
+
+            if exported {
+                return;
+            }
+        }
+
+        // No helper available: plain text is still useful for pasting paths elsewhere
+        if let Ok(mut clipboard) = arboard::Clipboard::new() {
+            let _ = clipboard.set_text(uri_list);
+        }
+    }
+
+    // Text copy for the rename buffer; without a clipboard server (e.g. over SSH) it lives only in text_clipboard
+    fn copy_text(&mut self, text: String) {
+        let copied = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text.clone()));
+        if copied.is_err() {
+            self.note_clipboard_unavailable();
+        }
+        self.text_clipboard = text;
+    }
+
+    fn paste_text(&mut self) -> String {
+        match arboard::Clipboard::new() {
+            // An empty or non-text system clipboard still leaves the internal copy to paste
+            Ok(mut clipboard) => clipboard.get_text().unwrap_or_else(|_| self.text_clipboard.clone()),
+            Err(_) => {
+                self.note_clipboard_unavailable();
+                self.text_clipboard.clone()
+            }
+        }
+    }
+
+    fn note_clipboard_unavailable(&mut self) {
+        if !self.clipboard_unavailable_noted {
+            self.clipboard_unavailable_noted = true;
+            self.show_status("System clipboard unavailable; copied text is only kept inside rusty_files".to_string());
+        }
+    }
+
+    fn read_desktop_clipboard() -> Option<Vec<PathBuf>> {
+        let helper_output = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            Command::new("wl-paste")
+                .args(["--no-newline", "--type", "text/uri-list"])
+                .stdin(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .output()
+                .ok()
+        } else if std::env::var_os("DISPLAY").is_some() {
+            Command::new("xclip")
+                .args(["-selection", "clipboard", "-t", "text/uri-list", "-o"])
+                .stdin(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .output()
+                .ok()
+        } else {
+            None
+        };
+
+        let text = match helper_output {
+            Some(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).to_string(),
+            _ => arboard::Clipboard::new().ok()?.get_text().ok()?,
+        };
+
+        // Only accept the clipboard if every line is a file URI pointing at something that exists
+        let mut paths = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let path = Self::file_uri_to_path(line)?;
+            if !path.exists() {
+                return None;
+            }
+            paths.push(path);
+        }
+
+        if paths.is_empty() { None } else { Some(paths) }
+    }
+
+    fn sync_from_desktop_clipboard(&mut self) {
+        // Files copied in another application take precedence over our own clipboard
+        if let Some(items) = Self::read_desktop_clipboard() {
+            let is_ours = self.clipboard.as_ref().is_some_and(|c| c.items == items);
+            if !is_ours {
+                self.clipboard = Some(Clipboard {
+                    items,
+                    operation: ClipboardOp::Copy,
+                });
+            }
+        }
+    }
+
+    fn paste(&mut self) -> io::Result<()> {
+        let destination = self.current_dir.clone();
+        self.paste_into(destination)
+    }
+
+    fn paste_into_cursor_dir(&mut self) -> io::Result<()> {
+        match self.entries.get(self.cursor_index) {
+            Some(entry) if entry.is_dir && entry.path.is_dir() => {
+                let destination = entry.path.clone();
+                self.paste_into(destination)
+            }
+            _ => {
+                self.show_status("Cursor is not on a directory".to_string());
+                Ok(())
+            }
+        }
+    }
+
+    fn paste_into(&mut self, destination: PathBuf) -> io::Result<()> {
+        self.sync_from_desktop_clipboard();
+
+        let Some(clipboard) = &self.clipboard else {
+            return Ok(());
+        };
+        let items = clipboard.items.clone();
+        let is_move = matches!(clipboard.operation, ClipboardOp::Cut);
+
+        if self.queue_mode {
+            // A queued cut is consumed now, so the same items can't be queued twice
+            if is_move {
+                self.clipboard = None;
+            }
+            self.enqueue(QueuedOperation::Paste { items, destination, is_move });
+            return Ok(());
+        }
+        if self.config.confirm_batch_conflicts {
+            // A copy into its own directory is always numbered, so it isn't a conflict
+            let conflicts: Vec<PathBuf> = items.iter()
+                .filter_map(|item| item.file_name().map(|name| (item, destination.join(name))))
+                .filter(|(item, target)| target != *item && fs::symlink_metadata(target).is_ok())
+                .map(|(_, target)| target)
+                .collect();
+            if !conflicts.is_empty() {
+                let batch = ConflictBatch::Paste { items, destination, is_move };
+                self.ui_mode = UIMode::ConfirmBatchConflicts { batch, conflicts, choices: Vec::new(), deciding: false };
+                return Ok(());
+            }
+        }
+        let threshold = self.config.confirm_paste_items;
+        if threshold > 0 && items.len() >= threshold {
+            self.ui_mode = UIMode::ConfirmPaste { items, destination, is_move, new_dir: false, scroll: 0 };
+            return Ok(());
+        }
+        self.paste_items(items, is_move, destination)?;
+        Ok(())
+    }
+
+    fn is_directly_in(item: &Path, directory: &Path) -> bool {
+        let Some(parent) = item.parent() else {
+            return false;
+        };
+        // Compare resolved paths too, so a symlinked or relative spelling of the same directory counts
+        parent == directory
+            || matches!((fs::canonicalize(parent), fs::canonicalize(directory)), (Ok(a), Ok(b)) if a == b)
+    }
+
+    // Drop clipboard items moved or deleted since they were copied or cut, so the indicator stays truthful
+    fn prune_clipboard(&mut self) {
+        let Some(clipboard) = &mut self.clipboard else {
+            return;
+        };
+        let before = clipboard.items.len();
+        clipboard.items.retain(|item| fs::symlink_metadata(item).is_ok());
+        let dropped = before - clipboard.items.len();
+        if dropped == 0 {
+            return;
+        }
+
+        let note = if clipboard.items.is_empty() {
+            self.clipboard = None;
+            "Clipboard cleared: its items no longer exist".to_string()
+        } else {
+            format!("Dropped {} clipboard item(s) that no longer exist", dropped)
+        };
+        match self.status_message.take() {
+            Some(status) => self.amend_status(format!("{}. {}", status, note)),
+            None => self.show_status(note),
+        }
+    }
+
+    // Forget a cut once its items have been moved; a newer clipboard is left alone
+    fn forget_cut_items(&mut self, items: &[PathBuf]) {
+        if let Some(Clipboard { items: cut, operation: ClipboardOp::Cut }) = &self.clipboard
+            && cut.iter().any(|item| items.contains(item))
+        {
+            self.clipboard = None;
+        }
+    }
+
+    fn paste_items(&mut self, all_items: Vec<PathBuf>, is_move: bool, destination: PathBuf) -> io::Result<OperationOutcome> {
+        self.log(&format!(
+            "{} {} item(s) to {}: {:?}",
+            if is_move { "move" } else { "copy" },
+            all_items.len(),
+            destination.display(),
+            all_items
+        ));
+        {
+            // Sources may have been deleted or moved elsewhere since they were copied or cut
+            let (items, missing): (Vec<PathBuf>, Vec<PathBuf>) = all_items.iter()
+                .cloned()
+                .partition(|item| fs::symlink_metadata(item).is_ok());
+            if items.is_empty() {
+                self.show_status(format!("Nothing to paste: {} clipboard item(s) no longer exist", missing.len()));
+                if is_move {
+                    self.forget_cut_items(&all_items);
+                }
+                return Ok(OperationOutcome::Skipped);
+            }
+
+            if is_move && self.refuse_protected(&items) {
+                return Ok(OperationOutcome::Failed);
+            }
+
+            // Moving an item into the directory it is already in would only renumber it to "name (1)"
+            let (in_place, items): (Vec<PathBuf>, Vec<PathBuf>) = items.into_iter()
+                .partition(|item| is_move && Self::is_directly_in(item, &destination));
+            if items.is_empty() {
+                self.show_status(format!(
+                    "Nothing moved: the {} cut item(s) are already in '{}'; paste them into another directory",
+                    in_place.len(),
+                    destination.file_name().unwrap_or_default().to_string_lossy()
+                ));
+                return Ok(OperationOutcome::Skipped);
+            }
+
+            // rename can't cross filesystems; moving those items means copying, so ask first
+            if is_move {
+                let cross_device = Self::count_cross_device(&items, &destination);
+                let external = if self.config.confirm_external_moves {
+                    Self::external_filesystem(&destination)
+                } else {
+                    None
+                };
+                if cross_device > 0 || external.is_some() {
+                    self.ui_mode = UIMode::ConfirmCrossDeviceMove { items, destination, cross_device, external };
+                    return Ok(OperationOutcome::Deferred);
+                }
+            }
+
+            let mut mismatches = Vec::new();
+            let mut skipped = Vec::new();
+            let mut failed_item = None;
+            let outcome = match self.perform_file_operation_tracked(&items, &destination, is_move, &mut mismatches, &mut skipped, &mut failed_item) {
+                Ok((count, mut undo_action)) => {
+                    if is_move {
+                        self.forget_cut_items(&all_items);
+                    }
+
+                    // Extract actual pasted filenames from the undo action
+                    let pasted_names: Vec<String> = match &undo_action {
+                        UndoAction::Move { moved_files, .. } => {
+                            moved_files.iter()
+                                .filter_map(|(_, dest)| dest.file_name())
+                                .filter_map(|n| n.to_str())
+                                .map(|s| s.to_string())
+                                .collect()
+                        }
+                        UndoAction::Copy { copied_files } => {
+                            copied_files.iter()
+                                .filter_map(|p| p.file_name())
+                                .filter_map(|n| n.to_str())
+                                .map(|s| s.to_string())
+                                .collect()
+                        }
+                        _ => Vec::new(),
+                    };
+
+                    // A copy that failed verification is kept for inspection, but not as a paste that undo would treat as good
+                    let mut unverified = 0;
+                    if let UndoAction::Copy { copied_files } = &mut undo_action {
+                        let before = copied_files.len();
+                        copied_files.retain(|copy| !mismatches.iter().any(|mismatch| mismatch.starts_with(copy)));
+                        unverified = before - copied_files.len();
+                    }
+                    for path in undo_action.clone().paths() {
+                        self.forget_sizes(path);
+                    }
+                    self.forget_sizes(&destination);
+                    if !matches!(&undo_action, UndoAction::Copy { copied_files } if copied_files.is_empty()) {
+                        self.undo_stack.push(undo_action);
+                    }
+                    // Ready to paste the same items again elsewhere; a cut's sources are gone anyway
+                    let keep_selection = !is_move && self.config.keep_selection_after_copy;
+                    if keep_selection {
+                        self.reload_keeping_position()?;
+                    } else {
+                        self.load_directory()?;
+                    }
+                    if destination == self.current_dir {
+                        self.show_status(format!("Pasted {} item(s)", count));
+                        if !keep_selection {
+                            self.select_items_by_name(&pasted_names);
+                        }
+                    } else if self.config.follow_paste {
+                        self.save_state();
+                        self.current_dir = destination.clone();
+                        self.load_directory()?;
+                        self.show_status(format!("Pasted {} item(s)", count));
+                        self.select_items_by_name(&pasted_names);
+                    } else {
+                        let dest_name = destination.file_name().unwrap_or_default().to_string_lossy();
+                        self.show_status(format!("Pasted {} item(s) into '{}'", count, dest_name));
+                    }
+
+                    if !missing.is_empty()
+                        && let Some(status) = self.status_message.take()
+                    {
+                        self.amend_status(format!("{}, skipped {} missing", status, missing.len()));
+                    }
+                    if !in_place.is_empty()
+                        && let Some(status) = self.status_message.take()
+                    {
+                        self.amend_status(format!("{}, left {} already in place", status, in_place.len()));
+                    }
+                    if !skipped.is_empty()
+                        && let Some(status) = self.status_message.take()
+                    {
+                        self.amend_status(format!("{}, skipped {} special file(s) (pipes, sockets, devices)", status, skipped.len()));
+                    }
+
+                    for mismatch in &mismatches {
+                        self.mark_failed(mismatch.clone(), "verification".to_string());
+                    }
+                    if let Some(first) = mismatches.first() {
+                        let status = self.status_message.take().unwrap_or_default();
+                        self.amend_status(format!(
+                            "{}; verification FAILED for {} file(s), e.g. '{}' ({} copied item(s) kept for inspection, not undoable)",
+                            status,
+                            mismatches.len(),
+                            first.display(),
+                            unverified
+                        ));
+                    } else if !is_move
+                        && self.config.verify_copies != CopyVerification::Off
+                        && let Some(status) = self.status_message.take()
+                    {
+                        self.amend_status(format!("{} (verified)", status));
+                    }
+                    if mismatches.is_empty() { OperationOutcome::Done } else { OperationOutcome::Failed }
+                }
+                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                    self.request_sudo(
+                        "Permission denied. Enter sudo password:".to_string(),
+                        PendingOperation {
+                            items,
+                            destination: Some(destination),
+                            operation: if is_move { OperationType::Move } else { OperationType::Copy },
+                            undo_action: None,
+                        },
+                    )?;
+                    OperationOutcome::Deferred
+                }
+                Err(e) => {
+                    // The destination is where the listing usually is after a paste; the source may be in view too
+                    match failed_item {
+                        Some((item, dest_path)) => {
+                            self.show_status(format!("Error: {} -> {}: {}", item.display(), dest_path.display(), e));
+                            self.mark_failed(item, e.to_string());
+                            self.mark_failed(dest_path, e.to_string());
+                        }
+                        None => self.show_status(format!("Error: {}", e)),
+                    }
+                    OperationOutcome::Failed
+                }
+            };
+            Ok(outcome)
+        }
+    }
+
+    fn start_create_new(&mut self) {
+        self.ui_mode = UIMode::CreateNew {
+            creation_type: None,
+            name: String::new(),
+        };
+    }
+
+    fn start_create_from_clipboard(&mut self) {
+        // Check up front so the user isn't asked for a name only to learn there's nothing to save
+        let has_text = arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .is_ok_and(|text| !text.is_empty());
+        if !has_text {
+            self.show_status("Clipboard does not contain text".to_string());
+            return;
+        }
+        self.ui_mode = UIMode::CreateNew {
+            creation_type: Some(CreationType::ClipboardText),
+            name: String::new(),
+        };
+    }
+
+    fn start_paste_into_new_dir(&mut self) {
+        self.sync_from_desktop_clipboard();
+        if self.clipboard.is_none() {
+            self.show_status("Nothing to paste".to_string());
+            return;
+        }
+        self.ui_mode = UIMode::CreateNew {
+            creation_type: Some(CreationType::PasteFolder),
+            name: String::new(),
+        };
+    }
+
+    fn paste_into_new_dir(&mut self, directory: PathBuf) -> io::Result<()> {
+        let Some(clipboard) = &self.clipboard else {
+            return Ok(());
+        };
+        let items = clipboard.items.clone();
+        let is_move = matches!(clipboard.operation, ClipboardOp::Cut);
+
+        // Same checks as paste_into, minus conflicts: nothing can be in a directory that doesn't exist yet
+        if self.queue_mode {
+            if is_move {
+                self.clipboard = None;
+            }
+            self.enqueue(QueuedOperation::PasteIntoNewDir { items, directory, is_move });
+            return Ok(());
+        }
+        let threshold = self.config.confirm_paste_items;
+        if threshold > 0 && items.len() >= threshold {
+            self.ui_mode = UIMode::ConfirmPaste { items, destination: directory, is_move, new_dir: true, scroll: 0 };
+            return Ok(());
+        }
+        self.create_dir_and_paste(items, is_move, directory)?;
+        Ok(())
+    }
+
+    fn create_dir_and_paste(&mut self, items: Vec<PathBuf>, is_move: bool, directory: PathBuf) -> io::Result<OperationOutcome> {
+        fs::create_dir(&directory)?;
+        let depth = self.undo_stack.len();
+        let pasted = self.paste_items(items, is_move, directory.clone());
+        // Nothing landed and nothing is waiting on a prompt: don't leave an empty directory that undo can't reach
+        if (pasted.is_err() || self.undo_stack.len() == depth)
+            && matches!(self.ui_mode, UIMode::Normal)
+            && fs::remove_dir(&directory).is_ok()
+        {
+            let outcome = pasted?;
+            self.reload_keeping_position()?;
+            return Ok(outcome);
+        }
+        let outcome = pasted?;
+
+        // One Ctrl+Z takes back both the paste and the new directory
+        if self.undo_stack.len() > depth
+            && let Some(paste) = self.undo_stack.pop()
+        {
+            self.undo_stack.push(UndoAction::PasteIntoNewDir { directory: directory.clone(), paste: Box::new(paste) });
+        }
+
+        // Stay put while a password or confirmation prompt finishes the paste
+        if matches!(self.ui_mode, UIMode::Normal) && self.current_dir != directory {
+            self.save_state();
+            self.current_dir = directory;
+            self.load_directory()?;
+        }
+        Ok(outcome)
+    }
+
+    fn create_new_item(&mut self, creation_type: CreationType, name: String) -> io::Result<()> {
+        self.log(&format!("create {:?} {:?} in {}", creation_type, name, self.current_dir.display()));
+        if name.is_empty() {
+            self.show_status("Name cannot be empty".to_string());
+            return Ok(());
+        }
+
+        let new_path = self.current_dir.join(&name);
+
+        // symlink_metadata also catches dangling links, which File::create would follow
+        if fs::symlink_metadata(&new_path).is_ok() {
+            self.show_status(format!("'{}' already exists", name));
+            return Ok(());
+        }
+
+        match creation_type {
+            CreationType::File => {
+                // Create file with default content based on extension
+                let mut file = fs::File::create(&new_path)?;
+
+                // Add default content based on file extension to ensure proper MIME type detection
+                let default_content = Self::get_default_file_content(&name);
+                if !default_content.is_empty() {
+                    file.write_all(default_content.as_bytes())?;
+                }
+
+                self.show_status(format!("Created file '{}'", name));
+
+                if self.config.edit_new_files {
+                    // run_app suspends the UI for the editor once this key is handled
+                    self.foreground_command = Some(Self::editor_command(&new_path));
+                }
+            }
+            CreationType::Directory => {
+                fs::create_dir(&new_path)?;
+                self.show_status(format!("Created directory '{}'", name));
+            }
+            CreationType::ClipboardText => {
+                let text = arboard::Clipboard::new()
+                    .and_then(|mut clipboard| clipboard.get_text())
+                    .map_err(|e| io::Error::other(format!("cannot read clipboard: {}", e)))?;
+                // create_new refuses to replace anything that appeared since the check above
+                let mut file = fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&new_path)?;
+                // The file is ours, so a failed write takes it away rather than leaving half the text
+                if let Err(e) = file.write_all(text.as_bytes()) {
+                    drop(file);
+                    let _ = fs::remove_file(&new_path);
+                    return Err(e);
+                }
+                self.show_status(format!("Wrote {} bytes from the clipboard to '{}'", text.len(), name));
+            }
+            CreationType::PasteFolder => return self.paste_into_new_dir(new_path),
+        }
+        if matches!(creation_type, CreationType::File | CreationType::ClipboardText) {
+            self.run_formatter(&new_path);
+        }
+
+        self.load_directory()?;
+
+        // Select the newly created item
+        self.select_items_by_name(&[name]);
+
+        Ok(())
+    }
+
+    // The [format_new] command for the new file's extension; the file is kept whatever the formatter does
+    fn run_formatter(&mut self, path: &Path) {
+        let Some(template) = path.extension()
+            .and_then(|extension| self.config.formatters.get(&extension.to_string_lossy().to_lowercase()))
+            .cloned()
+        else {
+            return;
+        };
+        let Some(mut command) = Self::template_command(&template, path) else {
+            return;
+        };
+
+        self.log(&format!("format {} with {}", path.display(), template));
+        let result = command
+            .current_dir(path.parent().unwrap_or(Path::new("/")))
+            .stdin(std::process::Stdio::null())
+            .output();
+        let failure = match result {
+            Ok(output) if output.status.success() => return,
+            Ok(output) => String::from_utf8_lossy(&output.stderr)
+                .lines()
+                .next()
+                .map(str::to_string)
+                .unwrap_or_else(|| output.status.to_string()),
+            Err(e) => e.to_string(),
+        };
+        if let Some(status) = self.status_message.take() {
+            self.amend_status(format!("{}, but `{}` failed: {}", status, template, failure));
+        }
+    }
+
+    fn get_default_file_content(filename: &str) -> String {
+        // Get file extension
+        let extension = if let Some(dot_pos) = filename.rfind('.') {
+            &filename[dot_pos + 1..]
+        } else {
+            return "\n".to_string(); // Default: just a newline for files without extension
+        };
+
+        // Return appropriate default content based on extension
+        match extension.to_lowercase().as_str() {
+            "py" => "#!/usr/bin/env python3\n".to_string(),
+            "sh" | "bash" => "#!/bin/bash\n".to_string(),
+            "rb" => "#!/usr/bin/env ruby\n".to_string(),
+            "pl" => "#!/usr/bin/env perl\n".to_string(),
+            "js" | "ts" | "jsx" | "tsx" => "// \n".to_string(),
+            "html" | "htm" => "<!DOCTYPE html>\n<html>\n<head>\n    <title></title>\n</head>\n<body>\n    \n</body>\n</html>\n".to_string(),
+            "css" => "/* */\n".to_string(),
+            "rs" => "fn main() {\n    \n}\n".to_string(),
+            "c" | "cpp" | "cc" | "h" | "hpp" => "// \n".to_string(),
+            "java" => "// \n".to_string(),
+            "go" => "package main\n\nfunc main() {\n    \n}\n".to_string(),
+            "md" | "markdown" => "# \n".to_string(),
+            _ => "\n".to_string(), // Default: just a newline
+        }
+    }
+
+    fn start_split(&mut self) {
+        match self.entries.get(self.cursor_index) {
+            Some(entry) if !entry.is_dir && entry.special.is_none() => {
+                self.ui_mode = UIMode::SplitFile {
+                    path: entry.path.clone(),
+                    chunk_size: String::new(),
+                };
+            }
+            _ => self.show_status("Cursor is not on a regular file".to_string()),
+        }
+    }
+
+    fn split_file(&mut self, path: &Path, chunk_size: &str) -> io::Result<()> {
+        use std::io::Read;
+
+        let Some(chunk_size) = Self::parse_file_size(chunk_size) else {
+            self.show_status(format!("Invalid chunk size '{}' (e.g. 4096, 512K, 100M, 2G)", chunk_size));
+            return Ok(());
+        };
+        let file_len = fs::metadata(path)?.len();
+        if file_len <= chunk_size {
+            self.show_status("File is not larger than the chunk size, nothing to split".to_string());
+            return Ok(());
+        }
+
+        // file.part001, file.part002, ...; wider numbers only when there are more than 999 parts
+        let part_count = file_len.div_ceil(chunk_size);
+        let digits = part_count.to_string().len().max(3);
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let parts: Vec<PathBuf> = (1..=part_count)
+            .map(|n| path.with_file_name(format!("{}.part{:0width$}", file_name, n, width = digits)))
+            .collect();
+        if let Some(existing) = parts.iter().find(|part| fs::symlink_metadata(part).is_ok()) {
+            self.show_status(format!("'{}' already exists, nothing split", existing.display()));
+            return Ok(());
+        }
+
+        let mut reader = fs::File::open(path)?;
+        let mut created = Vec::new();
+        let result = (|| -> io::Result<()> {
+            for part in &parts {
+                // Never truncate a part that appeared after the check above
+                let mut writer = fs::OpenOptions::new().write(true).create_new(true).open(part)?;
+                created.push(part.clone());
+                // Streams through a fixed buffer, however large the chunk
+                io::copy(&mut (&mut reader).take(chunk_size), &mut writer)?;
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            for part in &created {
+                let _ = fs::remove_file(part);
+            }
+            return Err(e);
+        }
+
+        self.undo_stack.push(UndoAction::Copy { copied_files: created });
+        self.show_status(format!(
+            "Split '{}' into {} part(s) of {}",
+            file_name,
+            part_count,
+            Self::format_file_size(chunk_size)
+        ));
+        self.load_directory()?;
+        let part_names: Vec<String> = parts.iter()
+            .filter_map(|part| part.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .collect();
+        self.select_items_by_name(&part_names);
+        Ok(())
+    }
+
+    // "name.part007" -> ("name", 7)
+    fn split_part_name(name: &str) -> Option<(&str, u64)> {
+        let (base, number) = name.rsplit_once(".part")?;
+        if base.is_empty() || number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some((base, number.parse().ok()?))
+    }
+
+    fn join_parts(&mut self) -> io::Result<()> {
+        let paths = self.get_selected_paths();
+        let mut parts: Vec<(u64, PathBuf)> = Vec::new();
+        let mut base_name: Option<String> = None;
+        for path in &paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let Some((base, number)) = Self::split_part_name(&name) else {
+                self.show_status(format!("'{}' is not a .partNNN file, nothing joined", name));
+                return Ok(());
+            };
+            match &base_name {
+                Some(existing) if existing != base => {
+                    self.show_status("Selected parts belong to different files, nothing joined".to_string());
+                    return Ok(());
+                }
+                _ => base_name = Some(base.to_string()),
+            }
+            parts.push((number, path.clone()));
+        }
+        let Some(base_name) = base_name else {
+            return Ok(());
+        };
+        parts.sort();
+
+        // A missing or doubled part would silently produce a corrupt file
+        if let Some(expected) = (1..).zip(&parts).find_map(|(expected, (number, _))| (*number != expected).then_some(expected)) {
+            self.show_status(format!(
+                "Selected parts are not numbered 1 to {} without gaps (part {} is missing or repeated), nothing joined",
+                parts.len(),
+                expected
+            ));
+            return Ok(());
+        }
+
+        let output = self.get_unique_path(&self.current_dir.join(&base_name));
+        // Only a file this created is removed again if a part can't be read
+        let mut writer = fs::OpenOptions::new().write(true).create_new(true).open(&output)?;
+        let result = (|| -> io::Result<u64> {
+            let mut total = 0;
+            for (_, part) in &parts {
+                total += io::copy(&mut fs::File::open(part)?, &mut writer)?;
+            }
+            Ok(total)
+        })();
+        let total = match result {
+            Ok(total) => total,
+            Err(e) => {
+                let _ = fs::remove_file(&output);
+                return Err(e);
+            }
+        };
+
+        let output_name = output.file_name().unwrap_or_default().to_string_lossy().to_string();
+        self.undo_stack.push(UndoAction::Copy { copied_files: vec![output] });
+        self.show_status(format!(
+            "Joined {} part(s) into '{}' ({})",
+            parts.len(),
+            output_name,
+            Self::format_file_size(total)
+        ));
+        self.selected_indices.clear();
+        self.load_directory()?;
+        self.select_items_by_name(&[output_name]);
+        Ok(())
+    }
+
+    fn start_rename(&mut self) {
+        if let Some(entry) = self.entries.get(self.cursor_index) {
+            let original_path = entry.path.clone();
+            let current_name = entry.name.clone();
+            if self.refuse_protected(std::slice::from_ref(&original_path)) {
+                return;
+            }
+
+            // Find the last dot to separate name from extension
+            // Select filename without extension
+            let cursor_pos = if let Some(dot_pos) = current_name.rfind('.') {
+                // Only treat as extension if dot is not at start (hidden files)
+                if dot_pos > 0 {
+                    dot_pos
+                } else {
+                    current_name.len()
+                }
+            } else {
+                current_name.len()
+            };
+
+            self.ui_mode = UIMode::RenameItem {
+                original_path,
+                new_name: current_name,
+                cursor_pos,
+                selection_start: Some(0), // Select from start to cursor (filename without extension)
+            };
+        }
+    }
+
+    fn editor_command(file: &Path) -> Command {
+        // $VISUAL, then $EDITOR, may carry arguments (e.g. "code -w")
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string());
+        let mut parts = editor.split_whitespace();
+        let mut command = Command::new(parts.next().unwrap_or("vi"));
+        command.args(parts).arg(file);
+        command
+    }
+
+    fn open_in_tmux_panes(&mut self) {
+        if std::env::var_os("TMUX").is_none() {
+            self.show_status("Not running inside tmux".to_string());
+            return;
+        }
+
+        // Directories get a shell there, files an editor in their directory; no selection means the current directory
+        let targets = if self.selected_indices.is_empty() {
+            vec![self.current_dir.clone()]
+        } else {
+            self.get_selected_paths()
+        };
+        let mut opened = 0;
+        for target in &targets {
+            let mut command = Command::new("tmux");
+            command.arg("split-window");
+            if target.is_dir() {
+                command.arg("-c").arg(target);
+            } else {
+                let editor = Self::editor_command(target);
+                command.arg("-c").arg(target.parent().unwrap_or(&self.current_dir));
+                command.arg(editor.get_program()).args(editor.get_args());
+            }
+            let result = command
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status();
+            match result {
+                Ok(status) if status.success() => opened += 1,
+                Ok(status) => {
+                    self.show_status(format!("tmux split-window exited with {}", status));
+                    return;
+                }
+                Err(e) => {
+                    self.show_status(format!("Failed to run tmux: {}", e));
+                    return;
+                }
+            }
+        }
+        self.show_status(format!("Opened {} tmux pane(s)", opened));
+    }
 
-        let mut entries = Vec::new();
-        if let Ok(read_dir) = fs::read_dir(&self.current_dir) {
-            for entry in read_dir.flatten() {
-                if let (Ok(name), Ok(metadata)) = (
-                    entry.file_name().into_string(),
-                    entry.metadata()
-                ) {
-                    // Skip hidden files/directories if show_hidden is false
-                    if !self.show_hidden && name.starts_with('.') {
-                        continue;
-                    }
+    fn pager_command(file: &Path) -> Command {
+        // $PAGER may carry arguments too (e.g. "less -R")
+        let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        let mut parts = pager.split_whitespace();
+        let mut command = Command::new(parts.next().unwrap_or("less"));
+        command.args(parts).arg(file);
+        command
+    }
 
-                    let path = entry.path();
-                    let is_dir = metadata.is_dir();
+    // No NUL bytes and valid UTF-8 in the first 8 KiB (a character cut off at the end is fine)
+    fn looks_like_text(path: &Path) -> io::Result<bool> {
+        use std::io::Read;
 
-                    // Get modified time
-                    let modified = if is_dir {
-                        // For directories, get max modified time from contents (depth limit 1)
-                        Self::get_dir_max_modified(&path, 1)
-                    } else {
-                        // For files, use the file's modified time
-                        metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)
-                    };
+        let mut buffer = Vec::with_capacity(8192);
+        fs::File::open(path)?.take(8192).read_to_end(&mut buffer)?;
+        if buffer.contains(&0) {
+            return Ok(false);
+        }
+        Ok(match std::str::from_utf8(&buffer) {
+            Ok(_) => true,
+            Err(e) => e.error_len().is_none(),
+        })
+    }
 
-                    // Get permissions
-                    let permissions = metadata.permissions().mode();
+    fn view_in_pager(&mut self) {
+        let Some(entry) = self.entries.get(self.cursor_index) else {
+            return;
+        };
+        if entry.is_dir || entry.special.is_some() {
+            self.show_status("Only files can be viewed in the pager".to_string());
+            return;
+        }
+        let path = entry.path.clone();
+        let name = entry.name.clone();
+
+        match Self::looks_like_text(&path) {
+            Ok(true) => {
+                self.log(&format!("view {}", path.display()));
+                // run_app suspends the UI for the pager once this key is handled
+                self.foreground_command = Some(Self::pager_command(&path));
+            }
+            Ok(false) => self.show_status(format!("'{}' looks binary; not opening it in the pager", name)),
+            Err(e) => self.show_status(format!("Cannot read '{}': {}", name, e)),
+        }
+    }
+
+    fn config_file_for_editing(&mut self) -> io::Result<Option<PathBuf>> {
+        let Some(path) = Config::config_path() else {
+            self.show_status("No config location: neither XDG_CONFIG_HOME nor HOME is set".to_string());
+            return Ok(None);
+        };
+        // The editor creates the file itself, but not the directory it goes in
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Some(path))
+    }
+
+    fn reload_config(&mut self, editor_status: io::Result<std::process::ExitStatus>) -> io::Result<()> {
+        if let Err(e) = editor_status {
+            self.show_status(format!("Failed to run editor: {}", e));
+            return Ok(());
+        }
+
+        let config = match Config::load() {
+            Ok(config) => config,
+            Err(e) => {
+                self.show_status(format!("Config error: {}; keeping the previous settings", e));
+                return Ok(());
+            }
+        };
+
+        // Settings that can also be toggled at runtime start over from the file
+        self.theme = config.theme;
+        self.age_colored_timestamps = config.age_colored_timestamps;
+        self.title_stats = config.title_stats;
+        self.inline_tree = config.inline_tree;
+        self.split_directories = config.split_directories;
+        self.type_indicators = config.type_indicators;
+        self.key_hints = config.key_hints;
+        self.density = config.density;
+        self.view_mode = config.view_mode;
+        self.dirs_position = config.dirs_position;
+        self.config = config;
+        self.show_status("Config reloaded".to_string());
+        self.reload_keeping_position()
+    }
+
+    fn write_bulk_rename_list(&mut self) -> io::Result<Option<(Vec<PathBuf>, PathBuf)>> {
+        // Selected items in listing order, or the item under the cursor
+        let mut indices: Vec<usize> = if self.selected_indices.is_empty() {
+            vec![self.cursor_index]
+        } else {
+            self.selected_indices.iter().copied().collect()
+        };
+        indices.sort_unstable();
+        let paths: Vec<PathBuf> = indices.iter().filter_map(|&i| self.entries.get(i)).map(|entry| entry.path.clone()).collect();
+
+        if paths.is_empty() || self.refuse_protected(&paths) {
+            return Ok(None);
+        }
+        let items: Vec<&DirEntry> = indices.iter().filter_map(|&i| self.entries.get(i)).collect();
+        if items.iter().any(|entry| entry.name.contains('\n')) {
+            self.show_status("Cannot bulk rename names containing line breaks".to_string());
+            return Ok(None);
+        }
+
+        use std::os::unix::fs::DirBuilderExt;
+
+        // A new directory only this user can enter, so nobody can plant a symlink where the list goes
+        let nanos = SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |elapsed| elapsed.subsec_nanos());
+        let list_dir = std::env::temp_dir().join(format!("rusty_files-rename-{}-{}", std::process::id(), nanos));
+        fs::DirBuilder::new().mode(0o700).create(&list_dir)?;
+        let list_path = list_dir.join("names.txt");
+        let contents: String = items.iter().map(|entry| format!("{}\n", entry.name)).collect();
+        let written = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&list_path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()));
+        if let Err(e) = written {
+            let _ = fs::remove_dir_all(&list_dir);
+            return Err(e);
+        }
+
+        Ok(Some((paths, list_path)))
+    }
+
+    fn apply_bulk_rename(&mut self, items: &[PathBuf], list_path: &Path, editor_status: io::Result<std::process::ExitStatus>) -> io::Result<()> {
+        let edited = fs::read_to_string(list_path);
+        // The whole private directory goes, along with any backup file the editor left in it
+        if let Some(list_dir) = list_path.parent() {
+            let _ = fs::remove_dir_all(list_dir);
+        }
+
+        match editor_status {
+            Ok(status) if status.success() => {}
+            Ok(_) => {
+                self.show_status("Editor exited with an error, nothing renamed".to_string());
+                return Ok(());
+            }
+            Err(e) => {
+                self.show_status(format!("Failed to start editor: {}", e));
+                return Ok(());
+            }
+        }
+
+        // Line N is the new name of item N; a blank line leaves the item alone
+        let edited = edited?;
+        let resolve_conflicts = self.config.bulk_rename_resolve_conflicts;
+        let new_names: Vec<&str> = edited.lines().collect();
+        if new_names.len() != items.len() {
+            self.show_status(format!(
+                "Line count changed ({} -> {}), nothing renamed",
+                items.len(),
+                new_names.len()
+            ));
+            return Ok(());
+        }
+
+        let confirm_conflicts = self.config.confirm_batch_conflicts;
+        let mut renames: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let mut conflicts = Vec::new();
+        for (original, new_name) in items.iter().zip(new_names) {
+            let new_name = new_name.trim_end_matches('\r');
+            // Compared lossily: a non-UTF-8 name comes back as the decoded text written to the list
+            if new_name.is_empty() || original.file_name().map(|n| n.to_string_lossy()).as_deref() == Some(new_name) {
+                continue;
+            }
+            if new_name.contains('/') || new_name == "." || new_name == ".." {
+                self.show_status(format!("Invalid name '{}', nothing renamed", new_name));
+                return Ok(());
+            }
 
-                    entries.push(DirEntry {
-                        path,
-                        name,
-                        is_dir,
-                        modified,
-                        permissions,
+            let target = original.with_file_name(new_name);
+            // Swaps and chains are refused so every rename (and its undo) lands on a free name,
+            // unless conflicts get numbered when the rename happens or are settled up front
+            let in_batch = renames.iter().any(|(_, other)| *other == target);
+            if confirm_conflicts && !in_batch && target.exists() && !items.contains(&target) {
+                conflicts.push(target.clone());
+            } else if !resolve_conflicts && (target.exists() || in_batch) {
+                self.show_status(format!("'{}' already exists, nothing renamed", new_name));
+                return Ok(());
+            }
+            renames.push((original.clone(), target));
+        }
+
+        if renames.is_empty() {
+            self.show_status("No names changed".to_string());
+            return Ok(());
+        }
+        if !conflicts.is_empty() {
+            let batch = ConflictBatch::BulkRename { renames };
+            self.ui_mode = UIMode::ConfirmBatchConflicts { batch, conflicts, choices: Vec::new(), deciding: false };
+            return Ok(());
+        }
+        self.perform_bulk_renames(renames, &HashMap::new())
+    }
+
+    fn perform_bulk_renames(&mut self, renames: Vec<(PathBuf, PathBuf)>, choices: &HashMap<PathBuf, ConflictChoice>) -> io::Result<()> {
+        let resolve_conflicts = self.config.bulk_rename_resolve_conflicts;
+        let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let mut trashed = Vec::new();
+        let mut resolved = 0;
+        let mut skipped = 0;
+        let mut error = None;
+        for (original, target) in renames {
+            let mut replacing = false;
+            // Checked per rename, so names freed or taken earlier in the batch count
+            let target = match choices.get(&target) {
+                Some(ConflictChoice::Skip) => {
+                    skipped += 1;
+                    continue;
+                }
+                Some(ConflictChoice::Overwrite) => match self.trash_conflict(&target) {
+                    Ok(pair) => {
+                        trashed.push(pair);
+                        replacing = true;
+                        target
+                    }
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                },
+                Some(ConflictChoice::KeepBoth) => {
+                    resolved += 1;
+                    self.get_unique_path(&target)
+                }
+                None if resolve_conflicts && fs::symlink_metadata(&target).is_ok() => {
+                    resolved += 1;
+                    self.get_unique_path(&target)
+                }
+                None => target,
+            };
+            match fs::rename(&original, &target) {
+                Ok(()) => {
+                    self.forget_sizes(&original);
+                    done.push((original, target));
+                }
+                Err(e) => {
+                    // The item trashed to make room for this rename goes back where it was
+                    let restored = match trashed.pop().filter(|_| replacing) {
+                        Some(pair) => self.restore_replaced(vec![pair]),
+                        None => Ok(()),
+                    };
+                    error = Some(match restored {
+                        Ok(()) => e,
+                        Err(restore) => io::Error::other(format!("{}; the item it would replace stays in the trash ({})", e, restore)),
                     });
+                    break;
                 }
             }
         }
 
-        // Sort based on current sort mode
-        match self.sort_mode {
-            SortMode::Name => {
-                entries.sort_by(|a, b| {
-                    match (a.is_dir, b.is_dir) {
-                        (true, false) => std::cmp::Ordering::Less,
-                        (false, true) => std::cmp::Ordering::Greater,
-                        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-                    }
-                });
+        let renamed_names: Vec<String> = done.iter()
+            .filter_map(|(_, target)| target.file_name())
+            .filter_map(|n| n.to_str())
+            .map(|n| n.to_string())
+            .collect();
+        let count = done.len();
+
+        // The whole batch undoes in one step, like a multi-item move; each pair records the name actually used.
+        // Replaced items come back from the trash once the renames are undone
+        if !done.is_empty() {
+            let renames = UndoAction::Move { moved_files: done, trashed_originals: Vec::new() };
+            if trashed.is_empty() {
+                self.undo_stack.push(renames);
+            } else {
+                self.undo_stack.push(UndoAction::Replace { replaced: trashed, action: Box::new(renames) });
             }
-            SortMode::Date => {
-                entries.sort_by(|a, b| {
-                    match (a.is_dir, b.is_dir) {
-                        (true, false) => std::cmp::Ordering::Less,
-                        (false, true) => std::cmp::Ordering::Greater,
-                        // Sort by modified time (newest first)
-                        _ => b.modified.cmp(&a.modified),
-                    }
-                });
+        }
+
+        match error {
+            Some(e) => self.show_status(format!("Renamed {} item(s), then failed: {}", count, e)),
+            None => self.show_status(format!("Renamed {} item(s)", count)),
+        }
+        if resolved > 0
+            && let Some(status) = self.status_message.take()
+        {
+            self.amend_status(format!("{}, {} numbered to avoid a name conflict", status, resolved));
+        }
+        if skipped > 0
+            && let Some(status) = self.status_message.take()
+        {
+            self.amend_status(format!("{}, skipped {} taken name(s)", status, skipped));
+        }
+
+        self.load_directory()?;
+        self.select_items_by_name(&renamed_names);
+        Ok(())
+    }
+
+    fn rename_item(&mut self, original_path: PathBuf, new_name: String) -> io::Result<OperationOutcome> {
+        self.log(&format!("rename {} to {:?}", original_path.display(), new_name));
+        if new_name.is_empty() {
+            self.show_status("Name cannot be empty".to_string());
+            return Ok(OperationOutcome::Failed);
+        }
+
+        // parent.join("../x") or "sub/x" would silently move the item out of its directory
+        if new_name.contains('/') || new_name == "." || new_name == ".." {
+            self.show_status("A name cannot contain '/' or be '.'/'..'; use cut and paste to move".to_string());
+            return Ok(OperationOutcome::Failed);
+        }
+
+        if self.queue_mode {
+            self.enqueue(QueuedOperation::Rename { original_path, new_name });
+            return Ok(OperationOutcome::Deferred);
+        }
+
+        let parent = original_path.parent().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
+        })?;
+
+        let new_path = parent.join(&new_name);
+
+        if new_path == original_path {
+            self.show_status("Name unchanged".to_string());
+            return Ok(OperationOutcome::Skipped);
+        }
+
+        if new_path.exists() {
+            if self.config.confirm_rename_overwrite {
+                self.ui_mode = UIMode::ConfirmOverwrite { original_path, new_path };
+                return Ok(OperationOutcome::Deferred);
             }
+            self.show_status(format!("'{}' already exists", new_name));
+            return Ok(OperationOutcome::Failed);
         }
 
-        self.entries = entries;
+        self.perform_rename(original_path, new_path, new_name)?;
+        // A permission error leaves the rename waiting at the sudo prompt
+        Ok(if matches!(self.ui_mode, UIMode::Normal) { OperationOutcome::Done } else { OperationOutcome::Deferred })
+    }
 
-        if let Some(state) = self.dir_memory.get(&self.current_dir) {
-            self.cursor_index = state.cursor_index.min(self.entries.len().saturating_sub(1));
-            self.selected_indices = state.selected_indices.clone();
-            self.scroll_offset = state.scroll_offset;
-        } else {
-            self.cursor_index = 0;
-            self.selected_indices.clear();
-            self.scroll_offset = 0;
+    fn swap_selected_names(&mut self) -> io::Result<()> {
+        let paths: Vec<PathBuf> = self.selected_indices.iter()
+            .filter_map(|&i| self.entries.get(i))
+            .map(|entry| entry.path.clone())
+            .collect();
+        let [first, second] = paths.as_slice() else {
+            self.show_status(format!("Select exactly two items to swap their names ({} selected)", paths.len()));
+            return Ok(());
+        };
+        if first.parent() != second.parent() {
+            self.show_status("Only items in the same directory can swap names".to_string());
+            return Ok(());
+        }
+        if self.refuse_protected(&paths) {
+            return Ok(());
         }
 
-        self.selection_anchor = None;
+        self.log(&format!("swap names of {} and {}", first.display(), second.display()));
+        match Self::swap_paths(first, second) {
+            Ok(()) => self.finish_swap_names(first, second, ""),
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                self.request_sudo(
+                    "Permission denied swapping names. Enter sudo password:".to_string(),
+                    PendingOperation {
+                        items: paths.clone(),
+                        destination: None,
+                        operation: OperationType::SwapNames,
+                        undo_action: None,
+                    },
+                )
+            }
+            Err(e) => {
+                self.show_status(format!("Swap failed: {}", e));
+                Ok(())
+            }
+        }
+    }
 
-        // Clear size cache for new directory and update current item size
-        self.size_cache.clear();
-        self.update_current_item_size();
+    fn finish_swap_names(&mut self, first: &Path, second: &Path, how: &str) -> io::Result<()> {
+        self.undo_stack.push(UndoAction::SwapNames { first: first.to_path_buf(), second: second.to_path_buf() });
+        self.forget_sizes(first);
+        self.forget_sizes(second);
+        let names: Vec<String> = [first, second].iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect();
+        self.show_status(format!("Swapped the names of '{}' and '{}'{}", names[0], names[1], how));
+        self.load_directory()?;
+        self.select_items_by_name(&names);
+        Ok(())
+    }
+
+    // Hidden name beside first that holds it while second takes its place
+    fn swap_temporary_path(first: &Path) -> PathBuf {
+        let name = first.file_name().unwrap_or_default().to_string_lossy();
+        let parent = first.parent().unwrap_or(Path::new("/"));
+        (0..)
+            .map(|n| parent.join(format!(".{}.swap{}", name, n)))
+            .find(|path| fs::symlink_metadata(path).is_err())
+            .unwrap_or_default()
+    }
 
+    // Three renames through a temporary name; a failed step puts back what already moved
+    fn swap_paths(first: &Path, second: &Path) -> io::Result<()> {
+        let temporary = Self::swap_temporary_path(first);
+        fs::rename(first, &temporary)?;
+        if let Err(e) = fs::rename(second, first) {
+            let _ = fs::rename(&temporary, first);
+            return Err(e);
+        }
+        if let Err(e) = fs::rename(&temporary, second) {
+            let _ = fs::rename(first, second);
+            let _ = fs::rename(&temporary, first);
+            return Err(e);
+        }
         Ok(())
     }
 
-    fn build_tree_lines(&self, terminal_width: usize) -> Vec<TreeLine> {
-        let mut lines = Vec::new();
-        let ancestors = self.get_ancestors();
+    fn perform_rename(&mut self, original_path: PathBuf, new_path: PathBuf, new_name: String) -> io::Result<()> {
+        // Try to rename, handle permission errors
+        match fs::rename(&original_path, &new_path) {
+            Ok(_) => {
+                self.show_status(format!("Renamed to '{}'", new_name));
 
-        for (depth, path) in ancestors.iter().enumerate() {
-            // Simple indentation without vertical pipes
-            let indent = "  ".repeat(depth);
+                // Add to undo stack
+                self.undo_stack.push(UndoAction::Rename {
+                    original_path: original_path.clone(),
+                    new_path: new_path.clone(),
+                });
 
-            let name = path.file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("/")
-                .to_string();
+                // Clear size cache entry for old path
+                self.forget_sizes(&original_path);
 
-            let is_current = path == &self.current_dir;
-            // Use corner pipe (└─) for directories on path to current
-            let marker = if depth == ancestors.len() - 1 {
-                "└─"  // Current directory (last in ancestors list)
-            } else if depth > 0 {
-                "└─"  // Intermediate directories on path
-            } else {
-                "─ "  // Root directory
-            };
+                self.load_directory()?;
 
-            lines.push(TreeLine {
-                tree_prefix: format!("{}{}", indent, marker),
-                text: name,
-                timestamp: None,
-                entry_index: None,
-                is_selected: false,
-                is_cursor: false,
-                is_dir: true,
-                is_current_dir: is_current,
-                is_hidden: false,
-            });
+                // Select the renamed item
+                self.select_items_by_name(&[new_name]);
 
-            if is_current && !self.entries.is_empty() {
-                // Child items should be indented one level more than the current directory
-                // Add one extra space after the base indentation
-                let child_indent = format!("{}  ", "  ".repeat(depth));
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied || e.raw_os_error() == Some(13) => {
+                // Need sudo privileges
+                self.request_sudo(
+                    format!("Enter sudo password to rename '{}':", original_path.file_name().unwrap_or_default().to_string_lossy()),
+                    PendingOperation {
+                        items: vec![original_path.clone()],
+                        destination: Some(new_path),
+                        operation: OperationType::Move, // Rename is essentially a move
+                        undo_action: None,
+                    },
+                )
+            }
+            Err(e) => Err(e),
+        }
+    }
 
-                for (i, entry) in self.entries.iter().enumerate() {
-                    let is_last = i == self.entries.len() - 1;
-                    let tree_char = if is_last { "└─" } else { "├─" };
-                    let icon = Self::get_file_icon(&entry.name, entry.is_dir, entry.permissions);
-                    let perms_str = Self::format_permissions(entry.permissions, entry.is_dir);
-                    let date_str = Self::format_date(entry.modified);
-                    let timestamp_str = format!("{}   {}", perms_str, date_str);
+    fn start_sanitize_names(&mut self) {
+        let items = self.get_selected_paths();
+        if items.is_empty() || self.refuse_protected(&items) {
+            return;
+        }
+        self.ui_mode = UIMode::SanitizeNames { items, options: self.config.sanitize, scroll: 0 };
+    }
 
-                    // Check if this is a hidden file/directory (starts with .)
-                    let is_hidden = entry.name.starts_with('.');
+    // The items whose names the options would change, with their new names
+    fn sanitize_renames(items: &[PathBuf], options: SanitizeOptions) -> Vec<(PathBuf, String)> {
+        items.iter()
+            .filter_map(|item| {
+                let name = item.file_name()?.to_string_lossy().to_string();
+                let new_name = options.apply(&name);
+                // Stripping can leave nothing, or only a dot name
+                let usable = !new_name.is_empty() && new_name != "." && new_name != "..";
+                (usable && new_name != name).then(|| (item.clone(), new_name))
+            })
+            .collect()
+    }
 
-                    // Calculate available width for filename
-                    // Timestamp format is "drwxr-xr-x   YYYY-MM-DD HH:mm" (29 chars: 10 for perms + 3 spaces + 16 for date)
-                    let date_width = 29;
-                    let buffer = 1; // Space between filename and timestamp (reduced to move timestamp left)
+    fn apply_sanitize_names(&mut self, items: &[PathBuf], options: SanitizeOptions) -> io::Result<()> {
+        let (mut count, mut deferred, mut skipped, mut failed) = (0, 0, 0, 0);
+        for (item, new_name) in Self::sanitize_renames(items, options) {
+            // Two names can clean up to the same one; the later gets numbered
+            let target = self.get_unique_path(&item.with_file_name(&new_name));
+            let new_name = target.file_name().unwrap_or_default().to_string_lossy().to_string();
+            match self.rename_item(item, new_name) {
+                Ok(OperationOutcome::Done) => count += 1,
+                Ok(OperationOutcome::Deferred) => deferred += 1,
+                Ok(OperationOutcome::Skipped) => skipped += 1,
+                Ok(OperationOutcome::Failed) => failed += 1,
+                Err(e) => {
+                    self.show_status(format!("Sanitized {} name(s), then failed: {}", count, e));
+                    return Ok(());
+                }
+            }
+            // A sudo prompt took over; the remaining names stay as they are
+            if !matches!(self.ui_mode, UIMode::Normal) {
+                return Ok(());
+            }
+        }
+        let mut status = format!("Sanitized {} name(s) (each undoes separately)", count);
+        for (number, what) in [(deferred, "queued"), (skipped, "unchanged"), (failed, "failed")] {
+            if number > 0 {
+                status.push_str(&format!(", {} {}", number, what));
+            }
+        }
+        self.show_status(status);
+        Ok(())
+    }
 
-                    // tree_char "├─" or "└─" is 2 chars
-                    // icon "" or "" is 1 char + space = 2 chars
-                    let tree_char_width = 2;
-                    let icon_display_width = 2; // " " or " "
-                    let prefix_len = child_indent.len() + tree_char_width + icon_display_width;
+    // Move an item that's in the way to the trash, returning the (original, trash) pair for undo
+    fn trash_conflict(&mut self, target: &Path) -> io::Result<(PathBuf, PathBuf)> {
+        let trash_path = self.trash_path_for(target)?;
+        Self::move_path(target, &trash_path)?;
+        self.forget_sizes(target);
+        Ok((target.to_path_buf(), trash_path))
+    }
 
-                    // Available width for filename
-                    let available_width = terminal_width.saturating_sub(prefix_len + date_width + buffer); // No border anymore
+    // o/s/k settle every conflict at once, d asks about each one in turn
+    fn choose_conflicts(&mut self, code: KeyCode) -> io::Result<()> {
+        let choice = match code {
+            KeyCode::Char('o') | KeyCode::Char('O') => ConflictChoice::Overwrite,
+            KeyCode::Char('s') | KeyCode::Char('S') => ConflictChoice::Skip,
+            KeyCode::Char('k') | KeyCode::Char('K') => ConflictChoice::KeepBoth,
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                if let UIMode::ConfirmBatchConflicts { deciding, .. } = &mut self.ui_mode {
+                    *deciding = true;
+                }
+                return Ok(());
+            }
+            KeyCode::Esc => {
+                self.ui_mode = UIMode::Normal;
+                self.show_status("Cancelled, nothing changed".to_string());
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
 
-                    // Truncate filename if needed and pad to fixed width
-                    let display_name = if entry.name.chars().count() > available_width {
-                        let truncate_at = available_width.saturating_sub(3); // Leave room for "..."
-                        let truncated: String = entry.name.chars().take(truncate_at).collect();
-                        format!("{}...", truncated)
-                    } else {
-                        entry.name.clone()
-                    };
+        let UIMode::ConfirmBatchConflicts { conflicts, choices, deciding, .. } = &mut self.ui_mode else {
+            return Ok(());
+        };
+        if *deciding {
+            choices.push(choice);
+        } else {
+            *choices = vec![choice; conflicts.len()];
+        }
+        if choices.len() < conflicts.len() {
+            return Ok(());
+        }
 
-                    // Pad filename to fill available_width so timestamp stays at fixed position
-                    let name_len = display_name.chars().count();
-                    let padding_for_name = available_width.saturating_sub(name_len);
-                    let padding = " ".repeat(padding_for_name);
+        let UIMode::ConfirmBatchConflicts { batch, conflicts, choices, .. } = std::mem::replace(&mut self.ui_mode, UIMode::Normal) else {
+            return Ok(());
+        };
+        let choices: HashMap<PathBuf, ConflictChoice> = conflicts.into_iter().zip(choices).collect();
+        match batch {
+            ConflictBatch::Paste { items, destination, is_move } => self.paste_resolving_conflicts(items, is_move, destination, &choices),
+            ConflictBatch::BulkRename { renames } => self.perform_bulk_renames(renames, &choices),
+        }
+    }
 
-                    lines.push(TreeLine {
-                        tree_prefix: format!("{}{} {} ", child_indent, tree_char, icon),
-                        text: format!("{}{}", display_name, padding),
-                        timestamp: Some(timestamp_str),
-                        entry_index: Some(i),
-                        is_selected: self.selected_indices.contains(&i),
-                        is_cursor: i == self.cursor_index,
-                        is_dir: entry.is_dir,
-                        is_current_dir: false,
-                        is_hidden,
-                    });
-                }
+    fn paste_resolving_conflicts(&mut self, items: Vec<PathBuf>, is_move: bool, destination: PathBuf, choices: &HashMap<PathBuf, ConflictChoice>) -> io::Result<()> {
+        let mut kept = Vec::new();
+        let mut trashed = Vec::new();
+        let mut skipped = 0;
+        let mut trash_error = None;
+        for item in items {
+            let target = destination.join(item.file_name().unwrap_or_default());
+            match choices.get(&target) {
+                Some(ConflictChoice::Skip) => skipped += 1,
+                Some(ConflictChoice::Overwrite) => match self.trash_conflict(&target) {
+                    Ok(entry) => {
+                        trashed.push(entry);
+                        kept.push(item);
+                    }
+                    Err(e) => {
+                        trash_error = Some(e);
+                        break;
+                    }
+                },
+                // A free or kept-both name gets numbered by the paste itself
+                _ => kept.push(item),
             }
         }
 
-        lines
-    }
+        // Nothing is pasted unless every item in the way made it to the trash
+        if let Some(e) = trash_error {
+            self.restore_replaced(trashed)?;
+            return Err(e);
+        }
+        if kept.is_empty() {
+            self.show_status(format!("Skipped {} item(s) whose names are taken, nothing pasted", skipped));
+            return Ok(());
+        }
 
-    fn get_cursor_line_index(&self, terminal_width: usize) -> usize {
-        let tree_lines = self.build_tree_lines(terminal_width);
-        for (line_idx, line) in tree_lines.iter().enumerate() {
-            if line.is_cursor {
-                return line_idx;
+        let depth = self.undo_stack.len();
+        if let Err(e) = self.paste_items(kept, is_move, destination) {
+            self.restore_replaced(trashed)?;
+            return Err(e);
+        }
+        if !trashed.is_empty() {
+            // A paste that stopped to ask for sudo or a cross-filesystem copy would land beside the items, not over them
+            let prompted = !matches!(self.ui_mode, UIMode::Normal);
+            if !prompted
+                && self.undo_stack.len() > depth
+                && let Some(paste) = self.undo_stack.pop()
+            {
+                self.undo_stack.push(UndoAction::Replace { replaced: trashed, action: Box::new(paste) });
+            } else {
+                self.ui_mode = UIMode::Normal;
+                let status = self.status_message.take().unwrap_or_default();
+                self.restore_replaced(trashed)?;
+                if prompted {
+                    self.show_status("Nothing was replaced: overwriting needs a paste that runs right away, without sudo or a cross-filesystem copy".to_string());
+                } else {
+                    self.show_status(format!("{}; nothing was replaced", status));
+                }
+                return Ok(());
             }
         }
-        0
+        if skipped > 0
+            && let Some(status) = self.status_message.take()
+        {
+            self.amend_status(format!("{}, skipped {} taken name(s)", status, skipped));
+        }
+        Ok(())
     }
 
-    fn calculate_scroll_offset(&mut self, visible_height: usize, tree_lines: &[TreeLine]) {
-        let scrolloff = 1;
+    // Puts items trashed to make room back, for an overwrite that didn't go through
+    fn restore_replaced(&mut self, replaced: Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
+        if replaced.is_empty() {
+            return Ok(());
+        }
+        self.apply_undo(&mut UndoAction::Delete { deleted_files: replaced })
+    }
 
-        if visible_height == 0 {
-            return;
+    fn overwrite_and_rename(&mut self, original_path: PathBuf, new_path: PathBuf) -> io::Result<()> {
+        let new_name = new_path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        // Trash the existing target first so the replacement can be undone, together with the rename
+        let trash_path = self.trash_path_for(&new_path)?;
+        fs::rename(&new_path, &trash_path)?;
+        self.forget_sizes(&new_path);
+        let replaced = vec![(new_path.clone(), trash_path)];
+
+        let depth = self.undo_stack.len();
+        if let Err(e) = self.perform_rename(original_path.clone(), new_path.clone(), new_name) {
+            self.restore_replaced(replaced)?;
+            return Err(e);
+        }
+        if let UIMode::PasswordPrompt { pending_operation, .. } = &mut self.ui_mode {
+            // The sudo rename records the combined undo, or puts the item back if it doesn't happen
+            pending_operation.undo_action = Some(UndoAction::Replace {
+                replaced,
+                action: Box::new(UndoAction::Rename { original_path, new_path }),
+            });
+        } else if self.undo_stack.len() > depth
+            && let Some(rename) = self.undo_stack.pop()
+        {
+            self.undo_stack.push(UndoAction::Replace { replaced, action: Box::new(rename) });
+        }
+        Ok(())
+    }
+
+    // An overwriting rename waiting for sudo has trashed its target already; put it back when the rename doesn't happen
+    fn restore_pending_replace(&mut self, op: &PendingOperation) -> io::Result<()> {
+        if matches!(op.operation, OperationType::Move)
+            && let Some(UndoAction::Replace { replaced, .. }) = &op.undo_action
+        {
+            self.restore_replaced(replaced.clone())?;
         }
+        Ok(())
+    }
 
-        let cursor_line_idx = tree_lines.iter()
-            .position(|line| line.is_cursor)
+    fn purge_expired_trash(&mut self) {
+        let max_age = self.config.trash_expiry_days.saturating_mul(24 * 3600);
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
             .unwrap_or(0);
 
-        if cursor_line_idx < self.scroll_offset + scrolloff {
-            self.scroll_offset = cursor_line_idx.saturating_sub(scrolloff);
-        } else if cursor_line_idx >= self.scroll_offset + visible_height - scrolloff {
-            self.scroll_offset = cursor_line_idx + scrolloff + 1 - visible_height.min(tree_lines.len());
+        // Trash names start with the unix time of the delete, see trash_path_for
+        let mut purged = 0;
+        let mut failed = 0;
+        for entry in fs::read_dir(&self.trash_dir).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(deleted_at) = name.split_once('_').and_then(|(secs, _)| secs.parse::<u64>().ok()) else {
+                continue;
+            };
+            if now.saturating_sub(deleted_at) > max_age {
+                match Self::remove_path(&entry.path()) {
+                    Ok(()) => purged += 1,
+                    Err(_) => failed += 1,
+                }
+            }
         }
 
-        self.scroll_offset = self.scroll_offset.min(tree_lines.len().saturating_sub(visible_height));
+        if purged > 0 || failed > 0 {
+            let failed_str = if failed > 0 { format!(" ({} could not be removed)", failed) } else { String::new() };
+            self.show_status(format!(
+                "Purged {} trash item(s) older than {} days{}",
+                purged, self.config.trash_expiry_days, failed_str
+            ));
+        }
     }
 
-    fn save_state(&mut self) {
-        self.dir_memory.insert(
-            self.current_dir.clone(),
-            DirState {
-                cursor_index: self.cursor_index,
-                selected_indices: self.selected_indices.clone(),
-                scroll_offset: self.scroll_offset,
-            },
-        );
+    fn count_cross_device(items: &[PathBuf], destination: &Path) -> usize {
+        use std::os::unix::fs::MetadataExt;
+
+        let Ok(destination_dev) = fs::metadata(destination).map(|m| m.dev()) else {
+            return 0;
+        };
+        items.iter()
+            .filter(|item| fs::symlink_metadata(item).is_ok_and(|m| m.dev() != destination_dev))
+            .count()
     }
 
-    fn move_up(&mut self, shift: bool) {
-        if self.cursor_index > 0 {
-            if shift {
-                if self.selection_anchor.is_none() {
-                    self.selection_anchor = Some(self.cursor_index);
-                }
-            } else {
-                self.selected_indices.clear();
-                self.selection_anchor = None;
-            }
+    // "a network filesystem (nfs4)" or "a removable drive (/dev/sdb1)" for the filesystem holding path
+    fn external_filesystem(path: &Path) -> Option<String> {
+        const NETWORK_TYPES: [&str; 12] = [
+            "nfs", "nfs4", "cifs", "smb3", "smbfs", "9p", "afs", "ceph", "glusterfs",
+            "davfs", "fuse.sshfs", "fuse.rclone",
+        ];
+
+        let path = path.canonicalize().ok()?;
+        let (device, _, fs_type) = Self::mount_table().into_iter()
+            .filter(|(_, mount_point, _)| path.starts_with(mount_point))
+            .max_by_key(|(_, mount_point, _)| mount_point.as_os_str().len())?;
+        if NETWORK_TYPES.contains(&fs_type.as_str()) {
+            return Some(format!("a network filesystem ({})", fs_type));
+        }
 
-            self.cursor_index -= 1;
+        // /sys/class/block/sdb1 resolves into .../block/sdb/sdb1; the removable flag lives on the disk
+        let sys_path = fs::canonicalize(Path::new("/sys/class/block").join(device.strip_prefix("/dev/")?)).ok()?;
+        let disk = if sys_path.join("partition").exists() { sys_path.parent()? } else { sys_path.as_path() };
+        // USB hard drives often report removable=0 but still sit below a usb node
+        let removable = fs::read_to_string(disk.join("removable")).is_ok_and(|flag| flag.trim() == "1")
+            || disk.components().any(|part| part.as_os_str().to_string_lossy().starts_with("usb"));
+        removable.then(|| format!("a removable drive ({})", device))
+    }
 
-            if shift {
-                self.update_selection_range();
+    // Items on the destination's filesystem are renamed as usual; the rest are copied, checked and trashed.
+    // Onto an external drive (to_external) that check is at least by size
+    fn perform_cross_device_move(&mut self, items: Vec<PathBuf>, destination: PathBuf, to_external: bool) -> io::Result<()> {
+        if self.task.is_some() {
+            self.show_status("Another operation is still running; paste again when it finishes".to_string());
+            return Ok(());
+        }
+
+        let mut moved = Vec::new();
+        let mut pending = Vec::new();
+        for item in items {
+            let file_name = item.file_name().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Invalid file name")
+            })?;
+            let dest_path = self.get_unique_path(&destination.join(file_name));
+            match fs::rename(&item, &dest_path) {
+                Ok(()) => moved.push((item, dest_path)),
+                Err(e) if e.kind() == io::ErrorKind::CrossesDevices => pending.push((item, dest_path)),
+                Err(e) => {
+                    if !moved.is_empty() {
+                        self.undo_stack.push(UndoAction::Move { moved_files: moved, trashed_originals: Vec::new() });
+                    }
+                    return Err(e);
+                }
             }
+        }
 
-            self.save_state();
-            self.update_current_item_size();
+        let all_items: Vec<PathBuf> = moved.iter().chain(&pending).map(|(item, _)| item.clone()).collect();
+        self.forget_cut_items(&all_items);
+        if pending.is_empty() {
+            // Everything was already on the destination's filesystem, so the renames did it all
+            let count = moved.len();
+            self.undo_stack.push(UndoAction::Move { moved_files: moved, trashed_originals: Vec::new() });
+            self.show_status(format!("Moved {} item(s)", count));
+            return Ok(());
         }
+        let label = format!("Moving {} item(s) to another filesystem", pending.len());
+        let verify = match self.config.verify_copies {
+            CopyVerification::Off if to_external => CopyVerification::Size,
+            verify => verify,
+        };
+        self.start_transfer(TaskKind::Move, label, pending, moved, verify);
+        Ok(())
     }
 
-    fn move_down(&mut self, shift: bool) {
-        if self.cursor_index < self.entries.len().saturating_sub(1) {
-            if shift {
-                if self.selection_anchor.is_none() {
-                    self.selection_anchor = Some(self.cursor_index);
-                }
-            } else {
-                self.selected_indices.clear();
-                self.selection_anchor = None;
-            }
+    // A changed item changes its own size and the recursive size of every directory above it
+    fn forget_sizes(&mut self, path: &Path) {
+        self.size_cache.remove(path);
+        self.dir_sizes.retain(|dir, _| !path.starts_with(dir) && !dir.starts_with(path));
+    }
 
-            self.cursor_index += 1;
+    fn trash_path_for(&self, item: &Path) -> io::Result<PathBuf> {
+        Self::trash_path_in(&self.trash_dir, item)
+    }
 
-            if shift {
-                self.update_selection_range();
-            }
+    fn trash_path_in(trash_dir: &Path, item: &Path) -> io::Result<PathBuf> {
+        let file_name = item.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Invalid file name")
+        })?;
 
-            self.save_state();
-            self.update_current_item_size();
-        }
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let trash_name = format!("{}_{}", timestamp, file_name.to_string_lossy());
+        Ok(trash_dir.join(trash_name))
     }
 
-    fn update_selection_range(&mut self) {
-        if let Some(anchor) = self.selection_anchor {
-            self.selected_indices.clear();
-            let start = anchor.min(self.cursor_index);
-            let end = anchor.max(self.cursor_index);
-            for i in start..=end {
-                self.selected_indices.insert(i);
+    fn scroll_confirm_list(&mut self, code: KeyCode) {
+        if let UIMode::ConfirmDelete { scroll, .. } | UIMode::ConfirmPaste { scroll, .. } | UIMode::SanitizeNames { scroll, .. } = &mut self.ui_mode {
+            // Rendering clamps the offset to the length of the list
+            match code {
+                KeyCode::Up => *scroll = scroll.saturating_sub(1),
+                KeyCode::Down => *scroll = scroll.saturating_add(1),
+                KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+                KeyCode::PageDown => *scroll = scroll.saturating_add(10),
+                KeyCode::Home => *scroll = 0,
+                _ => {}
             }
         }
     }
 
-    fn toggle_selection(&mut self) {
-        if self.selected_indices.contains(&self.cursor_index) {
-            self.selected_indices.remove(&self.cursor_index);
-        } else {
-            self.selected_indices.insert(self.cursor_index);
+    fn delete_selected(&mut self, cursor_only: bool) {
+        let items = self.target_paths(cursor_only);
+        if self.refuse_protected(&items) {
+            return;
         }
-        self.selection_anchor = None;
-        self.save_state();
-    }
-
-    fn enter_directory(&mut self) -> io::Result<()> {
-        if let Some(entry) = self.entries.get(self.cursor_index) {
-            if entry.is_dir {
-                self.current_dir = entry.path.clone();
-                self.load_directory()?;
-            }
+        if !items.is_empty() {
+            self.ui_mode = UIMode::ConfirmDelete { items, scroll: 0 };
         }
-        Ok(())
     }
 
-    fn open_file(&mut self, path: &PathBuf) -> io::Result<()> {
-        let path_str = path.to_str().ok_or_else(|| {
-            io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
-        })?;
+    fn perform_delete(&mut self, items: &[PathBuf]) -> io::Result<OperationOutcome> {
+        if self.queue_mode {
+            self.enqueue(QueuedOperation::Delete { items: items.to_vec() });
+            self.selected_indices.clear();
+            self.selection_anchor = None;
+            return Ok(OperationOutcome::Deferred);
+        }
 
-        if cfg!(target_os = "windows") {
-            Command::new("cmd")
-                .args(&["/c", "start", "", path_str])
-                .spawn()?;
-        } else if cfg!(target_os = "macos") {
-            Command::new("open")
-                .arg(path_str)
-                .stdin(std::process::Stdio::null())
-                .stdout(std::process::Stdio::null())
-                .stderr(std::process::Stdio::null())
-                .spawn()?;
-        } else {
-            // Linux - use sh -c with setsid and full redirection to suppress all output
-            let command = format!("setsid -f xdg-open '{}' >/dev/null 2>&1 &", path_str);
-            Command::new("sh")
-                .arg("-c")
-                .arg(&command)
-                .stdin(std::process::Stdio::null())
-                .stdout(std::process::Stdio::null())
-                .stderr(std::process::Stdio::null())
-                .spawn()?;
+        self.ensure_types_unchanged(items)?;
+        self.log(&format!("delete {} item(s): {:?}", items.len(), items));
+        for item in items {
+            self.forget_sizes(item);
         }
+        let mut count = 0;
+        let mut deleted_files = Vec::new();
+        let mut cross_device = Vec::new();
 
-        Ok(())
-    }
+        for item in items {
+            let trash_path = self.trash_path_for(item)?;
 
-    fn open_or_enter(&mut self) -> io::Result<()> {
-        if let Some(entry) = self.entries.get(self.cursor_index) {
-            if entry.is_dir {
-                self.current_dir = entry.path.clone();
-                self.load_directory()?;
-            } else {
-                let path = entry.path.clone();
-                let name = entry.name.clone();
-                if let Err(e) = self.open_file(&path) {
-                    self.show_status(format!("Failed to open file: {}", e));
-                } else {
-                    self.show_status(format!("Opening '{}'", name));
+            match fs::rename(item, &trash_path) {
+                Ok(_) => {
+                    deleted_files.push((item.clone(), trash_path));
+                    count += 1;
+                }
+                // The trash is on another filesystem, so the item has to be copied over instead
+                Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                    cross_device.push((item.clone(), trash_path));
+                }
+                Err(e) => {
+                    self.mark_failed(item.clone(), e.to_string());
+                    // Keep whatever already reached the trash undoable
+                    if !deleted_files.is_empty() {
+                        self.undo_stack.push(UndoAction::Delete { deleted_files });
+                    }
+                    return Err(e);
                 }
             }
         }
-        Ok(())
-    }
-
-    fn go_to_parent(&mut self) -> io::Result<()> {
-        if let Some(parent) = self.current_dir.parent() {
-            let current_dir_name = self.current_dir.file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("")
-                .to_string();
-
-            self.current_dir = parent.to_path_buf();
-            self.load_directory()?;
 
-            for (i, entry) in self.entries.iter().enumerate() {
-                if entry.name == current_dir_name {
-                    self.cursor_index = i;
-                    self.save_state();
-                    break;
-                }
+        let outcome = if cross_device.is_empty() {
+            self.undo_stack.push(UndoAction::Delete { deleted_files });
+            self.show_status(format!("Deleted {} item(s) (moved to trash)", count));
+            OperationOutcome::Done
+        } else if self.task.is_some() {
+            if !deleted_files.is_empty() {
+                self.undo_stack.push(UndoAction::Delete { deleted_files });
             }
+            self.show_status(format!(
+                "Deleted {} item(s); {} on another filesystem skipped while another operation is running",
+                count,
+                cross_device.len()
+            ));
+            OperationOutcome::Skipped
+        } else {
+            let label = format!("Moving {} item(s) to trash", cross_device.len());
+            self.start_transfer(TaskKind::Delete, label, cross_device, deleted_files, self.config.verify_copies);
+            OperationOutcome::Deferred
+        };
+
+        // Only the deleted items leave the selection; one kept aside with Alt+Delete survives
+        self.selected_indices.retain(|&i| self.entries.get(i).is_some_and(|entry| !items.contains(&entry.path)));
+        if self.selected_indices.is_empty() {
+            self.selection_anchor = None;
         }
-        Ok(())
+        self.reload_keeping_position()?;
+        Ok(outcome)
     }
 
-    fn handle_mouse_down(&mut self, row: u16, _col: u16, modifiers: KeyModifiers, area_top: u16) {
-        let tree_lines = self.build_tree_lines(self.terminal_width);
-        let clicked_line = (row as usize).saturating_sub(area_top as usize + 1).saturating_add(self.scroll_offset);
-
-        if clicked_line < tree_lines.len() {
-            if let Some(entry_index) = tree_lines[clicked_line].entry_index {
-                if modifiers.contains(KeyModifiers::CONTROL) {
-                    // Ctrl+click: toggle individual item
-                    self.cursor_index = entry_index;
-                    self.toggle_selection();
-                    self.update_current_item_size();
-                } else {
-                    // Regular click: start drag selection
-                    self.cursor_index = entry_index;
-                    self.drag_selection = Some(entry_index);
-                    self.selected_indices.clear();
-                    self.selected_indices.insert(entry_index);
-                    self.selection_anchor = Some(entry_index);
-                    self.save_state();
-                    self.update_current_item_size();
-                }
-            }
-        }
+    fn start_transfer(&mut self, kind: TaskKind, label: String, pending: Vec<(PathBuf, PathBuf)>, completed: Vec<(PathBuf, PathBuf)>, verify: CopyVerification) {
+        let (sender, receiver) = mpsc::channel();
+        // A move keeps its originals in the trash instead of deleting them, since it's no longer atomic
+        let trash_dir = (kind == TaskKind::Move).then(|| self.trash_dir.clone());
+        std::thread::spawn(move || Self::transfer_worker(pending, completed, trash_dir, verify, sender));
+
+        self.task = Some(BackgroundTask {
+            kind,
+            label,
+            receiver,
+            done_bytes: 0,
+            total_bytes: 0,
+            done_files: 0,
+            total_files: 0,
+        });
     }
 
-    fn handle_mouse_drag(&mut self, row: u16, _col: u16, area_top: u16) {
-        if self.drag_selection.is_none() {
-            return;
-        }
+    fn transfer_worker(pending: Vec<(PathBuf, PathBuf)>, mut completed: Vec<(PathBuf, PathBuf)>, trash_dir: Option<PathBuf>, verify: CopyVerification, sender: mpsc::Sender<TaskUpdate>) {
+        let (total_bytes, total_files) = pending.iter()
+            .map(|(src, _)| Self::path_totals(src))
+            .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f));
+        let (mut done_bytes, mut done_files) = (0, 0);
+        let _ = sender.send(TaskUpdate::Progress { done_bytes, total_bytes, done_files, total_files });
+
+        // Thousands of small files would otherwise flood the channel with an update each
+        let mut last_report = std::time::Instant::now();
+        let mut trashed = Vec::new();
+        let mut error = None;
+        for (src, dst) in pending {
+            let mut report = |bytes: u64, files: u64| {
+                done_bytes += bytes;
+                done_files += files;
+                if last_report.elapsed() >= std::time::Duration::from_millis(100) {
+                    last_report = std::time::Instant::now();
+                    let _ = sender.send(TaskUpdate::Progress { done_bytes, total_bytes, done_files, total_files });
+                }
+            };
 
-        let tree_lines = self.build_tree_lines(self.terminal_width);
-        let dragged_line = (row as usize).saturating_sub(area_top as usize + 1).saturating_add(self.scroll_offset);
+            // The source is only removed once the copy is complete and checked, so an interruption never loses data
+            let copied = Self::copy_path_with_progress(&src, &dst, &mut report)
+                .and_then(|_| match Self::verify_tree(&src, &dst, verify)? {
+                    true => Ok(()),
+                    false => Err(io::Error::other(format!("the copy of {} does not match it; the original was kept", src.display()))),
+                });
+            if let Err(e) = copied {
+                let _ = Self::remove_path(&dst);
+                error = Some((src, dst, e.to_string()));
+                break;
+            }
 
-        if dragged_line < tree_lines.len() {
-            if let Some(entry_index) = tree_lines[dragged_line].entry_index {
-                self.cursor_index = entry_index;
-                self.update_selection_range();
-                self.save_state();
-                self.update_current_item_size();
+            // From here on the copy is complete, and may be the only whole version if the source is half removed
+            let removed = match &trash_dir {
+                Some(trash_dir) => Self::trash_path_in(trash_dir, &src).and_then(|trash_path| {
+                    Self::move_path(&src, &trash_path)?;
+                    trashed.push((src.clone(), trash_path));
+                    Ok(())
+                }),
+                None => Self::remove_path(&src),
+            };
+            match removed {
+                Ok(()) => completed.push((src, dst)),
+                Err(e) => {
+                    let message = format!("copied to {}, but removing the original failed ({}); both were kept", dst.display(), e);
+                    error = Some((src, dst, message));
+                    break;
+                }
             }
         }
+
+        let _ = sender.send(TaskUpdate::Finished { completed, trashed, error });
     }
 
-    fn handle_mouse_up(&mut self) {
-        self.drag_selection = None;
+    // Bytes and non-directory entries below path, symlinks not followed
+    fn path_totals(path: &Path) -> (u64, u64) {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => fs::read_dir(path)
+                .map(|entries| entries.flatten()
+                    .map(|e| Self::path_totals(&e.path()))
+                    .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f)))
+                .unwrap_or((0, 0)),
+            Ok(metadata) => (metadata.len(), 1),
+            Err(_) => (0, 0),
+        }
     }
 
-    fn copy_selected(&mut self) {
-        let items = self.get_selected_paths();
-        if !items.is_empty() {
-            self.clipboard = Some(Clipboard {
-                items,
-                operation: ClipboardOp::Copy,
-            });
-            self.show_status(format!("Copied {} item(s)", self.clipboard.as_ref().unwrap().items.len()));
+    // report receives bytes copied and files finished since the last call
+    fn copy_path_with_progress(src: &Path, dst: &Path, report: &mut dyn FnMut(u64, u64)) -> io::Result<()> {
+        let metadata = fs::symlink_metadata(src)?;
+        let file_type = metadata.file_type();
+
+        if file_type.is_symlink() {
+            // Recreate the link itself rather than copying whatever it points to
+            std::os::unix::fs::symlink(fs::read_link(src)?, dst)?;
+            report(0, 1);
+        } else if file_type.is_dir() {
+            fs::create_dir_all(dst)?;
+            for entry in fs::read_dir(src)? {
+                let entry = entry?;
+                Self::copy_path_with_progress(&entry.path(), &dst.join(entry.file_name()), report)?;
+            }
+            fs::set_permissions(dst, metadata.permissions())?;
+        } else if let Some(kind) = SpecialKind::of(&file_type) {
+            // Fail rather than block reading a pipe or device that never ends
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is a {} and cannot be copied", src.display(), kind.label()),
+            ));
+        } else {
+            let mut reader = fs::File::open(src)?;
+            let mut writer = fs::File::create(dst)?;
+            let mut buffer = vec![0u8; 1024 * 1024];
+            loop {
+                let read = io::Read::read(&mut reader, &mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                writer.write_all(&buffer[..read])?;
+                report(read as u64, 0);
+            }
+            fs::set_permissions(dst, metadata.permissions())?;
+            report(0, 1);
         }
+        Ok(())
     }
 
-    fn cut_selected(&mut self) {
-        let items = self.get_selected_paths();
-        if !items.is_empty() {
-            self.clipboard = Some(Clipboard {
-                items,
-                operation: ClipboardOp::Cut,
-            });
-            self.show_status(format!("Cut {} item(s)", self.clipboard.as_ref().unwrap().items.len()));
+    fn remove_path(path: &Path) -> io::Result<()> {
+        if fs::symlink_metadata(path)?.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
         }
     }
 
-    fn paste(&mut self) -> io::Result<()> {
-        if let Some(clipboard) = &self.clipboard {
-            let destination = self.current_dir.clone();
-            let items = clipboard.items.clone();
-            let is_move = matches!(clipboard.operation, ClipboardOp::Cut);
+    fn move_path(src: &Path, dst: &Path) -> io::Result<()> {
+        // rename can't cross filesystems; fall back to copying and removing the original
+        match fs::rename(src, dst) {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                Self::copy_path_with_progress(src, dst, &mut |_, _| {})?;
+                Self::remove_path(src)
+            }
+            result => result,
+        }
+    }
 
-            match self.perform_file_operation_tracked(&items, &destination, is_move) {
-                Ok((count, undo_action)) => {
-                    if is_move {
-                        self.clipboard = None;
+    fn poll_task(&mut self) -> io::Result<()> {
+        let mut finished = None;
+        if let Some(task) = &mut self.task {
+            for update in task.receiver.try_iter() {
+                match update {
+                    TaskUpdate::Progress { done_bytes, total_bytes, done_files, total_files } => {
+                        task.done_bytes = done_bytes;
+                        task.total_bytes = total_bytes;
+                        task.done_files = done_files;
+                        task.total_files = total_files;
+                    }
+                    TaskUpdate::Finished { completed, trashed, error } => {
+                        finished = Some((task.kind, completed, trashed, error));
                     }
+                }
+            }
+        }
 
-                    // Extract actual pasted filenames from the undo action
-                    let pasted_names: Vec<String> = match &undo_action {
-                        UndoAction::Move { moved_files } => {
-                            moved_files.iter()
-                                .filter_map(|(_, dest)| dest.file_name())
-                                .filter_map(|n| n.to_str())
-                                .map(|s| s.to_string())
-                                .collect()
-                        }
-                        UndoAction::Copy { copied_files } => {
-                            copied_files.iter()
-                                .filter_map(|p| p.file_name())
-                                .filter_map(|n| n.to_str())
-                                .map(|s| s.to_string())
-                                .collect()
-                        }
-                        _ => Vec::new(),
-                    };
+        if let Some((kind, completed, trashed, error)) = finished {
+            self.task = None;
+            for (src, dst) in completed.iter().chain(&trashed) {
+                self.forget_sizes(src);
+                self.forget_sizes(dst);
+            }
+            let count = completed.len();
+            let mut follow_names = Vec::new();
 
-                    self.undo_stack.push(undo_action);
-                    self.show_status(format!("Pasted {} item(s)", count));
-                    self.load_directory()?;
-                    self.select_items_by_name(&pasted_names);
-                }
-                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
-                    self.ui_mode = UIMode::PasswordPrompt {
-                        prompt: "Permission denied. Enter sudo password:".to_string(),
-                        password: String::new(),
-                        pending_operation: Box::new(PendingOperation {
-                            items,
-                            destination: Some(destination),
-                            operation: if is_move { OperationType::Move } else { OperationType::Copy },
-                            undo_action: None,
-                        }),
-                    };
+            match kind {
+                TaskKind::Delete => {
+                    if !completed.is_empty() {
+                        self.undo_stack.push(UndoAction::Delete { deleted_files: completed });
+                    }
+                    match error {
+                        Some((path, _, e)) => {
+                            self.show_status(format!("Deleted {} item(s), then failed: {}: {}", count, path.display(), e));
+                            self.mark_failed(path, e);
+                        }
+                        None => self.show_status(format!("Deleted {} item(s) (moved to trash)", count)),
+                    }
                 }
-                Err(e) => {
-                    self.show_status(format!("Error: {}", e));
+                TaskKind::Move => {
+                    // Like a synchronous paste, optionally follow the items to where they landed
+                    let destination = completed.first().and_then(|(_, dst)| dst.parent()).map(Path::to_path_buf);
+                    if self.config.follow_paste
+                        && let Some(destination) = destination
+                        && destination != self.current_dir
+                    {
+                        self.save_state();
+                        self.current_dir = destination;
+                        follow_names = completed.iter()
+                            .filter_map(|(_, dst)| dst.file_name())
+                            .map(|n| n.to_string_lossy().to_string())
+                            .collect();
+                    }
+                    if !completed.is_empty() {
+                        self.undo_stack.push(UndoAction::Move { moved_files: completed, trashed_originals: trashed });
+                    }
+                    match error {
+                        Some((path, dst, e)) => {
+                            // Marked on both sides, since either directory may be the one in view
+                            self.show_status(format!("Moved {} item(s), then failed: {} -> {}: {}", count, path.display(), dst.display(), e));
+                            self.mark_failed(path, e.clone());
+                            self.mark_failed(dst, e);
+                        }
+                        None => self.show_status(format!("Moved {} item(s) (originals kept in trash)", count)),
+                    }
                 }
             }
+
+            self.load_directory()?;
+            if !follow_names.is_empty() {
+                self.select_items_by_name(&follow_names);
+            }
         }
         Ok(())
     }
 
-    fn start_create_new(&mut self) {
-        self.ui_mode = UIMode::CreateNew {
-            creation_type: None,
-            name: String::new(),
-        };
+    fn mark_failed(&mut self, path: PathBuf, reason: String) {
+        self.failed_paths.insert(path, (std::time::Instant::now(), reason));
     }
 
-    fn create_new_item(&mut self, creation_type: CreationType, name: String) -> io::Result<()> {
-        if name.is_empty() {
-            self.show_status("Name cannot be empty".to_string());
-            return Ok(());
+    fn expire_failed_marks(&mut self) {
+        self.failed_paths.retain(|_, (since, _)| since.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    fn toggle_queue_mode(&mut self) {
+        if !self.queue_mode {
+            self.queue_mode = true;
+            self.show_status("Queue mode: paste, delete and rename are collected; Alt+E runs them".to_string());
+        } else if self.operation_queue.is_empty() {
+            self.queue_mode = false;
+            self.show_status("Queue mode off".to_string());
+        } else {
+            let discarded = std::mem::take(&mut self.operation_queue).len();
+            self.queue_mode = false;
+            self.show_status(format!("Queue mode off, discarded {} queued operation(s)", discarded));
         }
+    }
 
-        let new_path = self.current_dir.join(&name);
+    fn enqueue(&mut self, operation: QueuedOperation) {
+        let description = operation.describe();
+        self.operation_queue.push(operation);
+        self.show_status(format!("Queued #{}: {} (Alt+E runs the queue)", self.operation_queue.len(), description));
+    }
 
-        if new_path.exists() {
-            self.show_status(format!("'{}' already exists", name));
+    fn run_queue(&mut self) -> io::Result<()> {
+        if self.operation_queue.is_empty() {
+            self.show_status("The operation queue is empty".to_string());
             return Ok(());
         }
 
-        match creation_type {
-            CreationType::File => {
-                // Create file with default content based on extension
-                let mut file = fs::File::create(&new_path)?;
+        // Leave queue mode first so the operations below actually run
+        self.queue_mode = false;
+        let mut results = Vec::new();
+        for operation in std::mem::take(&mut self.operation_queue) {
+            let description = operation.describe();
+            if operation.sources().iter().all(|path| fs::symlink_metadata(path).is_err()) {
+                results.push((OperationOutcome::Skipped, description, "source no longer exists".to_string()));
+                continue;
+            }
 
-                // Add default content based on file extension to ensure proper MIME type detection
-                let default_content = Self::get_default_file_content(&name);
-                if !default_content.is_empty() {
-                    file.write_all(default_content.as_bytes())?;
+            self.status_message = None;
+            let result = match operation {
+                QueuedOperation::Paste { items, destination, is_move } => self.paste_items(items, is_move, destination),
+                QueuedOperation::PasteIntoNewDir { items, directory, is_move } => {
+                    self.create_dir_and_paste(items, is_move, directory)
                 }
+                QueuedOperation::Delete { items } => self.perform_delete(&items),
+                QueuedOperation::Rename { original_path, new_name } => self.rename_item(original_path, new_name),
+            };
+            let message = self.status_message.take().unwrap_or_default();
 
-                self.show_status(format!("Created file '{}'", name));
-            }
-            CreationType::Directory => {
-                fs::create_dir(&new_path)?;
-                self.show_status(format!("Created directory '{}'", name));
-            }
+            // Operations that stop to ask (sudo password, overwrite, cross-filesystem move) don't run unattended
+            let outcome = if !matches!(self.ui_mode, UIMode::Normal) {
+                self.ui_mode = UIMode::Normal;
+                (OperationOutcome::Skipped, "needs a password or confirmation; run it on its own".to_string())
+            } else {
+                match result {
+                    Err(e) => (OperationOutcome::Failed, e.to_string()),
+                    Ok(outcome) => (outcome, message),
+                }
+            };
+            results.push((outcome.0, description, outcome.1));
         }
 
-        self.load_directory()?;
-
-        // Select the newly created item
-        self.select_items_by_name(&[name]);
-
+        let failed = results.iter().filter(|(outcome, ..)| *outcome != OperationOutcome::Done).count();
+        self.show_status(format!("Ran {} queued operation(s), {} not completed", results.len(), failed));
+        self.reload_keeping_position()?;
+        self.ui_mode = UIMode::QueueSummary { results, scroll: 0 };
         Ok(())
     }
 
-    fn get_default_file_content(filename: &str) -> String {
-        // Get file extension
-        let extension = if let Some(dot_pos) = filename.rfind('.') {
-            &filename[dot_pos + 1..]
-        } else {
-            return "\n".to_string(); // Default: just a newline for files without extension
+    fn start_size_scan(&mut self) {
+        let Some(entry) = self.entries.get(self.cursor_index).filter(|entry| entry.is_dir) else {
+            self.show_status("Cursor is not on a directory".to_string());
+            return;
         };
-
-        // Return appropriate default content based on extension
-        match extension.to_lowercase().as_str() {
-            "py" => "#!/usr/bin/env python3\n".to_string(),
-            "sh" | "bash" => "#!/bin/bash\n".to_string(),
-            "rb" => "#!/usr/bin/env ruby\n".to_string(),
-            "pl" => "#!/usr/bin/env perl\n".to_string(),
-            "js" | "ts" | "jsx" | "tsx" => "// \n".to_string(),
-            "html" | "htm" => "<!DOCTYPE html>\n<html>\n<head>\n    <title></title>\n</head>\n<body>\n    \n</body>\n</html>\n".to_string(),
-            "css" => "/* */\n".to_string(),
-            "rs" => "fn main() {\n    \n}\n".to_string(),
-            "c" | "cpp" | "cc" | "h" | "hpp" => "// \n".to_string(),
-            "java" => "// \n".to_string(),
-            "go" => "package main\n\nfunc main() {\n    \n}\n".to_string(),
-            "md" | "markdown" => "# \n".to_string(),
-            _ => "\n".to_string(), // Default: just a newline
+        if let Some(scan) = &self.size_scan {
+            scan.cancel.store(true, Ordering::Relaxed);
         }
-    }
-
-    fn start_rename(&mut self) {
-        if let Some(entry) = self.entries.get(self.cursor_index) {
-            let original_path = entry.path.clone();
-            let current_name = entry.name.clone();
 
-            // Find the last dot to separate name from extension
-            // Select filename without extension
-            let cursor_pos = if let Some(dot_pos) = current_name.rfind('.') {
-                // Only treat as extension if dot is not at start (hidden files)
-                if dot_pos > 0 {
-                    dot_pos
-                } else {
-                    current_name.len()
-                }
-            } else {
-                current_name.len()
-            };
+        let path = entry.path.clone();
+        // The status bar shows the progress, so drop anything that would cover it
+        self.status_message = None;
+        self.spawn_size_scan(path);
+    }
 
-            self.ui_mode = UIMode::RenameItem {
-                original_path,
-                new_name: current_name,
-                cursor_pos,
-                selection_start: Some(0), // Select from start to cursor (filename without extension)
-            };
+    // Size every listed directory not sized yet, one at a time, for the Directory Size sort
+    fn queue_dir_size_scans(&mut self) {
+        let dir_sizes = &self.dir_sizes;
+        let scanning = self.size_scan.as_ref().map(|scan| &scan.path);
+        self.size_scan_queue = self.entries.iter()
+            .filter(|entry| entry.is_dir && !entry.is_symlink && !dir_sizes.contains_key(&entry.path))
+            .filter(|entry| scanning != Some(&entry.path))
+            .map(|entry| entry.path.clone())
+            .rev() // Popped from the back, so the first listed is sized first
+            .collect();
+        if self.size_scan.is_none()
+            && let Some(path) = self.size_scan_queue.pop()
+        {
+            self.status_message = None;
+            self.spawn_size_scan(path);
         }
     }
 
-    fn rename_item(&mut self, original_path: PathBuf, new_name: String) -> io::Result<()> {
-        if new_name.is_empty() {
-            self.show_status("Name cannot be empty".to_string());
-            return Ok(());
+    // Size one directory as soon as the scan already running, if any, is done
+    fn size_dir_next(&mut self, path: PathBuf) {
+        if self.size_scan.as_ref().is_some_and(|scan| scan.path == path) {
+            return;
+        }
+        if self.size_scan.is_none() {
+            self.spawn_size_scan(path);
+        } else {
+            // Popped from the back
+            self.size_scan_queue.retain(|queued| *queued != path);
+            self.size_scan_queue.push(path);
         }
+    }
 
-        let parent = original_path.parent().ok_or_else(|| {
-            io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
-        })?;
+    fn spawn_size_scan(&mut self, path: PathBuf) {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let worker_path = path.clone();
+        let worker_cancel = Arc::clone(&cancel);
+        std::thread::spawn(move || Self::size_scan_worker(worker_path, worker_cancel, sender));
+        self.size_scan = Some(SizeScan { path, receiver, cancel, bytes_so_far: 0 });
+    }
 
-        let new_path = parent.join(&new_name);
+    fn size_scan_worker(root: PathBuf, cancel: Arc<AtomicBool>, sender: mpsc::Sender<SizeScanUpdate>) {
+        let mut total = 0;
+        let mut last_report = std::time::Instant::now();
+        let mut pending = vec![root];
 
-        if new_path == original_path {
-            self.show_status("Name unchanged".to_string());
-            return Ok(());
+        // Iterative walk that doesn't follow symlinks, checking for cancellation between entries
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                match entry.metadata() {
+                    Ok(metadata) if metadata.is_dir() => pending.push(entry.path()),
+                    Ok(metadata) => total += metadata.len(),
+                    Err(_) => {}
+                }
+                if last_report.elapsed() >= std::time::Duration::from_millis(100) {
+                    last_report = std::time::Instant::now();
+                    if sender.send(SizeScanUpdate::Progress(total)).is_err() {
+                        return;
+                    }
+                }
+            }
         }
+        let _ = sender.send(SizeScanUpdate::Finished(total));
+    }
 
-        if new_path.exists() {
-            self.show_status(format!("'{}' already exists", new_name));
-            return Ok(());
+    fn poll_size_scan(&mut self) -> io::Result<()> {
+        let mut finished = None;
+        if let Some(scan) = &mut self.size_scan {
+            for update in scan.receiver.try_iter() {
+                match update {
+                    SizeScanUpdate::Progress(bytes) => scan.bytes_so_far = bytes,
+                    SizeScanUpdate::Finished(bytes) => finished = Some(bytes),
+                }
+            }
         }
 
-        // Try to rename, handle permission errors
-        match fs::rename(&original_path, &new_path) {
-            Ok(_) => {
-                self.show_status(format!("Renamed to '{}'", new_name));
-
-                // Add to undo stack
-                self.undo_stack.push(UndoAction::Rename {
-                    original_path: original_path.clone(),
-                    new_path: new_path.clone(),
-                });
-
-                // Clear size cache entry for old path
-                self.size_cache.remove(&original_path);
-
-                self.load_directory()?;
+        if let Some(bytes) = finished
+            && let Some(scan) = self.size_scan.take()
+        {
+            if let UIMode::ItemInfo { path, lines } = &mut self.ui_mode
+                && *path == scan.path
+            {
+                *lines = Self::gather_item_info(path, Some(bytes));
+            }
+            self.dir_sizes.insert(scan.path, Some(bytes));
+            if let Some(path) = self.size_scan_queue.pop() {
+                self.spawn_size_scan(path);
+            } else if self.sort_mode == SortMode::DirSize {
+                // All sizes are in; sort once rather than reshuffling after every directory
+                self.reload_keeping_position()?;
+            }
+        }
+        Ok(())
+    }
 
-                // Select the renamed item
-                self.select_items_by_name(&[new_name]);
+    fn start_recent_files(&mut self) {
+        if let Some(scan) = self.recent_scan.take() {
+            scan.cancel.store(true, Ordering::Relaxed);
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let root = self.current_dir.clone();
+        let worker_cancel = Arc::clone(&cancel);
+        let (show_hidden, limit, since) = (self.show_hidden, self.config.recent_files_count, self.changed_since);
+        std::thread::spawn(move || Self::recent_files_worker(root, show_hidden, limit, since, worker_cancel, sender));
+        self.recent_scan = Some(RecentScan { receiver, cancel });
+        self.ui_mode = UIMode::RecentFiles { files: Vec::new(), selected_index: 0 };
+    }
 
-                Ok(())
+    // With a changed-since reference only files modified after it are collected
+    fn recent_files_worker(root: PathBuf, show_hidden: bool, limit: usize, since: Option<SystemTime>, cancel: Arc<AtomicBool>, sender: mpsc::Sender<Vec<(PathBuf, SystemTime)>>) {
+        let newest_first = |files: &mut Vec<(PathBuf, SystemTime)>| {
+            files.sort_by_key(|&(_, modified)| std::cmp::Reverse(modified));
+            files.truncate(limit);
+        };
+        let mut files = Vec::new();
+        let mut pending = vec![root];
+
+        // Same walk as size_scan_worker; the candidates are trimmed whenever they reach twice the limit
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                if !show_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                match entry.metadata() {
+                    Ok(metadata) if metadata.is_dir() => pending.push(entry.path()),
+                    Ok(metadata) => {
+                        if let Ok(modified) = metadata.modified()
+                            && since.is_none_or(|since| modified > since)
+                        {
+                            files.push((entry.path(), modified));
+                        }
+                        if files.len() >= limit.saturating_mul(2).max(1) {
+                            newest_first(&mut files);
+                        }
+                    }
+                    Err(_) => {}
+                }
             }
-            Err(e) if e.kind() == io::ErrorKind::PermissionDenied || e.raw_os_error() == Some(13) => {
-                // Need sudo privileges
-                self.ui_mode = UIMode::PasswordPrompt {
-                    prompt: format!("Enter sudo password to rename '{}':", original_path.file_name().unwrap_or_default().to_string_lossy()),
-                    password: String::new(),
-                    pending_operation: Box::new(PendingOperation {
-                        items: vec![original_path.clone()],
-                        destination: Some(new_path),
-                        operation: OperationType::Move, // Rename is essentially a move
-                        undo_action: None,
-                    }),
+        }
+        newest_first(&mut files);
+        let _ = sender.send(files);
+    }
+
+    fn poll_recent_scan(&mut self) {
+        let Some(scan) = &self.recent_scan else { return };
+        let Ok(found) = scan.receiver.try_recv() else { return };
+        self.recent_scan = None;
+        if let UIMode::RecentFiles { files, .. } = &mut self.ui_mode {
+            if found.is_empty() {
+                self.ui_mode = UIMode::Normal;
+                let message = match self.changed_since {
+                    Some(since) => format!("No files below the current directory changed after {}", Self::format_date(since)),
+                    None => "No files found below the current directory".to_string(),
                 };
-                Ok(())
+                self.show_status(message);
+            } else {
+                *files = found;
             }
-            Err(e) => Err(e),
         }
     }
 
-    fn delete_selected(&mut self) {
-        let items = self.get_selected_paths();
-        if !items.is_empty() {
-            self.ui_mode = UIMode::ConfirmDelete { items };
+    fn close_recent_files(&mut self) {
+        if let Some(scan) = self.recent_scan.take() {
+            scan.cancel.store(true, Ordering::Relaxed);
         }
+        self.ui_mode = UIMode::Normal;
     }
 
-    fn perform_delete(&mut self, items: &[PathBuf]) -> io::Result<()> {
-        let mut count = 0;
-        let mut deleted_files = Vec::new();
+    fn cancel_size_scan(&mut self) {
+        if let Some(scan) = self.size_scan.take() {
+            scan.cancel.store(true, Ordering::Relaxed);
+            self.dir_sizes.insert(scan.path, None);
+        }
+        for path in std::mem::take(&mut self.size_scan_queue) {
+            self.dir_sizes.insert(path, None);
+        }
+    }
 
-        for item in items {
-            let file_name = item.file_name().ok_or_else(|| {
-                io::Error::new(io::ErrorKind::InvalidInput, "Invalid file name")
-            })?;
+    fn size_scan_status_line(scan: &SizeScan, queued: usize) -> String {
+        let name = scan.path.file_name().unwrap_or_default().to_string_lossy();
+        let queued = if queued > 0 { format!(", {} more queued", queued) } else { String::new() };
+        format!(
+            "Calculating size of '{}': {} so far{}… (Esc to cancel)",
+            name,
+            Self::format_file_size(scan.bytes_so_far),
+            queued
+        )
+    }
 
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-            let trash_name = format!("{}_{}", timestamp, file_name.to_string_lossy());
-            let trash_path = self.trash_dir.join(trash_name);
+    fn task_status_line(task: &BackgroundTask) -> String {
+        // Empty files move nothing in bytes, so they are counted by number instead
+        let (done, total) = if task.total_bytes > 0 {
+            (task.done_bytes, task.total_bytes)
+        } else {
+            (task.done_files, task.total_files)
+        };
+        let percent = (done * 100)
+            .checked_div(total)
+            .unwrap_or(0)
+            .min(100);
+        let filled = (percent / 5) as usize;
+        format!(
+            "{}: [{}{}] {}% ({} of {}, {} of {} files)",
+            task.label,
+            "#".repeat(filled),
+            "-".repeat(20 - filled),
+            percent,
+            Self::format_file_size(task.done_bytes),
+            Self::format_file_size(task.total_bytes),
+            task.done_files,
+            task.total_files
+        )
+    }
 
-            fs::rename(item, &trash_path)?;
-            deleted_files.push((item.clone(), trash_path));
-            count += 1;
+    fn request_sudo(&mut self, prompt: String, pending_operation: PendingOperation) -> io::Result<()> {
+        self.log(&format!("sudo needed for {:?} on {:?}", pending_operation.operation, pending_operation.items));
+        // With session reuse on, a still-valid sudo timestamp skips the prompt entirely
+        if self.config.sudo_keep_session && Self::sudo_session_active() {
+            return self.run_sudo_operation(&pending_operation, "");
         }
 
-        self.undo_stack.push(UndoAction::Delete { deleted_files });
-        self.show_status(format!("Deleted {} item(s) (moved to trash)", count));
-        self.selected_indices.clear();
-        self.selection_anchor = None;
-        self.save_state(); // Save cleared selection before loading directory
-        self.load_directory()?;
+        self.ui_mode = UIMode::PasswordPrompt {
+            prompt,
+            password: String::new(),
+            pending_operation: Box::new(pending_operation),
+        };
         Ok(())
     }
 
+    fn sudo_session_active() -> bool {
+        // -n fails instead of prompting when no cached credential exists
+        Command::new("sudo")
+            .args(["-n", "-v"])
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
     fn validate_sudo_password(&self, password: &str) -> io::Result<()> {
         // Use sudo -kSv to clear cache (-k) and validate password (-v) from stdin (-S)
+        // Session reuse drops -k so the validated timestamp carries over to later operations
+        let flags = if self.config.sudo_keep_session { "-S" } else { "-kS" };
         let mut child = Command::new("sudo")
-            .arg("-kS")
+            .arg(flags)
             .arg("-v")
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
@@ -917,26 +5998,192 @@ impl FileExplorer {
                 "Incorrect sudo password"
             ));
         }
-
+
+        Ok(())
+    }
+
+    fn run_sudo_operation(&mut self, op: &PendingOperation, pwd: &str) -> io::Result<()> {
+        self.log(&format!("sudo {:?} on {:?}", op.operation, op.items));
+        match &op.operation {
+            OperationType::Copy | OperationType::Move => {
+                let is_move = matches!(op.operation, OperationType::Move);
+                if let Some(dest) = &op.destination {
+                    // Check if this is a rename operation (single item, same parent directory)
+                    // A paste always targets an existing directory, a rename never does
+                    let is_rename = op.items.len() == 1
+                        && op.items[0].parent() == dest.parent()
+                        && !dest.is_dir();
+
+                    if is_rename {
+                        // Handle rename with sudo
+                        let original_path = &op.items[0];
+                        let new_name = dest.file_name()
+                            .and_then(|n| n.to_str())
+                            .unwrap_or("")
+                            .to_string();
+
+                        match self.perform_rename_sudo(original_path, dest, pwd) {
+                            Ok(_) => {
+                                self.show_status(format!("Renamed to '{}' with sudo", new_name));
+
+                                // Add to undo stack; an overwrite brings its trashed target along
+                                self.undo_stack.push(op.undo_action.clone().unwrap_or_else(|| UndoAction::Rename {
+                                    original_path: original_path.clone(),
+                                    new_path: dest.clone(),
+                                }));
+
+                                // Clear size cache entry for old path
+                                self.forget_sizes(original_path);
+
+                                self.load_directory()?;
+                                self.select_items_by_name(&[new_name]);
+                            }
+                            Err(e) => {
+                                self.restore_pending_replace(op)?;
+                                self.show_status(format!("Error: {}", e));
+                            }
+                        }
+                    } else {
+                        // Handle copy/move with sudo
+                        let pasted_names: Vec<String> = op.items.iter()
+                            .filter_map(|p| p.file_name())
+                            .filter_map(|n| n.to_str())
+                            .map(|s| s.to_string())
+                            .collect();
+
+                        match self.perform_file_operation_sudo(&op.items, dest, is_move, pwd) {
+                            Ok(count) => {
+                                if is_move {
+                                    self.clipboard = None;
+                                }
+                                for item in op.items.iter().chain([dest]) {
+                                    self.forget_sizes(item);
+                                }
+                                self.show_status(format!("Pasted {} item(s) with sudo", count));
+                                self.load_directory()?;
+                                self.select_items_by_name(&pasted_names);
+                            }
+                            Err(e) => {
+                                self.show_status(format!("Error: {}", e));
+                            }
+                        }
+                    }
+                }
+            }
+            OperationType::Delete => {
+                match self.perform_delete_sudo(&op.items, pwd) {
+                    Ok(deleted_files) => {
+                        let count = deleted_files.len();
+                        for item in &op.items {
+                            self.forget_sizes(item);
+                        }
+                        self.undo_stack.push(UndoAction::Delete { deleted_files });
+                        self.show_status(format!("Deleted {} item(s) with sudo (moved to trash)", count));
+                        self.selected_indices.clear();
+                        self.selection_anchor = None;
+                        self.load_directory()?;
+                    }
+                    Err(e) => {
+                        self.show_status(format!("Error: {}", e));
+                    }
+                }
+            }
+            OperationType::Undo => {
+                if let Some(undo_action) = &op.undo_action {
+                    // Like undo, a replace whose first part went through is narrowed to the restore still left
+                    let mut undo_action = undo_action.clone();
+                    if let UndoAction::Replace { replaced, action } = &undo_action {
+                        if let Err(e) = self.perform_undo_sudo(action, pwd) {
+                            self.show_status(format!("Error: {}", e));
+                            return Ok(());
+                        }
+                        let remaining = UndoAction::Delete { deleted_files: replaced.clone() };
+                        if let Some(index) = self.undo_stack.iter().rposition(|entry| *entry == undo_action) {
+                            self.undo_stack[index] = remaining.clone();
+                        }
+                        undo_action = remaining;
+                    }
+                    let undo_action = &undo_action;
+                    match self.perform_undo_sudo(undo_action, pwd) {
+                        Ok(count) => {
+                            // Take the action out of the history wherever it sits, since we successfully undid it
+                            if let Some(index) = self.undo_stack.iter().rposition(|action| action == undo_action) {
+                                self.undo_stack.remove(index);
+                            }
+                            let msg = match undo_action {
+                                UndoAction::Copy { .. } => format!("Undone copy: removed {} item(s) with sudo", count),
+                                UndoAction::Move { .. } => format!("Undone move: restored {} item(s) with sudo", count),
+                                UndoAction::Delete { .. } => format!("Undone delete: restored {} item(s) with sudo", count),
+                                UndoAction::PasteIntoNewDir { .. } => format!("Undone paste: {} item(s) with sudo", count),
+                                UndoAction::SwapNames { .. } => "Undone name swap with sudo".to_string(),
+                                UndoAction::Replace { .. } => format!("Undone replace: {} item(s) with sudo", count),
+                                UndoAction::Rename { original_path, .. } => {
+                                    let name = original_path.file_name()
+                                        .and_then(|n| n.to_str())
+                                        .unwrap_or("");
+                                    format!("Undone rename: restored to '{}' with sudo", name)
+                                }
+                            };
+                            self.show_status(msg);
+                            self.load_directory()?;
+                        }
+                        Err(e) => {
+                            self.show_status(format!("Error: {}", e));
+                        }
+                    }
+                }
+            }
+            OperationType::Mount | OperationType::Unmount => {
+                let unmount = matches!(op.operation, OperationType::Unmount);
+                if let Some(mount_point) = op.items.first() {
+                    match self.perform_mount_sudo(mount_point, unmount, pwd) {
+                        Ok(()) => self.finish_mount_toggle(mount_point, unmount)?,
+                        Err(e) => self.show_status(format!("Error: {}", e)),
+                    }
+                }
+            }
+            OperationType::MakeExecutable | OperationType::ClearExecutable => {
+                let executable = matches!(op.operation, OperationType::MakeExecutable);
+                match self.perform_chmod_sudo(&op.items, executable, pwd) {
+                    Ok(()) => self.finish_executable_toggle(&op.items, executable)?,
+                    Err(e) => self.show_status(format!("Error: {}", e)),
+                }
+            }
+            OperationType::SwapNames => {
+                if let [first, second] = op.items.as_slice() {
+                    let swapped = self.validate_sudo_password(pwd)
+                        .and_then(|_| Self::swap_paths_sudo(first, second, pwd));
+                    match swapped {
+                        Ok(()) => self.finish_swap_names(first, second, " with sudo")?,
+                        Err(e) => self.show_status(format!("Error: {}", e)),
+                    }
+                }
+            }
+            OperationType::ListDirectory => {
+                // Only list if the user is still looking at the directory that was denied
+                if op.items.first() == Some(&self.current_dir) {
+                    match self.load_directory_sudo(pwd) {
+                        Ok(count) => {
+                            self.show_status(format!("Listed {} item(s) with sudo (read-only view)", count));
+                        }
+                        Err(e) => {
+                            self.show_status(format!("Error: {}", e));
+                        }
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
     fn perform_delete_sudo(&self, items: &[PathBuf], password: &str) -> io::Result<Vec<(PathBuf, PathBuf)>> {
         // Validate password first to avoid cached credentials
         self.validate_sudo_password(password)?;
+        self.ensure_types_unchanged(items)?;
         let mut deleted_files = Vec::new();
 
         for item in items {
-            let file_name = item.file_name().ok_or_else(|| {
-                io::Error::new(io::ErrorKind::InvalidInput, "Invalid file name")
-            })?;
-
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-            let trash_name = format!("{}_{}", timestamp, file_name.to_string_lossy());
-            let trash_path = self.trash_dir.join(trash_name);
+            let trash_path = self.trash_path_for(item)?;
 
             let item_str = item.to_str().ok_or_else(|| {
                 io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
@@ -1004,12 +6251,262 @@ impl FileExplorer {
         Ok(())
     }
 
+    // (device, mount point, filesystem type) of everything currently mounted
+    fn mount_table() -> Vec<(String, PathBuf, String)> {
+        // /proc/mounts escapes spaces and other separators as \ooo octal sequences
+        let unescape = |field: &str| {
+            let mut out = Vec::new();
+            let bytes = field.as_bytes();
+            let mut i = 0;
+            while i < bytes.len() {
+                if bytes[i] == b'\\'
+                    && i + 3 < bytes.len()
+                    && let Some(byte) = std::str::from_utf8(&bytes[i + 1..i + 4])
+                        .ok()
+                        .and_then(|digits| u8::from_str_radix(digits, 8).ok())
+                {
+                    out.push(byte);
+                    i += 4;
+                } else {
+                    out.push(bytes[i]);
+                    i += 1;
+                }
+            }
+            String::from_utf8_lossy(&out).to_string()
+        };
+
+        fs::read_to_string("/proc/mounts")
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let device = unescape(fields.next()?);
+                let mount_point = PathBuf::from(unescape(fields.next()?));
+                let fs_type = fields.next()?.to_string();
+                Some((device, mount_point, fs_type))
+            })
+            .collect()
+    }
+
+    fn toggle_mount(&mut self) -> io::Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let Some(entry) = self.entries.get(self.cursor_index) else {
+            return Ok(());
+        };
+        let path = entry.path.clone();
+        let mounts = Self::mount_table();
+
+        let is_block_device = fs::metadata(&path)
+            .map(|m| m.file_type().is_block_device())
+            .unwrap_or(false);
+
+        if is_block_device {
+            // udisksctl goes through polkit, so removable media needs no sudo
+            let device = path.to_string_lossy().to_string();
+            let mounted_at = mounts.iter().find(|(dev, ..)| *dev == device).map(|(_, mp, _)| mp.clone());
+            let action = if mounted_at.is_some() { "unmount" } else { "mount" };
+            let output = Command::new("udisksctl")
+                .args([action, "--no-user-interaction", "-b", &device])
+                .stdin(std::process::Stdio::null())
+                .output();
+
+            match output {
+                Ok(output) if output.status.success() => {
+                    if mounted_at.is_some() {
+                        self.show_status(format!("Unmounted {}", device));
+                        self.load_directory()?;
+                    } else if let Some((_, mount_point, _)) = Self::mount_table().into_iter().find(|(dev, ..)| *dev == device) {
+                        self.show_status(format!("Mounted {} at {}", device, mount_point.display()));
+                        self.save_state();
+                        self.current_dir = mount_point;
+                        self.load_directory()?;
+                    } else {
+                        self.show_status(format!("Mounted {}", device));
+                    }
+                }
+                Ok(output) => {
+                    let error_msg = String::from_utf8_lossy(&output.stderr);
+                    self.show_status(format!("Failed to {} {}: {}", action, device, error_msg.trim()));
+                }
+                Err(e) => {
+                    self.show_status(format!("Failed to run udisksctl: {}", e));
+                }
+            }
+            return Ok(());
+        }
+
+        if !self.config.mount_points.contains(&path) {
+            self.show_status("Not a block device or a configured mount point".to_string());
+            return Ok(());
+        }
+
+        // fstab mount points: try as the current user ("user" option), then fall back to sudo
+        let unmount = mounts.iter().any(|(_, mp, _)| *mp == path);
+        let program = if unmount { "umount" } else { "mount" };
+        let output = Command::new(program)
+            .arg(&path)
+            .stdin(std::process::Stdio::null())
+            .output()?;
+
+        if output.status.success() {
+            self.finish_mount_toggle(&path, unmount)?;
+        } else {
+            self.request_sudo(
+                format!("{} needs root. Enter sudo password:", program),
+                PendingOperation {
+                    items: vec![path],
+                    destination: None,
+                    operation: if unmount { OperationType::Unmount } else { OperationType::Mount },
+                    undo_action: None,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    // Like chmod a+x, or a-x when every selected file is already executable by its owner
+    fn toggle_executable(&mut self) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let files: Vec<PathBuf> = self.get_selected_paths().into_iter().filter(|path| path.is_file()).collect();
+        if files.is_empty() {
+            self.show_status("No files to change (directories are left alone)".to_string());
+            return Ok(());
+        }
+        let executable = !files.iter()
+            .all(|path| fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o100 != 0));
+
+        for path in &files {
+            // A file that vanished or can't be read is reported like a failed change, not passed up
+            let changed = fs::metadata(path).and_then(|metadata| {
+                let mode = metadata.permissions().mode();
+                let mode = if executable { mode | 0o111 } else { mode & !0o111 };
+                fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            });
+            match changed {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                    return self.request_sudo(
+                        format!("Permission denied changing '{}'. Enter sudo password:", path.file_name().unwrap_or_default().to_string_lossy()),
+                        PendingOperation {
+                            items: files,
+                            destination: None,
+                            operation: if executable { OperationType::MakeExecutable } else { OperationType::ClearExecutable },
+                            undo_action: None,
+                        },
+                    );
+                }
+                Err(e) => {
+                    self.show_status(format!("Error: {}", e));
+                    self.mark_failed(path.clone(), e.to_string());
+                    return self.reload_keeping_position();
+                }
+            }
+        }
+        self.finish_executable_toggle(&files, executable)
+    }
+
+    fn finish_executable_toggle(&mut self, files: &[PathBuf], executable: bool) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        self.log(&format!("{} execute permission on {:?}", if executable { "added" } else { "removed" }, files));
+        match files {
+            [path] => {
+                let mode = fs::metadata(path).map(|metadata| metadata.permissions().mode()).unwrap_or(0);
+                self.show_status(format!(
+                    "'{}' is now {} ({:o})",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    Self::format_permissions(mode, false),
+                    mode & 0o7777
+                ));
+            }
+            _ if executable => self.show_status(format!("Made {} file(s) executable", files.len())),
+            _ => self.show_status(format!("Cleared the execute bits of {} file(s)", files.len())),
+        }
+        self.reload_keeping_position()
+    }
+
+    fn finish_mount_toggle(&mut self, mount_point: &Path, unmounted: bool) -> io::Result<()> {
+        if unmounted {
+            self.show_status(format!("Unmounted {}", mount_point.display()));
+            self.load_directory()?;
+        } else {
+            self.show_status(format!("Mounted {}", mount_point.display()));
+            self.save_state();
+            self.current_dir = mount_point.to_path_buf();
+            self.load_directory()?;
+        }
+        Ok(())
+    }
+
+    fn perform_mount_sudo(&self, mount_point: &Path, unmount: bool, password: &str) -> io::Result<()> {
+        // Validate password first to avoid cached credentials
+        self.validate_sudo_password(password)?;
+
+        let mut child = Command::new("sudo")
+            .arg("-S")
+            .arg(if unmount { "umount" } else { "mount" })
+            .arg(mount_point)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{}", password)?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(error_msg.trim().to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn perform_chmod_sudo(&self, files: &[PathBuf], executable: bool, password: &str) -> io::Result<()> {
+        // Validate password first to avoid cached credentials
+        self.validate_sudo_password(password)?;
+
+        let mut child = Command::new("sudo")
+            .arg("-S")
+            .arg("chmod")
+            .arg(if executable { "a+x" } else { "a-x" })
+            .arg("--")
+            .args(files)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{}", password)?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(error_msg.trim().to_string()));
+        }
+
+        Ok(())
+    }
+
     fn perform_undo_sudo(&self, action: &UndoAction, password: &str) -> io::Result<usize> {
         // Validate password first to avoid cached credentials
         self.validate_sudo_password(password)?;
 
         let mut count = 0;
         match action {
+            UndoAction::PasteIntoNewDir { paste, .. } => {
+                count = self.perform_undo_sudo(paste, password)?;
+            }
+            UndoAction::Replace { replaced, action } => {
+                count = self.perform_undo_sudo(action, password)?;
+                count += self.perform_undo_sudo(&UndoAction::Delete { deleted_files: replaced.clone() }, password)?;
+            }
             UndoAction::Copy { copied_files } => {
                 for file in copied_files {
                     if file.exists() {
@@ -1043,12 +6540,23 @@ impl FileExplorer {
                     }
                 }
             }
-            UndoAction::Move { moved_files } => {
+            UndoAction::Move { moved_files, trashed_originals } => {
                 for (original, moved_to) in moved_files {
-                    if moved_to.exists() {
+                    // Same as the unprivileged undo: the original comes back from the trash and the copy goes there
+                    let kept = trashed_originals.iter()
+                        .find(|(source, trash_path)| source == original && trash_path.exists());
+                    if let Some((_, trash_path)) = kept {
+                        let restore_to = self.restore_path(original);
+                        Self::sudo_mv(trash_path, &restore_to, password)?;
+                        if fs::symlink_metadata(moved_to).is_ok() {
+                            Self::sudo_mv(moved_to, &self.trash_path_for(moved_to)?, password)?;
+                        }
+                        count += 1;
+                    } else if moved_to.exists() {
                         let moved_to_str = moved_to.to_str().ok_or_else(|| {
                             io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
                         })?;
+                        let original = self.restore_path(original);
                         let original_str = original.to_str().ok_or_else(|| {
                             io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
                         })?;
@@ -1083,6 +6591,7 @@ impl FileExplorer {
                         let trash_path_str = trash_path.to_str().ok_or_else(|| {
                             io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
                         })?;
+                        let original = self.restore_path(original);
                         let original_str = original.to_str().ok_or_else(|| {
                             io::Error::new(io::ErrorKind::InvalidInput, "Invalid path")
                         })?;
@@ -1116,6 +6625,7 @@ impl FileExplorer {
                     let new_path_str = new_path.to_str().ok_or_else(|| {
                         io::Error::new(io::ErrorKind::InvalidInput, "Invalid new path")
                     })?;
+                    let original_path = self.restore_path(original_path);
                     let original_str = original_path.to_str().ok_or_else(|| {
                         io::Error::new(io::ErrorKind::InvalidInput, "Invalid original path")
                     })?;
@@ -1143,10 +6653,55 @@ impl FileExplorer {
                     count += 1;
                 }
             }
+            UndoAction::SwapNames { first, second } => {
+                Self::swap_paths_sudo(first, second, password)?;
+                count = 2;
+            }
         }
         Ok(count)
     }
 
+    // swap_paths through sudo mv, putting things back the same way if a step fails
+    fn swap_paths_sudo(first: &Path, second: &Path, password: &str) -> io::Result<()> {
+        let temporary = Self::swap_temporary_path(first);
+        Self::sudo_mv(first, &temporary, password)?;
+        if let Err(e) = Self::sudo_mv(second, first, password) {
+            let _ = Self::sudo_mv(&temporary, first, password);
+            return Err(e);
+        }
+        if let Err(e) = Self::sudo_mv(&temporary, second, password) {
+            let _ = Self::sudo_mv(first, second, password);
+            let _ = Self::sudo_mv(&temporary, first, password);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // One `sudo mv` with the password on stdin; the caller validated it already
+    fn sudo_mv(from: &Path, to: &Path, password: &str) -> io::Result<()> {
+        let mut child = Command::new("sudo")
+            .arg("-S")
+            .arg("mv")
+            .arg("--")
+            .arg(from)
+            .arg(to)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{}", password)?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(error_msg.trim().to_string()));
+        }
+        Ok(())
+    }
+
     fn select_items_by_name(&mut self, names: &[String]) {
         self.selected_indices.clear();
         for (i, entry) in self.entries.iter().enumerate() {
@@ -1161,109 +6716,208 @@ impl FileExplorer {
     }
 
     fn undo(&mut self) -> io::Result<()> {
-        if let Some(action) = self.undo_stack.pop() {
-            let action_clone = action.clone();
-            let result: io::Result<()> = match action {
-                UndoAction::Copy { copied_files } => {
-                    let mut count = 0;
-                    for file in &copied_files {
-                        if file.exists() {
-                            if file.is_dir() {
-                                if let Err(e) = fs::remove_dir_all(file) {
-                                    return self.handle_undo_error(e, action_clone);
-                                }
-                            } else {
-                                if let Err(e) = fs::remove_file(file) {
-                                    return self.handle_undo_error(e, action_clone);
-                                }
-                            }
-                            count += 1;
-                        }
+        let Some(action) = self.undo_stack.pop() else {
+            self.show_status("Nothing to undo".to_string());
+            return Ok(());
+        };
+        // A failed undo goes back to the same place in the history, narrowed to what is left to undo
+        let index = self.undo_stack.len();
+        let mut action = action;
+        for path in action.clone().paths() {
+            self.forget_sizes(path);
+        }
+        match self.apply_undo(&mut action) {
+            Ok(()) => {
+                if let Err(e) = self.load_directory() {
+                    self.show_status(format!("Warning: {}", e));
+                }
+                Ok(())
+            }
+            Err(e) => self.handle_undo_error(e, action, index),
+        }
+    }
+
+    // Reverses one recorded action; errors are left to the caller, with action narrowed to the part still to undo
+    fn apply_undo(&mut self, action: &mut UndoAction) -> io::Result<()> {
+        match action.clone() {
+            UndoAction::Copy { copied_files } => {
+                let mut count = 0;
+                for file in &copied_files {
+                    // Not following symlinks, so a copied link is removed rather than what it points to
+                    if fs::symlink_metadata(file).is_ok() {
+                        Self::remove_path(file)?;
+                        count += 1;
                     }
-                    self.show_status(format!("Undone copy: removed {} item(s)", count));
-                    Ok(())
                 }
-                UndoAction::Move { moved_files } => {
-                    let mut count = 0;
-                    for (original, moved_to) in &moved_files {
-                        if moved_to.exists() {
-                            if let Err(e) = fs::rename(moved_to, original) {
-                                return self.handle_undo_error(e, action_clone);
-                            }
-                            count += 1;
-                        }
+                self.show_status(format!("Undone copy: removed {} item(s)", count));
+                Ok(())
+            }
+            UndoAction::Move { moved_files, trashed_originals } => {
+                let mut count = 0;
+                let mut renamed = 0;
+                for (original, moved_to) in &moved_files {
+                    // Copied across filesystems: bring back the untouched original and trash the copy,
+                    // which may have been edited since the move
+                    let kept = trashed_originals.iter()
+                        .find(|(source, trash_path)| source == original && trash_path.exists());
+                    if let Some((_, trash_path)) = kept {
+                        let restore_to = self.restore_path(original);
+                        Self::move_path(trash_path, &restore_to)
+                            .and_then(|_| match fs::symlink_metadata(moved_to) {
+                                Ok(_) => Self::move_path(moved_to, &self.trash_path_for(moved_to)?),
+                                Err(_) => Ok(()),
+                            })?;
+                        count += 1;
+                        renamed += usize::from(restore_to != *original);
+                    } else if moved_to.exists() {
+                        let restore_to = self.restore_path(original);
+                        Self::move_path(moved_to, &restore_to)?;
+                        count += 1;
+                        renamed += usize::from(restore_to != *original);
                     }
-                    self.show_status(format!("Undone move: restored {} item(s)", count));
-                    Ok(())
                 }
-                UndoAction::Delete { deleted_files } => {
-                    let mut count = 0;
-                    for (original, trash_path) in &deleted_files {
-                        if trash_path.exists() {
-                            if let Err(e) = fs::rename(trash_path, original) {
-                                return self.handle_undo_error(e, action_clone);
-                            }
-                            count += 1;
-                        }
+                self.show_status(format!("Undone move: restored {} item(s){}", count, Self::renamed_on_restore(renamed)));
+                Ok(())
+            }
+            UndoAction::Delete { deleted_files } => {
+                let mut count = 0;
+                let mut renamed = 0;
+                for (original, trash_path) in &deleted_files {
+                    if trash_path.exists() {
+                        let restore_to = self.restore_path(original);
+                        Self::move_path(trash_path, &restore_to)?;
+                        count += 1;
+                        renamed += usize::from(restore_to != *original);
                     }
-                    self.show_status(format!("Undone delete: restored {} item(s)", count));
-                    Ok(())
                 }
-                UndoAction::Rename { original_path, new_path } => {
-                    // Rename back from new_path to original_path
-                    if new_path.exists() {
-                        if let Err(e) = fs::rename(&new_path, &original_path) {
-                            return self.handle_undo_error(e, action_clone);
-                        }
-                        let original_name = original_path.file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("")
-                            .to_string();
-                        self.show_status(format!("Undone rename: restored to '{}'", original_name));
+                self.show_status(format!("Undone delete: restored {} item(s){}", count, Self::renamed_on_restore(renamed)));
+                Ok(())
+            }
+            UndoAction::PasteIntoNewDir { directory, mut paste } => {
+                // The directory goes only if the paste came back out of it
+                self.apply_undo(&mut paste)?;
+                if fs::remove_dir(&directory).is_ok() {
+                    if self.current_dir == directory
+                        && let Some(parent) = directory.parent()
+                    {
+                        self.current_dir = parent.to_path_buf();
+                    }
+                    if let Some(status) = self.status_message.take() {
+                        self.amend_status(format!("{} and the new directory", status));
+                    }
+                }
+                Ok(())
+            }
+            UndoAction::Replace { replaced, action: mut taken_by } => {
+                self.apply_undo(&mut taken_by)?;
+                // Once the names are free again only the restore is left; retrying the first part could remove
+                // the restored items, which now sit at the same paths
+                *action = UndoAction::Delete { deleted_files: replaced };
+                let taken_status = self.status_message.take();
+                self.apply_undo(action)?;
+                if let (Some(taken_status), Some(status)) = (taken_status, self.status_message.take()) {
+                    self.show_status(format!("{}; {}", taken_status, status.to_lowercase()));
+                }
+                Ok(())
+            }
+            UndoAction::SwapNames { first, second } => {
+                Self::swap_paths(&first, &second)?;
+                self.show_status(format!(
+                    "Undone swap of '{}' and '{}'",
+                    first.file_name().unwrap_or_default().to_string_lossy(),
+                    second.file_name().unwrap_or_default().to_string_lossy()
+                ));
+                Ok(())
+            }
+            UndoAction::Rename { original_path, new_path } => {
+                // Rename back from new_path to original_path
+                if new_path.exists() {
+                    let restore_to = self.restore_path(&original_path);
+                    fs::rename(&new_path, &restore_to)?;
+                    let restored_name = restore_to.file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("")
+                        .to_string();
+                    if restore_to == original_path {
+                        self.show_status(format!("Undone rename: restored to '{}'", restored_name));
                     } else {
-                        self.show_status("Cannot undo rename: file not found".to_string());
+                        self.show_status(format!("Undone rename: old name is taken, restored as '{}'", restored_name));
                     }
-                    Ok(())
+                } else {
+                    self.show_status("Cannot undo rename: file not found".to_string());
                 }
-            };
+                Ok(())
+            }
+        }
+    }
 
-            match result {
-                Ok(_) => {
-                    if let Err(e) = self.load_directory() {
-                        self.show_status(format!("Warning: {}", e));
+    fn undo_last_delete(&mut self) -> io::Result<()> {
+        // Restore the most recent delete even if other actions were recorded after it
+        let index = match self.undo_stack.iter().rposition(|a| matches!(a, UndoAction::Delete { .. })) {
+            Some(index) => index,
+            None => {
+                self.show_status("No deletions to restore".to_string());
+                return Ok(());
+            }
+        };
+
+        let action = self.undo_stack.remove(index);
+        let mut restored_names = Vec::new();
+        if let UndoAction::Delete { deleted_files } = &action {
+            let mut count = 0;
+            let mut renamed = 0;
+            for (original, trash_path) in deleted_files {
+                if trash_path.exists() {
+                    let restore_to = self.restore_path(original);
+                    if let Err(e) = Self::move_path(trash_path, &restore_to) {
+                        return self.handle_undo_error(e, action.clone(), index);
+                    }
+                    count += 1;
+                    renamed += usize::from(restore_to != *original);
+                    if restore_to.parent() == Some(self.current_dir.as_path())
+                        && let Some(name) = restore_to.file_name().and_then(|n| n.to_str())
+                    {
+                        restored_names.push(name.to_string());
                     }
-                }
-                Err(e) => {
-                    return self.handle_undo_error(e, action_clone);
                 }
             }
-        } else {
-            self.show_status("Nothing to undo".to_string());
+            self.show_status(format!("Restored {} deleted item(s){}", count, Self::renamed_on_restore(renamed)));
+        }
+
+        self.load_directory()?;
+        if !restored_names.is_empty() {
+            self.select_items_by_name(&restored_names);
         }
         Ok(())
     }
 
-    fn handle_undo_error(&mut self, e: io::Error, action: UndoAction) -> io::Result<()> {
+    fn renamed_on_restore(renamed: usize) -> String {
+        if renamed == 0 {
+            String::new()
+        } else {
+            format!(", {} under a numbered name because the original is taken", renamed)
+        }
+    }
+
+    // index is where the action sat in the history before it was taken out to be undone
+    fn handle_undo_error(&mut self, e: io::Error, action: UndoAction, index: usize) -> io::Result<()> {
+        self.undo_stack.insert(index.min(self.undo_stack.len()), action.clone());
+
         // Check if this is a permission error
         let is_permission_error = e.kind() == io::ErrorKind::PermissionDenied
             || e.raw_os_error() == Some(13);
 
         if is_permission_error {
-            // Push the action back onto the stack
-            self.undo_stack.push(action.clone());
             // Prompt for sudo password
-            self.ui_mode = UIMode::PasswordPrompt {
-                prompt: "Permission denied. Enter sudo password:".to_string(),
-                password: String::new(),
-                pending_operation: Box::new(PendingOperation {
+            self.request_sudo(
+                "Permission denied. Enter sudo password:".to_string(),
+                PendingOperation {
                     items: Vec::new(),
                     destination: None,
                     operation: OperationType::Undo,
                     undo_action: Some(action),
-                }),
-            };
-            // Always return Ok for permission errors
-            Ok(())
+                },
+            )
         } else {
             // For non-permission errors, show as status and don't crash
             self.show_status(format!("Undo error: {}", e));
@@ -1271,8 +6925,20 @@ impl FileExplorer {
         }
     }
 
+    // Where an undo puts an item back: its old path, or a numbered one beside it if something new has taken that name
+    fn restore_path(&self, original: &Path) -> PathBuf {
+        let original = original.to_path_buf();
+        // symlink_metadata, so a dangling link in the way isn't overwritten either
+        if fs::symlink_metadata(&original).is_ok() {
+            self.get_unique_path(&original)
+        } else {
+            original
+        }
+    }
+
     fn get_unique_path(&self, dest_path: &PathBuf) -> PathBuf {
-        if !dest_path.exists() {
+        // symlink_metadata, so a dangling symlink counts as taken rather than being written through
+        if fs::symlink_metadata(dest_path).is_err() {
             return dest_path.clone();
         }
 
@@ -1293,17 +6959,18 @@ impl FileExplorer {
         loop {
             let new_name = format!("{} ({}){}", name, counter, ext);
             let new_path = parent.join(new_name);
-            if !new_path.exists() {
+            if fs::symlink_metadata(&new_path).is_err() {
                 return new_path;
             }
             counter += 1;
         }
     }
 
-    fn perform_file_operation_tracked(&self, items: &[PathBuf], destination: &PathBuf, is_move: bool) -> io::Result<(usize, UndoAction)> {
+    fn perform_file_operation_tracked(&self, items: &[PathBuf], destination: &PathBuf, is_move: bool, mismatches: &mut Vec<PathBuf>, skipped: &mut Vec<PathBuf>, failed_item: &mut Option<(PathBuf, PathBuf)>) -> io::Result<(usize, UndoAction)> {
         let mut count = 0;
         let mut tracked_operations = Vec::new();
         let mut copied_files = Vec::new();
+        let verify = self.config.verify_copies;
 
         for item in items {
             let file_name = item.file_name().ok_or_else(|| {
@@ -1312,24 +6979,35 @@ impl FileExplorer {
             let initial_dest_path = destination.join(file_name);
             // Get a unique path to avoid conflicts
             let dest_path = self.get_unique_path(&initial_dest_path);
+            *failed_item = Some((item.clone(), dest_path.clone()));
 
             if is_move {
                 fs::rename(item, &dest_path)?;
                 tracked_operations.push((item.clone(), dest_path.clone()));
             } else {
+                if fs::metadata(item).is_ok_and(|m| SpecialKind::of(&m.file_type()).is_some()) {
+                    // Copying would read from the pipe or device until it closes, possibly never
+                    skipped.push(item.clone());
+                    continue;
+                }
                 if item.is_dir() {
-                    self.copy_dir_recursive(item, &dest_path)?;
+                    self.copy_dir_recursive(item, &dest_path, verify, mismatches, skipped)?;
                 } else {
                     fs::copy(item, &dest_path)?;
+                    if !Self::verify_copy(item, &dest_path, verify)? {
+                        mismatches.push(dest_path.clone());
+                    }
                 }
                 copied_files.push(dest_path.clone());
             }
             count += 1;
         }
+        *failed_item = None;
 
         let undo_action = if is_move {
             UndoAction::Move {
                 moved_files: tracked_operations,
+                trashed_originals: Vec::new(),
             }
         } else {
             UndoAction::Copy { copied_files }
@@ -1338,7 +7016,7 @@ impl FileExplorer {
         Ok((count, undo_action))
     }
 
-    fn copy_dir_recursive(&self, src: &PathBuf, dst: &PathBuf) -> io::Result<()> {
+    fn copy_dir_recursive(&self, src: &PathBuf, dst: &PathBuf, verify: CopyVerification, mismatches: &mut Vec<PathBuf>, skipped: &mut Vec<PathBuf>) -> io::Result<()> {
         fs::create_dir_all(dst)?;
         for entry in fs::read_dir(src)? {
             let entry = entry?;
@@ -1347,14 +7025,71 @@ impl FileExplorer {
             let dst_path = dst.join(entry.file_name());
 
             if file_type.is_dir() {
-                self.copy_dir_recursive(&src_path, &dst_path)?;
+                self.copy_dir_recursive(&src_path, &dst_path, verify, mismatches, skipped)?;
+            } else if SpecialKind::of(&file_type).is_some() {
+                skipped.push(src_path);
             } else {
                 fs::copy(&src_path, &dst_path)?;
+                if !Self::verify_copy(&src_path, &dst_path, verify)? {
+                    mismatches.push(dst_path);
+                }
             }
         }
         Ok(())
     }
 
+    // verify_copy for every file below src; links were recreated rather than copied, so only files are compared
+    fn verify_tree(src: &Path, dst: &Path, verify: CopyVerification) -> io::Result<bool> {
+        if verify == CopyVerification::Off {
+            return Ok(true);
+        }
+        let metadata = fs::symlink_metadata(src)?;
+        if metadata.is_dir() {
+            for entry in fs::read_dir(src)? {
+                let entry = entry?;
+                if !Self::verify_tree(&entry.path(), &dst.join(entry.file_name()), verify)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        } else if metadata.is_file() {
+            Self::verify_copy(src, dst, verify)
+        } else {
+            Ok(true)
+        }
+    }
+
+    // True when the copy matches its source as far as the verification mode checks
+    fn verify_copy(src: &Path, dst: &Path, verify: CopyVerification) -> io::Result<bool> {
+        use std::io::Read;
+
+        if verify == CopyVerification::Off {
+            return Ok(true);
+        }
+        if fs::metadata(src)?.len() != fs::metadata(dst)?.len() {
+            return Ok(false);
+        }
+        if verify == CopyVerification::Size {
+            return Ok(true);
+        }
+
+        let mut src_file = io::BufReader::new(fs::File::open(src)?);
+        let mut dst_file = io::BufReader::new(fs::File::open(dst)?);
+        let mut src_buf = vec![0u8; 64 * 1024];
+        let mut dst_buf = vec![0u8; 64 * 1024];
+        loop {
+            let read = src_file.read(&mut src_buf)?;
+            if read == 0 {
+                // Sizes matched, so the destination is exhausted too
+                return Ok(true);
+            }
+            dst_file.read_exact(&mut dst_buf[..read])?;
+            if src_buf[..read] != dst_buf[..read] {
+                return Ok(false);
+            }
+        }
+    }
+
     fn perform_file_operation_sudo(&self, items: &[PathBuf], destination: &PathBuf, is_move: bool, password: &str) -> io::Result<usize> {
         // Validate password first to avoid cached credentials
         self.validate_sudo_password(password)?;
@@ -1401,6 +7136,15 @@ impl FileExplorer {
         Ok(count)
     }
 
+    // With cursor_only (the Alt variants of copy/cut/delete) a lingering selection is ignored
+    fn target_paths(&self, cursor_only: bool) -> Vec<PathBuf> {
+        if cursor_only {
+            self.entries.get(self.cursor_index).map(|entry| entry.path.clone()).into_iter().collect()
+        } else {
+            self.get_selected_paths()
+        }
+    }
+
     fn get_selected_paths(&self) -> Vec<PathBuf> {
         let indices = if self.selected_indices.is_empty() {
             vec![self.cursor_index]
@@ -1414,6 +7158,18 @@ impl FileExplorer {
             .collect()
     }
 
+    // "1048576", "512K", "100M" or "2G" (binary units, like format_file_size)
+    fn parse_file_size(input: &str) -> Option<u64> {
+        let input = input.trim();
+        let (number, multiplier) = match input.chars().last()?.to_ascii_uppercase() {
+            'K' => (&input[..input.len() - 1], 1024),
+            'M' => (&input[..input.len() - 1], 1024 * 1024),
+            'G' => (&input[..input.len() - 1], 1024 * 1024 * 1024),
+            _ => (input, 1),
+        };
+        number.trim().parse::<u64>().ok()?.checked_mul(multiplier).filter(|&size| size > 0)
+    }
+
     fn format_file_size(size: u64) -> String {
         const KB: u64 = 1024;
         const MB: u64 = KB * 1024;
@@ -1670,10 +7426,8 @@ impl FileExplorer {
 
         if let Ok(read_dir) = fs::read_dir(dir) {
             for entry in read_dir.flatten() {
-                if let (Ok(name), Ok(metadata)) = (
-                    entry.file_name().into_string(),
-                    entry.metadata()
-                ) {
+                if let Ok(metadata) = entry.metadata() {
+                    let name = entry.file_name().to_string_lossy().to_string();
                     // Skip hidden files if show_hidden is false
                     if !self.show_hidden && name.starts_with('.') {
                         continue;
@@ -1719,6 +7473,14 @@ impl FileExplorer {
         }
     }
 
+    fn start_fuzzy_find(&mut self) {
+        self.ui_mode = UIMode::FuzzyFind {
+            search_term: String::new(),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+    }
+
     fn perform_fuzzy_search(&self, search_term: &str) -> Vec<FuzzyMatch> {
         let mut results = Vec::new();
 
@@ -1749,12 +7511,113 @@ impl FileExplorer {
         0
     }
 
-    fn get_selected_total_size(&self) -> u64 {
-        self.selected_indices
-            .iter()
+    fn available_space(dir: &Path) -> Option<u64> {
+        // POSIX df output: header line, then "filesystem blocks used available capacity mount" in 1K blocks
+        let output = Command::new("df").arg("-Pk").arg(dir).output().ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let available_kb: u64 = stdout.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+        Some(available_kb * 1024)
+    }
+
+    // Fill a status bar template; unknown placeholders are left as written
+    fn format_status_template(&self, template: &str) -> String {
+        let entry = self.entries.get(self.cursor_index);
+        let mut result = String::new();
+        let mut rest = template;
+
+        while let Some(open) = rest.find('{') {
+            result.push_str(&rest[..open]);
+            let Some(close) = rest[open..].find('}') else {
+                rest = &rest[open..];
+                break;
+            };
+            let placeholder = &rest[open + 1..open + close];
+            let value = match placeholder {
+                "count" => Some(self.entries.len().to_string()),
+                "name" => Some(entry.map(|e| e.name.clone()).unwrap_or_default()),
+                "type" => Some(entry.map(|e| if e.is_dir { "Directory" } else { "File" }).unwrap_or("").to_string()),
+                "size" => Some(match entry {
+                    Some(e) if !e.is_dir => Self::format_file_size(self.current_item_size.unwrap_or(0)),
+                    _ => String::new(),
+                }),
+                "perms" => Some(entry.map(|e| Self::format_permissions(e.permissions, e.is_dir)).unwrap_or_default()),
+                "modified" => Some(entry.map(|e| Self::format_date(e.time)).unwrap_or_default()),
+                "media" => Some(self.current_item_media.clone().unwrap_or_default()),
+                "selected" => Some(self.selected_indices.len().to_string()),
+                "selected_size" => Some(self.format_selected_size()),
+                "hidden" => Some(self.hidden_count.to_string()),
+                "free" => Some(self.free_space.map(Self::format_file_size).unwrap_or_else(|| "?".to_string())),
+                "clipboard" => Some(self.clipboard.as_ref().map(|c| c.items.len()).unwrap_or(0).to_string()),
+                "dir" => Some(self.current_dir.display().to_string()),
+                _ => None,
+            };
+            match value {
+                Some(value) => result.push_str(&value),
+                None => result.push_str(&rest[open..=open + close]),
+            }
+            rest = &rest[open + close + 1..];
+        }
+
+        result.push_str(rest);
+        result
+    }
+
+    // Total and whether some directory sizes are still being calculated
+    fn get_selected_total_size(&self) -> (u64, bool) {
+        let recursive = self.config.recursive_selection_size;
+        let mut total = 0;
+        let mut pending = false;
+        for entry in self.selected_indices.iter().filter_map(|&i| self.entries.get(i)) {
+            if recursive && entry.is_dir && !entry.is_symlink {
+                match self.dir_sizes.get(&entry.path) {
+                    Some(Some(size)) => total += size,
+                    Some(None) => {} // Cancelled; counted as nothing rather than waiting forever
+                    None => pending = true,
+                }
+            } else {
+                total += self.size_cache.get(&entry.path)
+                    .copied()
+                    .unwrap_or_else(|| Self::get_file_size(&entry.path));
+            }
+        }
+        (total, pending)
+    }
+
+    fn format_selected_size(&self) -> String {
+        let (total, pending) = self.get_selected_total_size();
+        let computing = if pending { " (computing…)" } else { "" };
+        format!("{}{}", Self::format_file_size(total), computing)
+    }
+
+    // Size selected directories in the background, through the same queue as the Directory Size sort
+    fn queue_selected_size_scans(&mut self) {
+        if !self.config.recursive_selection_size || self.selected_indices.is_empty() {
+            return;
+        }
+        let scanning = self.size_scan.as_ref().map(|scan| scan.path.clone());
+        let unsized_dirs: Vec<PathBuf> = self.selected_indices.iter()
             .filter_map(|&i| self.entries.get(i))
-            .map(|entry| Self::get_file_size(&entry.path))
-            .sum()
+            .filter(|entry| entry.is_dir && !entry.is_symlink && !self.dir_sizes.contains_key(&entry.path))
+            .filter(|entry| scanning.as_ref() != Some(&entry.path) && !self.size_scan_queue.contains(&entry.path))
+            .map(|entry| entry.path.clone())
+            .collect();
+        self.size_scan_queue.extend(unsized_dirs);
+        if self.size_scan.is_none()
+            && let Some(path) = self.size_scan_queue.pop()
+        {
+            self.spawn_size_scan(path);
+        }
+    }
+
+    fn cache_selected_sizes(&mut self) {
+        // Fill the size cache for the selection so redraws during a drag don't re-stat every item
+        for &i in &self.selected_indices {
+            if let Some(entry) = self.entries.get(i) {
+                self.size_cache
+                    .entry(entry.path.clone())
+                    .or_insert_with(|| Self::get_file_size(&entry.path));
+            }
+        }
     }
 
     fn update_current_item_size(&mut self) {
@@ -1767,105 +7630,632 @@ impl FileExplorer {
                 self.size_cache.insert(path.clone(), size);
                 self.current_item_size = Some(size);
             }
+
+            // Media info is only read for the item under the cursor, once per directory visit
+            self.current_item_media = if entry.is_dir || entry.special.is_some() {
+                None
+            } else {
+                self.media_info_cache
+                    .entry(path.clone())
+                    .or_insert_with(|| Self::read_media_info(path))
+                    .clone()
+            };
         } else {
             self.current_item_size = None;
+            self.current_item_media = None;
+        }
+    }
+
+    fn read_media_info(path: &Path) -> Option<String> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        let (width, height) = match extension.as_str() {
+            "png" => Self::png_dimensions(path),
+            "gif" => Self::gif_dimensions(path),
+            "jpg" | "jpeg" => Self::jpeg_dimensions(path),
+            _ => None,
+        }?;
+        Some(format!("{}x{}", width, height))
+    }
+
+    fn read_header(path: &Path, len: usize) -> Option<Vec<u8>> {
+        use std::io::Read;
+
+        let mut header = vec![0u8; len];
+        fs::File::open(path).ok()?.read_exact(&mut header).ok()?;
+        Some(header)
+    }
+
+    fn png_dimensions(path: &Path) -> Option<(u32, u32)> {
+        // 8-byte signature, then the IHDR chunk: length, "IHDR", width, height (big-endian)
+        let header = Self::read_header(path, 24)?;
+        if &header[..8] != b"\x89PNG\r\n\x1a\n" || &header[12..16] != b"IHDR" {
+            return None;
+        }
+        let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+        let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+        Some((width, height))
+    }
+
+    fn gif_dimensions(path: &Path) -> Option<(u32, u32)> {
+        // "GIF87a"/"GIF89a", then the logical screen width and height (little-endian)
+        let header = Self::read_header(path, 10)?;
+        if &header[..4] != b"GIF8" {
+            return None;
+        }
+        let width = u16::from_le_bytes([header[6], header[7]]) as u32;
+        let height = u16::from_le_bytes([header[8], header[9]]) as u32;
+        Some((width, height))
+    }
+
+    fn jpeg_dimensions(path: &Path) -> Option<(u32, u32)> {
+        use std::io::{BufReader, Read, Seek, SeekFrom};
+
+        // Walk the marker segments until a start-of-frame, skipping everything else by length
+        let mut reader = BufReader::new(fs::File::open(path).ok()?);
+        let mut buf = [0u8; 2];
+        reader.read_exact(&mut buf).ok()?;
+        if buf != [0xFF, 0xD8] {
+            return None;
+        }
+
+        loop {
+            reader.read_exact(&mut buf).ok()?;
+            if buf[0] != 0xFF {
+                return None;
+            }
+            let marker = buf[1];
+            if marker == 0xFF || (0xD0..=0xD9).contains(&marker) || marker == 0x01 {
+                // Fill bytes and standalone markers carry no length
+                continue;
+            }
+
+            reader.read_exact(&mut buf).ok()?;
+            let segment_len = u16::from_be_bytes(buf) as i64;
+
+            // SOF0-SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+            if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                let mut frame = [0u8; 5];
+                reader.read_exact(&mut frame).ok()?;
+                let height = u16::from_be_bytes([frame[1], frame[2]]) as u32;
+                let width = u16::from_be_bytes([frame[3], frame[4]]) as u32;
+                return Some((width, height));
+            }
+
+            reader.seek(SeekFrom::Current(segment_len - 2)).ok()?;
+        }
+    }
+
+    // One timestamped line in the --log file; write errors are ignored so logging never gets in the way
+    fn log(&mut self, message: &str) {
+        if let Some(file) = &mut self.log_file {
+            let now = SystemTime::now();
+            let secs = now.duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            let _ = writeln!(file, "{}:{:02} {}", Self::format_date(now), secs % 60, message);
+        }
+    }
+
+    fn show_status(&mut self, message: String) {
+        self.log(&format!("status: {}", message));
+        self.log_message(message.clone());
+        self.status_logged_last = true;
+        self.status_message = Some(message);
+    }
+
+    // Extends the message just shown ("Pasted 2 item(s), skipped 1 missing"); the log keeps only the final version
+    fn amend_status(&mut self, message: String) {
+        if !self.status_logged_last {
+            return self.show_status(message);
+        }
+        self.log(&format!("status: {}", message));
+        if let Some(last) = self.message_log.last_mut() {
+            *last = (SystemTime::now(), message.clone());
+        }
+        self.status_message = Some(message);
+    }
+
+    // Adds to the F6 message log without showing anything in the status bar
+    fn log_message(&mut self, message: String) {
+        const LOG_LIMIT: usize = 500;
+
+        self.message_log.push((SystemTime::now(), message));
+        self.status_logged_last = false;
+        if self.message_log.len() > LOG_LIMIT {
+            self.message_log.remove(0);
+        }
+    }
+
+    fn toggle_message_log(&mut self) {
+        if matches!(self.ui_mode, UIMode::MessageLog { .. }) {
+            self.ui_mode = UIMode::Normal;
+        } else {
+            // Open at the newest messages
+            self.ui_mode = UIMode::MessageLog { scroll: usize::MAX };
+        }
+    }
+
+    fn refuse_in_read_only(&mut self) {
+        self.show_status("Read-only mode: files cannot be modified".to_string());
+    }
+
+    fn clear_status(&mut self) {
+        self.status_message = None;
+        if matches!(self.ui_mode, UIMode::StatusMessage { .. }) {
+            self.ui_mode = UIMode::Normal;
+        }
+    }
+
+    fn filtered_help_lines(filter: &str) -> Vec<&'static str> {
+        if filter.is_empty() {
+            return HELP_LINES.to_vec();
+        }
+
+        // Keep matching shortcut lines, each preceded by the heading of its section
+        let filter_lower = filter.to_lowercase();
+        let mut lines = Vec::new();
+        let mut section: Option<&'static str> = None;
+        for &line in HELP_LINES {
+            if line.ends_with(':') && !line.starts_with(' ') {
+                section = Some(line);
+            } else if line.starts_with(' ') && line.to_lowercase().contains(&filter_lower) {
+                if let Some(heading) = section.take() {
+                    if !lines.is_empty() {
+                        lines.push("");
+                    }
+                    lines.push(heading);
+                }
+                lines.push(line);
+            }
+        }
+
+        if lines.is_empty() {
+            lines.push("No matching shortcuts");
+        }
+        lines
+    }
+
+    // Copy full path of the cursor item to the clipboard
+    fn copy_cursor_path(&mut self) {
+        if let Some(entry) = self.entries.get(self.cursor_index) {
+            let full_path = entry.path.display().to_string();
+            if let Ok(mut clipboard) = arboard::Clipboard::new() {
+                if clipboard.set_text(&full_path).is_ok() {
+                    self.show_status(format!("Copied path: {}", full_path));
+                } else {
+                    self.show_status("Failed to copy path to clipboard".to_string());
+                }
+            } else {
+                self.show_status("Failed to access clipboard".to_string());
+            }
+        }
+    }
+
+    fn toggle_help(&mut self) {
+        if matches!(self.ui_mode, UIMode::Help { .. }) {
+            self.ui_mode = UIMode::Normal;
+        } else {
+            self.ui_mode = UIMode::Help {
+                scroll: 0,
+                filter: String::new(),
+            };
+        }
+    }
+
+    fn get_ancestors(&self) -> Vec<PathBuf> {
+        let mut ancestors = Vec::new();
+        let mut current = self.current_dir.clone();
+
+        ancestors.push(current.clone());
+
+        while let Some(parent) = current.parent() {
+            if parent == current {
+                break;
+            }
+            current = parent.to_path_buf();
+            ancestors.insert(0, current.clone());
+        }
+
+        ancestors
+    }
+
+    fn get_dir_max_modified(path: &PathBuf, max_depth: usize) -> SystemTime {
+        Self::get_dir_max_modified_recursive(path, max_depth, 0)
+    }
+
+    fn get_dir_max_modified_recursive(path: &PathBuf, max_depth: usize, current_depth: usize) -> SystemTime {
+        let mut max_time = SystemTime::UNIX_EPOCH;
+
+        // Get the directory's own modification time
+        if let Ok(metadata) = fs::metadata(path) {
+            if let Ok(modified) = metadata.modified() {
+                max_time = modified;
+            }
+        }
+
+        // If we've reached max depth, return the directory's own time
+        if current_depth >= max_depth {
+            return max_time;
+        }
+
+        // Scan immediate files/subdirectories
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                if let Ok(metadata) = entry.metadata() {
+                    if let Ok(modified) = metadata.modified() {
+                        if metadata.is_file() {
+                            // For files, just check the modified time
+                            if modified > max_time {
+                                max_time = modified;
+                            }
+                        } else if metadata.is_dir() && current_depth + 1 <= max_depth {
+                            // For subdirectories, recurse if we haven't hit depth limit
+                            let sub_max = Self::get_dir_max_modified_recursive(&entry.path(), max_depth, current_depth + 1);
+                            if sub_max > max_time {
+                                max_time = sub_max;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        max_time
+    }
+
+    // Natural order for each sort key; ties fall back to the name so the order is stable
+    fn compare_entries(sort_mode: SortMode, a: &DirEntry, b: &DirEntry) -> std::cmp::Ordering {
+        let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        let extension = |entry: &DirEntry| {
+            entry.path.extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default()
+        };
+
+        match sort_mode {
+            SortMode::Name => by_name(),
+            // Newest first
+            SortMode::Date => b.time.cmp(&a.time),
+            // Largest first
+            SortMode::Size => b.size.cmp(&a.size).then_with(by_name),
+            SortMode::Extension => extension(a).cmp(&extension(b)).then_with(by_name),
+            // Group files that share an icon (source, images, archives, ...)
+            SortMode::Type => Self::get_file_icon(&a.name, a.is_dir, a.permissions)
+                .cmp(Self::get_file_icon(&b.name, b.is_dir, b.permissions))
+                .then_with(by_name),
+            SortMode::NameLength => a.name.chars().count().cmp(&b.name.chars().count()).then_with(by_name),
+            // Directories are ordered in sort_entries, which has the calculated sizes
+            SortMode::DirSize => by_name(),
+        }
+    }
+
+    fn open_sort_menu(&mut self) {
+        let selected_index = SortMode::ALL.iter()
+            .position(|mode| *mode == self.sort_mode)
+            .unwrap_or(0);
+        self.ui_mode = UIMode::SortMenu { selected_index };
+    }
+
+    fn apply_sort_mode(&mut self, sort_mode: SortMode) -> io::Result<()> {
+        self.ui_mode = UIMode::Normal;
+        self.sort_mode = sort_mode;
+
+        let direction = if self.sort_reverse { " (reversed)" } else { "" };
+        self.show_status(format!("Sorting by: {}{}", sort_mode.label(), direction));
+
+        self.load_directory()?;
+        Ok(())
+    }
+
+    fn cycle_type_filter(&mut self) -> io::Result<()> {
+        self.type_filter = match self.type_filter {
+            TypeFilter::All => TypeFilter::DirsOnly,
+            TypeFilter::DirsOnly => TypeFilter::FilesOnly,
+            TypeFilter::FilesOnly => TypeFilter::All,
+        };
+
+        let filter_name = match self.type_filter {
+            TypeFilter::All => "All items",
+            TypeFilter::DirsOnly => "Directories only",
+            TypeFilter::FilesOnly => "Files only",
+        };
+        self.show_status(format!("Showing: {}", filter_name));
+
+        // Indices from the previous listing no longer line up with the filtered entries
+        self.dir_memory.remove(&self.current_dir);
+        self.load_directory()?;
+        Ok(())
+    }
+
+    fn toggle_changed_since(&mut self) -> io::Result<()> {
+        if self.changed_since.take().is_some() {
+            self.show_status("Showing items of any age again".to_string());
+        } else {
+            let now = SystemTime::now();
+            self.changed_since = Some(now);
+            self.show_status(format!(
+                "Showing only items changed after {} (Ctrl+L refreshes, Alt+D clears)",
+                Self::format_date(now)
+            ));
+        }
+
+        self.dir_memory.remove(&self.current_dir);
+        self.load_directory()?;
+        Ok(())
+    }
+
+    fn show_item_info(&mut self) {
+        if let Some(entry) = self.entries.get(self.cursor_index) {
+            let path = entry.path.clone();
+            // A directory's size comes from the background scan; poll_size_scan fills it in when it's done
+            let dir_size = self.dir_sizes.get(&path).copied().flatten();
+            if entry.is_dir && dir_size.is_none() {
+                self.size_dir_next(path.clone());
+            }
+            let lines = Self::gather_item_info(&path, dir_size);
+            self.ui_mode = UIMode::ItemInfo { path, lines };
+        }
+    }
+
+    #[cfg(feature = "xattr")]
+    fn xattr_info_lines(path: &Path) -> Vec<String> {
+        let names = match xattr::list(path) {
+            Ok(names) => names,
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => return Vec::new(),
+            Err(e) => return vec![format!("Attributes:  unreadable ({})", e)],
+        };
+
+        let mut lines = Vec::new();
+        for name in names {
+            let value = match xattr::get(path, &name) {
+                Ok(Some(value)) => match String::from_utf8(value) {
+                    Ok(text) => Self::truncate_to_width(&text.replace('\n', " "), 60),
+                    Err(e) => format!("({} bytes of binary data)", e.as_bytes().len()),
+                },
+                Ok(None) => String::new(),
+                Err(e) => format!("unreadable ({})", e),
+            };
+            let label = if lines.is_empty() { "Attributes:" } else { "" };
+            lines.push(format!("{:<12} {} = {}", label, name.to_string_lossy(), value));
+        }
+        lines
+    }
+
+    #[cfg(not(feature = "xattr"))]
+    fn xattr_info_lines(_path: &Path) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[cfg(feature = "xattr")]
+    fn read_tags(path: &Path) -> io::Result<Vec<String>> {
+        let value = xattr::get_deref(path, TAGS_XATTR)?.unwrap_or_default();
+        Ok(String::from_utf8_lossy(&value)
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    #[cfg(feature = "xattr")]
+    fn write_tags(path: &Path, tags: &[String]) -> io::Result<()> {
+        if tags.is_empty() {
+            return match xattr::remove_deref(path, TAGS_XATTR) {
+                // Nothing to remove when the attribute was never set
+                Err(_) if xattr::get_deref(path, TAGS_XATTR).is_ok_and(|value| value.is_none()) => Ok(()),
+                result => result,
+            };
         }
+        xattr::set_deref(path, TAGS_XATTR, tags.join(",").as_bytes())
     }
 
-    fn show_status(&mut self, message: String) {
-        self.status_message = Some(message);
+    #[cfg(not(feature = "xattr"))]
+    fn read_tags(_path: &Path) -> io::Result<Vec<String>> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "built without the xattr feature"))
     }
 
-    fn clear_status(&mut self) {
-        self.status_message = None;
-        if matches!(self.ui_mode, UIMode::StatusMessage { .. }) {
-            self.ui_mode = UIMode::Normal;
+    #[cfg(not(feature = "xattr"))]
+    fn write_tags(_path: &Path, _tags: &[String]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "built without the xattr feature"))
+    }
+
+    fn start_edit_tag(&mut self) {
+        let Some(entry) = self.entries.get(self.cursor_index) else {
+            return;
+        };
+        let path = entry.path.clone();
+        match Self::read_tags(&path) {
+            Ok(tags) => self.ui_mode = UIMode::EditTag { path, tags, tag: String::new() },
+            Err(e) => self.show_status(format!("Cannot read tags of '{}': {}", entry.name, e)),
         }
     }
 
-    fn toggle_help(&mut self) {
-        if matches!(self.ui_mode, UIMode::Help) {
-            self.ui_mode = UIMode::Normal;
-        } else {
-            self.ui_mode = UIMode::Help;
+    fn toggle_tag(&mut self, path: &Path, mut tags: Vec<String>, tag: &str) -> io::Result<()> {
+        let tag = tag.trim();
+        if tag.is_empty() || tag.contains(',') {
+            self.show_status("Tags cannot be empty or contain commas".to_string());
+            return Ok(());
         }
+
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let action = if let Some(index) = tags.iter().position(|existing| existing == tag) {
+            tags.remove(index);
+            "Removed"
+        } else {
+            tags.push(tag.to_string());
+            "Added"
+        };
+        Self::write_tags(path, &tags)?;
+        self.log(&format!("tag {} {}", path.display(), tags.join(",")));
+        self.show_status(format!("{} tag '{}' on '{}'", action, tag, name));
+        Ok(())
     }
 
-    fn get_ancestors(&self) -> Vec<PathBuf> {
-        let mut ancestors = Vec::new();
-        let mut current = self.current_dir.clone();
+    // dir_size is a directory's recursive size when it is known already
+    fn gather_item_info(path: &Path, dir_size: Option<u64>) -> Vec<String> {
+        use std::os::unix::fs::MetadataExt;
 
-        ancestors.push(current.clone());
+        let mut lines = vec![format!("Path:        {}", path.display())];
 
-        while let Some(parent) = current.parent() {
-            if parent == current {
-                break;
+        // symlink_metadata describes the entry itself, metadata the file a link points to
+        let link_metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                lines.push(format!("Error:       {}", e));
+                return lines;
             }
-            current = parent.to_path_buf();
-            ancestors.insert(0, current.clone());
+        };
+        if link_metadata.file_type().is_symlink() {
+            let target = fs::read_link(path)
+                .map(|t| t.display().to_string())
+                .unwrap_or_else(|e| format!("unreadable ({})", e));
+            let broken = if path.exists() { "" } else { " (broken)" };
+            lines.push(format!("Link target: {}{}", target, broken));
         }
+        if let Ok(canonical) = fs::canonicalize(path)
+            && canonical != path
+        {
+            lines.push(format!("Resolved:    {}", canonical.display()));
+        }
+        let metadata = fs::metadata(path).unwrap_or(link_metadata);
 
-        ancestors
+        let kind = if metadata.is_dir() {
+            "Directory"
+        } else if metadata.is_file() {
+            "File"
+        } else {
+            SpecialKind::of(&metadata.file_type()).map(SpecialKind::label).unwrap_or("Special file")
+        };
+        lines.push(format!("Type:        {}", kind));
+
+        match (metadata.is_dir(), dir_size) {
+            (true, Some(size)) => lines.push(format!("Size:        {} ({} bytes) (recursive)", Self::format_file_size(size), size)),
+            (true, None) => lines.push("Size:        calculating…".to_string()),
+            (false, _) => lines.push(format!("Size:        {} ({} bytes)", Self::format_file_size(metadata.len()), metadata.len())),
+        }
+
+        let mode = metadata.permissions().mode();
+        lines.push(format!(
+            "Permissions: {} ({:o})",
+            Self::format_permissions(mode, metadata.is_dir()),
+            mode & 0o7777
+        ));
+        lines.push(format!(
+            "Owner:       {} ({})",
+            Self::lookup_id_name("/etc/passwd", metadata.uid()).unwrap_or_else(|| "?".to_string()),
+            metadata.uid()
+        ));
+        lines.push(format!(
+            "Group:       {} ({})",
+            Self::lookup_id_name("/etc/group", metadata.gid()).unwrap_or_else(|| "?".to_string()),
+            metadata.gid()
+        ));
+
+        let format_time = |time: io::Result<SystemTime>| {
+            time.map(Self::format_date).unwrap_or_else(|_| "unavailable".to_string())
+        };
+        lines.push(format!("Created:     {}", format_time(metadata.created())));
+        lines.push(format!("Modified:    {}", format_time(metadata.modified())));
+        lines.push(format!("Accessed:    {}", format_time(metadata.accessed())));
+        lines.extend(Self::xattr_info_lines(path));
+
+        lines
     }
 
-    fn get_dir_max_modified(path: &PathBuf, max_depth: usize) -> SystemTime {
-        Self::get_dir_max_modified_recursive(path, max_depth, 0)
+    fn lookup_id_name(database: &str, id: u32) -> Option<String> {
+        // /etc/passwd and /etc/group both start with name:password:id
+        let contents = fs::read_to_string(database).ok()?;
+        contents.lines().find_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let line_id: u32 = fields.nth(1)?.parse().ok()?;
+            (line_id == id).then(|| name.to_string())
+        })
     }
 
-    fn get_dir_max_modified_recursive(path: &PathBuf, max_depth: usize, current_depth: usize) -> SystemTime {
-        let mut max_time = SystemTime::UNIX_EPOCH;
+    fn toggle_title_stats(&mut self) {
+        self.title_stats = !self.title_stats;
+        let state = if self.title_stats { "on" } else { "off" };
+        self.show_status(format!("Title bar stats: {}", state));
+    }
 
-        // Get the directory's own modification time
-        if let Ok(metadata) = fs::metadata(path) {
-            if let Ok(modified) = metadata.modified() {
-                max_time = modified;
+    fn toggle_key_hints(&mut self) {
+        self.key_hints = !self.key_hints;
+        let state = if self.key_hints { "on" } else { "off" };
+        self.show_status(format!("Key hints: {}", state));
+    }
+
+    // The few keys that matter most in the current mode, for the hint line
+    fn key_hints_line(&self) -> &'static str {
+        match &self.ui_mode {
+            UIMode::Normal | UIMode::StatusMessage { .. } if !self.selected_indices.is_empty() => {
+                "Ctrl+C copy • Ctrl+X cut • Delete delete • Alt+R bulk rename • Ctrl+Space toggle • [ ] jump"
+            }
+            UIMode::Normal | UIMode::StatusMessage { .. } => {
+                "Enter open • ← back • Shift+↑/↓ select • Ctrl+V paste • Ctrl+N new • Ctrl+P commands • F1 help"
             }
+            UIMode::PasswordPrompt { .. } => "Enter submit • Esc cancel",
+            UIMode::ConfirmDelete { .. } | UIMode::ConfirmPaste { .. } => "y confirm • n cancel • ↑/↓ scroll",
+            UIMode::ConfirmOverwrite { .. } | UIMode::ConfirmCrossDeviceMove { .. } => "y confirm • n cancel",
+            UIMode::SanitizeNames { .. } => "s spaces • l lowercase • a ASCII • Enter apply • Esc cancel",
+            UIMode::ConfirmBatchConflicts { .. } => "o overwrite • s skip • k keep both • Esc cancel",
+            UIMode::CreateNew { .. } => "f file • d directory • Enter create • Esc cancel",
+            UIMode::RenameItem { .. } => "Enter rename • Ctrl+A select all • ↑ last input • Esc cancel",
+            UIMode::SplitFile { .. } | UIMode::EditTag { .. } => "Enter apply • Esc cancel",
+            UIMode::JumpToNumber { .. } => "type a number • Enter jump • Esc cancel",
+            UIMode::ExportListing { .. } => "t text • c CSV • r tree • empty name copies • Esc cancel",
+            UIMode::ContextMenu { .. } | UIMode::SortMenu { .. } => "↑/↓ choose • Enter apply • Esc close",
+            UIMode::Help { .. } => "type to search • ↑/↓ PgUp/PgDn scroll • Esc close",
+            UIMode::MessageLog { .. } | UIMode::QueueSummary { .. } => "↑/↓ PgUp/PgDn scroll • Esc close",
+            UIMode::ItemInfo { .. } => "Esc close",
+            UIMode::FuzzyFind { .. } => "type to search • ↑/↓ choose • Enter go • Ctrl+D copy path • Esc close",
+            UIMode::RecentFiles { .. } => "↑/↓ choose • Enter go to file • Esc close",
+            UIMode::CommandPalette { .. } => "type to filter • ↑/↓ choose • Enter run • Esc close",
         }
+    }
 
-        // If we've reached max depth, return the directory's own time
-        if current_depth >= max_depth {
-            return max_time;
-        }
+    fn explorer_title(&self, width: usize) -> String {
+        let prefix = if self.read_only { "File Explorer [read-only]: " } else { "File Explorer: " };
+        let stats = if self.title_stats {
+            let mut parts = vec![format!("{} items", self.entries.len())];
+            if self.hidden_count > 0 {
+                parts.push(format!("{} hidden", self.hidden_count));
+            }
+            parts.push(Self::format_file_size(self.listing_size));
+            format!("  [{}]", parts.join(", "))
+        } else {
+            String::new()
+        };
 
-        // Scan immediate files/subdirectories
-        if let Ok(entries) = fs::read_dir(path) {
-            for entry in entries.flatten() {
-                if let Ok(metadata) = entry.metadata() {
-                    if let Ok(modified) = metadata.modified() {
-                        if metadata.is_file() {
-                            // For files, just check the modified time
-                            if modified > max_time {
-                                max_time = modified;
-                            }
-                        } else if metadata.is_dir() && current_depth + 1 <= max_depth {
-                            // For subdirectories, recurse if we haven't hit depth limit
-                            let sub_max = Self::get_dir_max_modified_recursive(&entry.path(), max_depth, current_depth + 1);
-                            if sub_max > max_time {
-                                max_time = sub_max;
-                            }
-                        }
-                    }
+        // Drop leading path components first so the stats and the deepest directories stay visible
+        let path = self.current_dir.display().to_string();
+        let available = width.saturating_sub(prefix.width() + stats.width());
+        let path = if path.width() > available {
+            let mut kept: Vec<&str> = Vec::new();
+            let mut used = 1; // The leading ellipsis
+            for grapheme in path.graphemes(true).rev() {
+                if used + grapheme.width() > available {
+                    break;
                 }
+                used += grapheme.width();
+                kept.push(grapheme);
             }
-        }
+            kept.reverse();
+            format!("…{}", kept.concat())
+        } else {
+            path
+        };
 
-        max_time
+        format!("{}{}{}", prefix, path, stats)
     }
 
-    fn toggle_sort_mode(&mut self) -> io::Result<()> {
-        self.sort_mode = match self.sort_mode {
-            SortMode::Name => SortMode::Date,
-            SortMode::Date => SortMode::Name,
-        };
-
-        let mode_name = match self.sort_mode {
-            SortMode::Name => "Name",
-            SortMode::Date => "Date Modified",
-        };
-        self.show_status(format!("Sorting by: {}", mode_name));
+    fn toggle_age_colors(&mut self) {
+        self.age_colored_timestamps = !self.age_colored_timestamps;
+        let state = if self.age_colored_timestamps { "on" } else { "off" };
+        self.show_status(format!("Age-colored timestamps: {}", state));
+    }
 
-        self.load_directory()?;
-        Ok(())
+    fn cycle_theme(&mut self) {
+        self.theme = self.theme.next();
+        self.show_status(format!("Theme: {}", self.theme.name));
     }
 
     fn toggle_hidden(&mut self) -> io::Result<()> {
@@ -1883,16 +8273,84 @@ impl FileExplorer {
     }
 }
 
+impl Drop for FileExplorer {
+    fn drop(&mut self) {
+        // A reused sudo session should not outlive the application
+        if self.config.sudo_keep_session {
+            let _ = Command::new("sudo")
+                .arg("-k")
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status();
+        }
+    }
+}
+
+// Normal-mode keys that modify files (or the clipboard feeding a paste); refused in read-only mode
+fn is_mutating_key(code: KeyCode, ctrl: bool, alt: bool) -> bool {
+    match code {
+        KeyCode::Delete => true,
+        KeyCode::Char('c' | 'x' | 'n' | 'z' | 'u') if ctrl => true,
+        KeyCode::Char('v' | 'V' | 'r' | 'R') => ctrl || alt,
+        KeyCode::Char('m' | 'n' | 'p' | 'j' | 'f' | 'c' | 'a' | 'x' | 'b') => alt,
+        KeyCode::Char('d') => !ctrl && !alt, // dd
+        _ => false,
+    }
+}
+
+// Hand the terminal to an interactive program (editor, pager, ...) and take it back afterwards
+fn run_in_foreground<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    command: &mut Command,
+) -> io::Result<std::process::ExitStatus> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+
+    let status = command.status();
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+
+    status
+}
+
+// Alt+R: rename the selection by editing its names in $EDITOR
+fn bulk_rename_in_editor<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, explorer: &mut FileExplorer) -> io::Result<()> {
+    if let Some((items, list_path)) = explorer.write_bulk_rename_list()? {
+        let status = run_in_foreground(terminal, &mut FileExplorer::editor_command(&list_path));
+        explorer.apply_bulk_rename(&items, &list_path, status)?;
+    }
+    Ok(())
+}
+
+// Alt+O: edit the config file in $EDITOR and apply it on return
+fn edit_config_in_editor<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, explorer: &mut FileExplorer) -> io::Result<()> {
+    if let Some(config_path) = explorer.config_file_for_editing()? {
+        let status = run_in_foreground(terminal, &mut FileExplorer::editor_command(&config_path));
+        explorer.reload_config(status)?;
+    }
+    Ok(())
+}
+
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     mut explorer: FileExplorer,
 ) -> io::Result<()> {
     loop {
+        explorer.poll_task()?;
+        explorer.poll_size_scan()?;
+        explorer.poll_recent_scan();
+        explorer.expire_failed_marks();
+        explorer.queue_selected_size_scans();
+
         terminal.draw(|f| {
             let area = f.area();
+            let theme = explorer.theme;
 
             let chunks = match &explorer.ui_mode {
-                UIMode::Normal | UIMode::StatusMessage { .. } | UIMode::PasswordPrompt { .. } | UIMode::ConfirmDelete { .. } => Layout::default()
+                UIMode::Normal | UIMode::StatusMessage { .. } | UIMode::PasswordPrompt { .. } | UIMode::ConfirmDelete { .. } | UIMode::ConfirmPaste { .. } | UIMode::SanitizeNames { .. } | UIMode::ConfirmBatchConflicts { .. } | UIMode::ConfirmOverwrite { .. } | UIMode::ConfirmCrossDeviceMove { .. } | UIMode::ContextMenu { .. } => Layout::default()
                     .direction(Direction::Vertical)
                     .constraints([
                         Constraint::Min(3),
@@ -1911,7 +8369,16 @@ fn run_app<B: ratatui::backend::Backend>(
                     .to_vec(),
             };
 
-            let main_area = chunks[0];
+            // The hint line takes the bottom row of the listing, right above the status bar
+            let (main_area, hint_area) = if explorer.key_hints {
+                let split = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(1)])
+                    .split(chunks[0]);
+                (split[0], Some(split[1]))
+            } else {
+                (chunks[0], None)
+            };
             let status_bar_area = chunks[1];
             let visible_height = main_area.height.saturating_sub(2) as usize;
             let terminal_width = main_area.width as usize;
@@ -1920,6 +8387,7 @@ fn run_app<B: ratatui::backend::Backend>(
             explorer.terminal_width = terminal_width;
 
             // Check if we're in fuzzy find mode
+            let mut pinned_lines = 0;
             let (tree_items, list_state, title) = if let UIMode::FuzzyFind { search_term, matches, selected_index } = &explorer.ui_mode {
                 // Render fuzzy find results (best match at bottom)
                 let fuzzy_items: Vec<ListItem> = matches
@@ -1933,9 +8401,9 @@ fn run_app<B: ratatui::backend::Backend>(
                         // Build spans with highlighted matched characters
                         let mut spans = vec![Span::raw(format!("{} ", icon))];
 
-                        let grey_color = Color::Rgb(120, 120, 117);  // Grey for non-matched
-                        let green_color = Color::Rgb(140, 180, 120); // Green for matched
-                        let bg_color = if is_selected { Some(Color::Rgb(50, 50, 50)) } else { None };
+                        let grey_color = theme.timestamp_fg;  // Grey for non-matched
+                        let green_color = theme.match_fg; // Green for matched
+                        let bg_color = if is_selected { Some(theme.cursor_bg) } else { None };
 
                         let chars: Vec<char> = fuzzy_match.display_path.chars().collect();
                         let mut last_pos = 0;
@@ -1978,7 +8446,7 @@ fn run_app<B: ratatui::backend::Backend>(
 
                         // Right-align permissions with 1 char buffer from right edge
                         let icon_width = 2; // Icon char + space
-                        let path_width = fuzzy_match.display_path.chars().count();
+                        let path_width = fuzzy_match.display_path.width();
                         let perms_width = 10; // "-rwxr-xr-x" is always 10 chars
                         let buffer = 1;
 
@@ -1998,7 +8466,7 @@ fn run_app<B: ratatui::backend::Backend>(
 
                         // Add permissions
                         let perms_str = FileExplorer::format_permissions(fuzzy_match.permissions, fuzzy_match.is_dir);
-                        let perm_color = Color::Rgb(120, 120, 117);  // Grey for permissions
+                        let perm_color = theme.timestamp_fg;  // Grey for permissions
                         let mut perm_style = Style::default().fg(perm_color);
                         if let Some(bg) = bg_color {
                             perm_style = perm_style.bg(bg);
@@ -2049,59 +8517,100 @@ fn run_app<B: ratatui::backend::Backend>(
 
                 let title = format!("Fuzzy Find: {} ({} matches)", search_term, matches.len());
                 (fuzzy_items, list_state, title)
+            } else if let UIMode::RecentFiles { files, selected_index } = &explorer.ui_mode {
+                // Newest at the top, each path relative to the current directory with its date at the right edge
+                let recent_items: Vec<ListItem> = files
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (path, modified))| {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let icon = FileExplorer::get_file_icon(&name, false, 0);
+                        let display_path = path.strip_prefix(&explorer.current_dir).unwrap_or(path).display().to_string();
+                        let date = FileExplorer::format_date(*modified);
+                        let used_width = 2 + display_path.width() + date.width() + 1;
+                        let padding = terminal_width.saturating_sub(used_width).max(1);
+
+                        let mut style = Style::default().fg(theme.file_fg);
+                        let mut date_style = Style::default().fg(theme.timestamp_fg);
+                        if idx == *selected_index {
+                            style = style.bg(theme.cursor_bg).fg(theme.text_fg);
+                            date_style = date_style.bg(theme.cursor_bg);
+                        }
+                        ListItem::new(Line::from(vec![
+                            Span::styled(format!("{} {}{}", icon, display_path, " ".repeat(padding)), style),
+                            Span::styled(date, date_style),
+                        ]))
+                    })
+                    .collect();
+
+                let list_state = ListState::default().with_selected((!files.is_empty()).then_some(*selected_index));
+                let title = if explorer.recent_scan.is_some() {
+                    "Recently Modified: scanning…".to_string()
+                } else {
+                    format!("Recently Modified ({} files)", files.len())
+                };
+                (recent_items, list_state, title)
             } else {
                 // Normal tree view
                 let tree_lines = explorer.build_tree_lines(terminal_width);
                 explorer.calculate_scroll_offset(visible_height, &tree_lines);
+                pinned_lines = explorer.pinned_lines;
 
                 let tree_items: Vec<ListItem> = tree_lines
                 .iter()
                 .map(|tree_line| {
                     // Determine base text color using sage's color scheme
                     // Green for files, brown for directories, dimmer for hidden
-                    let text_color = if tree_line.is_cursor && tree_line.is_selected {
-                        Color::Rgb(165, 162, 157) // Bright neutral grey (normal text)
+                    let text_color = if tree_line.is_detail {
+                        theme.timestamp_fg // Metadata reads like the timestamp column
+                    } else if tree_line.is_broken_link || tree_line.is_failed || tree_line.is_case_clash {
+                        theme.warning_fg // Dangling symlink, failed item or case clash, whatever the cursor/selection state
+                    } else if tree_line.is_cursor && tree_line.is_selected {
+                        theme.text_fg // Bright neutral grey (normal text)
                     } else if tree_line.is_cursor {
-                        Color::Rgb(165, 162, 157) // Bright neutral grey
+                        theme.text_fg // Bright neutral grey
                     } else if tree_line.is_selected {
-                        Color::Rgb(190, 182, 165) // Very bright (brighter than function color)
+                        theme.file_fg // Very bright (brighter than function color)
                     } else if tree_line.is_current_dir {
-                        Color::Rgb(160, 150, 135) // Bright grey with warm hint (keywords)
+                        theme.current_dir_fg // Bright grey with warm hint (keywords)
                     } else if tree_line.is_hidden && tree_line.is_dir {
                         // Hidden directories use very dark grey
-                        Color::Rgb(75, 75, 75) // Very dark grey (near comment color)
+                        theme.hidden_dir_fg // Very dark grey (near comment color)
                     } else if tree_line.is_hidden {
                         // Hidden files use very dim grey
-                        Color::Rgb(100, 100, 98) // Dark neutral grey (punctuation color)
+                        theme.hidden_file_fg // Dark neutral grey (punctuation color)
                     } else if tree_line.is_dir {
                         // Directories use dim grey (much darker than files)
-                        Color::Rgb(130, 125, 115)
+                        theme.dir_fg
                     } else {
                         // Files use very bright grey (much lighter than directories)
-                        Color::Rgb(190, 182, 165)
+                        theme.file_fg
                     };
 
                     // Determine background and modifiers
                     let (bg_color, modifiers) = if tree_line.is_cursor && tree_line.is_selected {
-                        (Some(Color::Rgb(60, 60, 60)), Modifier::BOLD) // Darker background
+                        (Some(theme.cursor_selected_bg), Modifier::BOLD) // Darker background
                     } else if tree_line.is_cursor {
-                        (Some(Color::Rgb(50, 50, 50)), Modifier::BOLD) // Dark background
+                        (Some(theme.cursor_bg), Modifier::BOLD) // Dark background
                     } else if tree_line.is_selected {
-                        (Some(Color::Rgb(45, 45, 45)), Modifier::empty()) // Subtle dark background
+                        (Some(theme.selected_bg), Modifier::empty()) // Subtle dark background
                     } else {
                         (None, Modifier::empty())
                     };
 
-                    // Create style for main text
+                    // Create style for main text, dimmed if it's waiting to be moved
                     let mut text_style = Style::default()
                         .fg(text_color)
                         .add_modifier(modifiers);
+                    if tree_line.is_cut {
+                        text_style = text_style.add_modifier(Modifier::DIM | Modifier::ITALIC);
+                    }
                     if let Some(bg) = bg_color {
                         text_style = text_style.bg(bg);
                     }
 
                     // Create style for tree prefix (very dim - dimmer than hidden items)
-                    let tree_prefix_color = Color::Rgb(65, 65, 65);  // Very dark grey (comment color)
+                    let tree_prefix_color = theme.tree_prefix_fg;  // Very dark grey (comment color)
                     let mut tree_prefix_style = Style::default()
                         .fg(tree_prefix_color)
                         .add_modifier(modifiers);
@@ -2109,11 +8618,13 @@ fn run_app<B: ratatui::backend::Backend>(
                         tree_prefix_style = tree_prefix_style.bg(bg);
                     }
 
-                    // Create style for timestamp - use grey
-                    let timestamp_color = if tree_line.is_cursor || tree_line.is_selected {
-                        Color::Rgb(130, 130, 126)  // Medium-light neutral grey (type color)
+                    // Create style for timestamp - grey, or faded by age when age coloring is on
+                    let timestamp_color = if let (true, Some(modified)) = (explorer.age_colored_timestamps, tree_line.modified) {
+                        theme.age_color(modified)
+                    } else if tree_line.is_cursor || tree_line.is_selected {
+                        theme.timestamp_highlight_fg  // Medium-light neutral grey (type color)
                     } else {
-                        Color::Rgb(120, 120, 117)  // Medium-dark neutral grey (operator color)
+                        theme.timestamp_fg  // Medium-dark neutral grey (operator color)
                     };
 
                     let mut timestamp_style = Style::default()
@@ -2136,34 +8647,67 @@ fn run_app<B: ratatui::backend::Backend>(
                 })
                 .collect();
 
-                let cursor_line_idx = explorer.get_cursor_line_index(terminal_width);
+                let cursor_line_idx = tree_lines.iter().position(|line| line.is_cursor).unwrap_or(0);
                 let list_state = ListState::default()
                     .with_selected(Some(cursor_line_idx))
                     .with_offset(explorer.scroll_offset);
 
-                let current_dir_str = explorer.current_dir.display().to_string();
-                let title = format!("File Explorer: {}", current_dir_str);
+                let title = explorer.explorer_title(terminal_width);
                 (tree_items, list_state, title)
             };
 
             // Render the list with title
             let title_style = Style::default()
-                .fg(Color::Rgb(65, 65, 65))  // Very dark grey (comment color)
+                .fg(theme.tree_prefix_fg)  // Very dark grey (comment color)
                 .add_modifier(Modifier::BOLD);
 
-            let tree_list = List::new(tree_items)
-                .block(
-                    Block::default()
-                        .title(Span::styled(title, title_style))
-                );
-
             let mut list_state = list_state;
-            f.render_stateful_widget(tree_list, main_area, &mut list_state);
+            if pinned_lines > 0 {
+                // Directories (with the title above them) stay put; only the files below scroll
+                let mut pinned_items = tree_items;
+                let scrolling_items = pinned_items.split_off(pinned_lines);
+                let split = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Length(pinned_lines as u16 + 1),
+                        Constraint::Min(0),
+                    ])
+                    .split(main_area);
+
+                let cursor_line = list_state.selected().unwrap_or(0);
+                let pinned_list = List::new(pinned_items)
+                    .block(
+                        Block::default()
+                            .title(Span::styled(title, title_style))
+                    );
+                let mut pinned_state = ListState::default()
+                    .with_selected(Some(cursor_line).filter(|&line| line < pinned_lines));
+                f.render_stateful_widget(pinned_list, split[0], &mut pinned_state);
+
+                let mut scrolling_state = ListState::default()
+                    .with_selected(cursor_line.checked_sub(pinned_lines))
+                    .with_offset(list_state.offset());
+                f.render_stateful_widget(List::new(scrolling_items), split[1], &mut scrolling_state);
+            } else {
+                let tree_list = List::new(tree_items)
+                    .block(
+                        Block::default()
+                            .title(Span::styled(title, title_style))
+                    );
+                f.render_stateful_widget(tree_list, main_area, &mut list_state);
+            }
 
             // Render status bar
             let status_text = if let Some(ref msg) = explorer.status_message {
                 // Show status message if present
                 msg.clone()
+            } else if let Some(task) = &explorer.task {
+                FileExplorer::task_status_line(task)
+            } else if let Some(scan) = &explorer.size_scan
+                && (!explorer.config.recursive_selection_size || explorer.selected_indices.is_empty())
+            {
+                // With a selection, its total and "(computing…)" are shown instead
+                FileExplorer::size_scan_status_line(scan, explorer.size_scan_queue.len())
             } else {
                 // Check for UI mode-specific status bar content
                 match &explorer.ui_mode {
@@ -2171,40 +8715,160 @@ fn run_app<B: ratatui::backend::Backend>(
                         let masked_password = "*".repeat(password.len());
                         format!("{} {}", prompt, masked_password)
                     }
-                    UIMode::ConfirmDelete { items } => {
+                    UIMode::ConfirmDelete { items, .. } => {
                         format!("Delete {} item(s)? (y/n)", items.len())
                     }
+                    UIMode::ConfirmPaste { items, destination, is_move, new_dir, .. } => {
+                        format!(
+                            "{} {} item(s) {} {}? (y/n)",
+                            if *is_move { "Move" } else { "Copy" },
+                            items.len(),
+                            if *new_dir { "into new folder" } else { "to" },
+                            destination.display()
+                        )
+                    }
+                    UIMode::ConfirmOverwrite { new_path, .. } => {
+                        let name = new_path.file_name().unwrap_or_default().to_string_lossy();
+                        format!("'{}' already exists. Replace it? (y/n)", name)
+                    }
+                    UIMode::ConfirmCrossDeviceMove { items, external: Some(kind), .. } => {
+                        format!(
+                            "The destination is on {}: copy {} item(s), check each copy, then move the originals to trash? (y/n)",
+                            kind,
+                            items.len()
+                        )
+                    }
+                    UIMode::ConfirmCrossDeviceMove { items, cross_device, .. } => {
+                        format!(
+                            "{} of {} item(s) are on another filesystem: copy them and move the originals to trash? (y/n)",
+                            cross_device,
+                            items.len()
+                        )
+                    }
+                    UIMode::SanitizeNames { options, .. } => {
+                        let flag = |on: bool| if on { "x" } else { " " };
+                        format!(
+                            "[{}] (s)paces to _  [{}] (l)owercase  [{}] (a)SCII only  Enter renames, Esc cancels",
+                            flag(options.spaces),
+                            flag(options.lowercase),
+                            flag(options.ascii)
+                        )
+                    }
+                    UIMode::ConfirmBatchConflicts { conflicts, choices, deciding: false, .. } => {
+                        format!(
+                            "{} name(s) already taken, e.g. '{}': (o)verwrite all, (s)kip all, (k)eep both, (d)ecide each, Esc cancels",
+                            conflicts.len(),
+                            conflicts.get(choices.len()).and_then(|p| p.file_name()).unwrap_or_default().to_string_lossy()
+                        )
+                    }
+                    UIMode::ConfirmBatchConflicts { conflicts, choices, .. } => {
+                        format!(
+                            "'{}' already exists ({}/{}): (o)verwrite, (s)kip, (k)eep both, Esc cancels",
+                            conflicts.get(choices.len()).and_then(|p| p.file_name()).unwrap_or_default().to_string_lossy(),
+                            choices.len() + 1,
+                            conflicts.len()
+                        )
+                    }
                     UIMode::FuzzyFind { search_term, matches, .. } => {
                         format!("Find: {} ({} matches)", search_term, matches.len())
                     }
+                    UIMode::RecentFiles { files, selected_index } => match files.get(*selected_index) {
+                        Some((path, _)) => format!("{} | Enter goes to the file, Esc closes", path.display()),
+                        None => format!("Collecting the newest files below {}… (Esc cancels)", explorer.current_dir.display()),
+                    },
                     _ => {
                         // Show normal status info
                         let total_items = explorer.entries.len();
                         let selected_count = explorer.selected_indices.len();
-                        if selected_count > 0 {
-                            let total_size = explorer.get_selected_total_size();
-                            let size_str = FileExplorer::format_file_size(total_size);
-                            format!("{} items | {} selected | {}", total_items, selected_count, size_str)
+                        let filter_prefix = match explorer.type_filter {
+                            TypeFilter::All => "",
+                            TypeFilter::DirsOnly => "[Dirs only] ",
+                            TypeFilter::FilesOnly => "[Files only] ",
+                        };
+                        let since_prefix = explorer.changed_since
+                            .map(|since| format!("[Changed since {}] ", FileExplorer::format_date(since)))
+                            .unwrap_or_default();
+                        let queue_prefix = if explorer.queue_mode {
+                            format!("[Queue: {}] ", explorer.operation_queue.len())
+                        } else {
+                            String::new()
+                        };
+                        // "12 items (3 hidden)" hints that Ctrl+H would show more
+                        let items_str = if explorer.config.show_hidden_count && explorer.hidden_count > 0 {
+                            format!("{} items ({} hidden)", total_items, explorer.hidden_count)
+                        } else {
+                            format!("{} items", total_items)
+                        };
+                        let status = if let Some(template) = &explorer.config.status_format {
+                            explorer.format_status_template(template)
+                        } else if selected_count > 0 {
+                            let size_str = explorer.format_selected_size();
+                            format!("{} | {} selected | {}", items_str, selected_count, size_str)
                         } else if let Some(entry) = explorer.entries.get(explorer.cursor_index) {
                             if entry.is_dir {
-                                format!("{} items | Directory: {}", total_items, entry.name)
+                                let size_str = match explorer.dir_sizes.get(&entry.path) {
+                                    Some(Some(size)) => format!(" | {}", FileExplorer::format_file_size(*size)),
+                                    Some(None) => " | (size unknown)".to_string(),
+                                    None => String::new(),
+                                };
+                                format!("{} | Directory: {}{}", items_str, entry.name, size_str)
                             } else {
                                 let item_size = explorer.current_item_size.unwrap_or(0);
                                 let size_str = FileExplorer::format_file_size(item_size);
-                                format!("{} items | File: {} | {}", total_items, entry.name, size_str)
+                                let media_str = explorer.current_item_media.as_ref()
+                                    .map(|info| format!(" | {}", info))
+                                    .unwrap_or_default();
+                                let trash_str = if explorer.current_dir == explorer.trash_dir {
+                                    FileExplorer::decode_trash_name(&entry.name)
+                                        .map(|(deleted_at, original)| format!(" | Original: {} (deleted {})", original, FileExplorer::format_date(deleted_at)))
+                                        .unwrap_or_default()
+                                } else {
+                                    String::new()
+                                };
+                                format!("{} | File: {} | {}{}{}", items_str, entry.name, size_str, media_str, trash_str)
                             }
                         } else {
-                            format!("{} items", total_items)
-                        }
+                            items_str
+                        };
+                        let clipboard_suffix = match &explorer.clipboard {
+                            // A custom format decides for itself whether to show the clipboard
+                            _ if explorer.config.status_format.is_some() => String::new(),
+                            Some(clipboard) => {
+                                let op = match clipboard.operation {
+                                    ClipboardOp::Copy => "copy",
+                                    ClipboardOp::Cut => "cut",
+                                };
+                                format!(" | Clipboard: {} item(s) ({})", clipboard.items.len(), op)
+                            }
+                            None => String::new(),
+                        };
+                        let pending_keys = match (explorer.count_prefix, explorer.pending_operator) {
+                            (None, None) => String::new(),
+                            (count, operator) => {
+                                let count_str = count.map(|c| c.to_string()).unwrap_or_default();
+                                let operator_str = operator.map(|(op, n)| {
+                                    if n > 1 { format!("{}{}", n, op) } else { op.to_string() }
+                                }).unwrap_or_default();
+                                format!("[{}{}] ", operator_str, count_str)
+                            }
+                        };
+                        format!("{}{}{}{}{}{}", pending_keys, queue_prefix, filter_prefix, since_prefix, status, clipboard_suffix)
                     }
                 }
             };
 
             let status_bar = Paragraph::new(status_text)
-                .style(Style::default().fg(Color::Rgb(150, 142, 130)).bg(Color::Rgb(30, 30, 30)))  // Medium-bright grey with warm hint (number color) on sage background
+                .style(Style::default().fg(theme.status_fg).bg(theme.background))  // Medium-bright grey with warm hint (number color) on sage background
                 .alignment(Alignment::Left);
             f.render_widget(status_bar, status_bar_area);
 
+            if let Some(hint_area) = hint_area {
+                let hints = Paragraph::new(explorer.key_hints_line())
+                    .style(Style::default().fg(theme.timestamp_fg).bg(theme.background))
+                    .alignment(Alignment::Left);
+                f.render_widget(hints, hint_area);
+            }
+
             if chunks.len() > 2 {
                 match &explorer.ui_mode {
                     UIMode::PasswordPrompt { prompt, password, .. } => {
@@ -2212,22 +8876,22 @@ fn run_app<B: ratatui::backend::Backend>(
                         let text = format!("{}\n{}", prompt, masked_password);
                         let para = Paragraph::new(text)
                             .block(Block::default().title("Password Required"))
-                            .style(Style::default().fg(Color::Rgb(175, 167, 150)))  // Brightest grey with warm hint (function color)
+                            .style(Style::default().fg(theme.accent_fg))  // Brightest grey with warm hint (function color)
                             .wrap(Wrap { trim: false });
                         f.render_widget(para, chunks[2]);
                     }
                     UIMode::StatusMessage { message } => {
                         let para = Paragraph::new(message.as_str())
                             .block(Block::default().title("Status"))
-                            .style(Style::default().fg(Color::Rgb(170, 160, 145)))  // Lighter grey with warm hint
+                            .style(Style::default().fg(theme.message_fg))  // Lighter grey with warm hint
                             .alignment(Alignment::Left);
                         f.render_widget(para, chunks[2]);
                     }
-                    UIMode::ConfirmDelete { items } => {
+                    UIMode::ConfirmDelete { items, .. } => {
                         let text = format!("Delete {} item(s)? (y/n)", items.len());
                         let para = Paragraph::new(text)
                             .block(Block::default().title("Confirm Delete"))
-                            .style(Style::default().fg(Color::Rgb(145, 135, 125)))  // Medium-bright grey with warm hint (decorator color)
+                            .style(Style::default().fg(theme.warning_fg))  // Medium-bright grey with warm hint (decorator color)
                             .alignment(Alignment::Left);
                         f.render_widget(para, chunks[2]);
                     }
@@ -2249,106 +8913,394 @@ fn run_app<B: ratatui::backend::Backend>(
 
                             let style = if is_cursor && is_selected {
                                 // Cursor on selected text
-                                Style::default().bg(Color::Rgb(165, 162, 157)).fg(Color::Rgb(160, 150, 135))
+                                Style::default().bg(theme.text_fg).fg(theme.current_dir_fg)
                             } else if is_cursor {
                                 // Cursor - use function color
-                                Style::default().bg(Color::Rgb(175, 167, 150)).fg(Color::Rgb(30, 30, 30))
+                                Style::default().bg(theme.accent_fg).fg(theme.background)
                             } else if is_selected {
                                 // Selected text - use keyword color
-                                Style::default().bg(Color::Rgb(160, 150, 135)).fg(Color::Rgb(165, 162, 157))
+                                Style::default().bg(theme.current_dir_fg).fg(theme.text_fg)
                             } else {
                                 // Normal text
                                 Style::default()
                             };
 
-                            spans.push(Span::styled(ch.to_string(), style));
-                        }
+                            spans.push(Span::styled(ch.to_string(), style));
+                        }
+
+                        // If cursor is at the end (past all characters), show a block cursor
+                        if *cursor_pos >= new_name.len() {
+                            spans.push(Span::styled("█", Style::default().bg(theme.accent_fg).fg(theme.background)));
+                        }
+
+                        let text = Line::from(spans);
+                        let title = if explorer.last_rename_input.is_some() {
+                            "Rename (Up: recall cancelled input)"
+                        } else {
+                            "Rename"
+                        };
+                        let para = Paragraph::new(text)
+                            .block(Block::default().title(title))
+                            .style(Style::default().fg(theme.accent_fg))  // Brightest grey with warm hint (function color)
+                            .alignment(Alignment::Left);
+                        f.render_widget(para, chunks[2]);
+                    }
+                    UIMode::CreateNew { creation_type, name } => {
+                        let text = if creation_type.is_none() {
+                            "Create new: (f)ile or (d)irectory?".to_string()
+                        } else {
+                            let prompt = match creation_type {
+                                Some(CreationType::File) => "Enter file name:",
+                                Some(CreationType::Directory) => "Enter directory name:",
+                                Some(CreationType::ClipboardText) => "Save clipboard text as:",
+                                Some(CreationType::PasteFolder) => "Paste into new directory:",
+                                None => unreachable!(),
+                            };
+                            format!("{} {}", prompt, name)
+                        };
+                        let title = if creation_type.is_some() && explorer.last_create_input.is_some() {
+                            "Create New (Up: recall cancelled input)"
+                        } else {
+                            "Create New"
+                        };
+                        let para = Paragraph::new(text)
+                            .block(Block::default().title(title))
+                            .style(Style::default().fg(theme.accent_fg))  // Brightest grey with warm hint (function color)
+                            .alignment(Alignment::Left);
+                        f.render_widget(para, chunks[2]);
+                    }
+                    UIMode::EditTag { path, tags, tag } => {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let current = if tags.is_empty() { "none".to_string() } else { tags.join(", ") };
+                        let text = format!("Tag '{}' (current: {}), a present tag is removed: {}", name, current, tag);
+                        let para = Paragraph::new(text)
+                            .block(Block::default().title("Edit Tags"))
+                            .style(Style::default().fg(theme.accent_fg))  // Brightest grey with warm hint (function color)
+                            .alignment(Alignment::Left);
+                        f.render_widget(para, chunks[2]);
+                    }
+                    UIMode::ExportListing { format, name } => {
+                        let text = match format {
+                            None => "Export listing as (t)ext, (c)sv or t(r)ee?".to_string(),
+                            Some(_) => format!("Save as (empty for clipboard): {}", name),
+                        };
+                        let para = Paragraph::new(text)
+                            .block(Block::default().title("Export Listing"))
+                            .style(Style::default().fg(theme.accent_fg))  // Brightest grey with warm hint (function color)
+                            .alignment(Alignment::Left);
+                        f.render_widget(para, chunks[2]);
+                    }
+                    UIMode::JumpToNumber { buffer } => {
+                        let text = format!("Go to item (1-{}): {}", explorer.entries.len(), buffer);
+                        let para = Paragraph::new(text)
+                            .block(Block::default().title("Jump To Number"))
+                            .style(Style::default().fg(theme.accent_fg))  // Brightest grey with warm hint (function color)
+                            .alignment(Alignment::Left);
+                        f.render_widget(para, chunks[2]);
+                    }
+                    UIMode::SplitFile { path, chunk_size } => {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let text = format!("Split '{}' into chunks of (e.g. 100M): {}", name, chunk_size);
+                        let para = Paragraph::new(text)
+                            .block(Block::default().title("Split File"))
+                            .style(Style::default().fg(theme.accent_fg))  // Brightest grey with warm hint (function color)
+                            .alignment(Alignment::Left);
+                        f.render_widget(para, chunks[2]);
+                    }
+                    _ => {}
+                }
+            }
+
+            // Render help overlay over entire screen if in Help mode
+            if matches!(explorer.ui_mode, UIMode::Help { .. }) {
+                // Clear the entire screen first
+                f.render_widget(Clear, area);
+
+                let (scroll, filter) = match &mut explorer.ui_mode {
+                    UIMode::Help { scroll, filter } => {
+                        // Clamp here where the height is known so scrolling never runs past the end
+                        let visible = area.height.saturating_sub(2) as usize;
+                        let max_scroll = FileExplorer::filtered_help_lines(filter).len().saturating_sub(visible);
+                        *scroll = (*scroll).min(max_scroll);
+                        (*scroll, filter.clone())
+                    }
+                    _ => (0, String::new()),
+                };
+
+                // In read-only mode, mark the commands that are switched off
+                let mut section = "";
+                let help_lines: Vec<Line> = FileExplorer::filtered_help_lines(&filter)
+                    .into_iter()
+                    .map(|line| {
+                        if !line.starts_with(' ') {
+                            section = line;
+                        }
+                        let disabled = explorer.read_only
+                            && line.starts_with(' ')
+                            && (section == "File Operations:" || line.starts_with("  Alt+M"));
+                        if disabled {
+                            Line::styled(format!("{}  (disabled: read-only)", line), Style::default().fg(theme.hidden_file_fg))
+                        } else {
+                            Line::from(line)
+                        }
+                    })
+                    .collect();
+
+                let title = if filter.is_empty() {
+                    "Help - Keyboard Shortcuts (type to search, Up/Down/PgUp/PgDn to scroll)".to_string()
+                } else {
+                    format!("Help - Search: {}", filter)
+                };
+
+                let para = Paragraph::new(help_lines)
+                    .block(Block::default()
+                        .title(title)
+                        .title_alignment(Alignment::Center))
+                    .style(Style::default().fg(theme.text_fg).bg(theme.background))  // Bright neutral grey (normal text) on background
+                    .alignment(Alignment::Left)
+                    .scroll((scroll as u16, 0));
+                f.render_widget(para, area);
+            }
+
+            // Render queue summary overlay over entire screen
+            if let UIMode::QueueSummary { results, scroll } = &mut explorer.ui_mode {
+                f.render_widget(Clear, area);
+
+                let visible = area.height.saturating_sub(2) as usize;
+                *scroll = (*scroll).min(results.len().saturating_sub(visible));
+
+                let summary_lines: Vec<Line> = results.iter()
+                    .map(|(outcome, operation, detail)| {
+                        let (label, color) = match outcome {
+                            OperationOutcome::Done => ("done   ", theme.text_fg),
+                            OperationOutcome::Failed => ("FAILED ", theme.warning_fg),
+                            OperationOutcome::Skipped => ("skipped", theme.timestamp_fg),
+                            OperationOutcome::Deferred => ("pending", theme.timestamp_fg),
+                        };
+                        let detail = if detail.is_empty() { String::new() } else { format!(" - {}", detail) };
+                        Line::from(vec![
+                            Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                            Span::raw("  "),
+                            Span::raw(operation.clone()),
+                            Span::styled(detail, Style::default().fg(theme.timestamp_fg)),
+                        ])
+                    })
+                    .collect();
+
+                let para = Paragraph::new(summary_lines)
+                    .block(Block::default()
+                        .title("Queue Results (Up/Down to scroll, Esc to close)")
+                        .title_alignment(Alignment::Center))
+                    .style(Style::default().fg(theme.text_fg).bg(theme.background))
+                    .alignment(Alignment::Left)
+                    .scroll((*scroll as u16, 0));
+                f.render_widget(para, area);
+            }
+
+            // Old and new names of the sanitize command, recomputed as its options are toggled
+            if let UIMode::SanitizeNames { items, options, scroll } = &mut explorer.ui_mode {
+                let list_area = chunks[0];
+                f.render_widget(Clear, list_area);
+
+                let renames = FileExplorer::sanitize_renames(items, *options);
+                let visible = list_area.height.saturating_sub(2) as usize;
+                *scroll = (*scroll).min(renames.len().saturating_sub(visible));
+
+                let rename_lines: Vec<Line> = if renames.is_empty() {
+                    vec![Line::from("No names would change")]
+                } else {
+                    renames.iter()
+                        .map(|(item, new_name)| Line::from(vec![
+                            Span::raw(item.file_name().unwrap_or_default().to_string_lossy().to_string()),
+                            Span::styled("  →  ", Style::default().fg(theme.timestamp_fg)),
+                            Span::styled(new_name.clone(), Style::default().fg(theme.accent_fg)),
+                        ]))
+                        .collect()
+                };
+
+                let para = Paragraph::new(rename_lines)
+                    .block(Block::default()
+                        .title(format!("Sanitize {} of {} name(s) (Up/Down to scroll)", renames.len(), items.len()))
+                        .title_alignment(Alignment::Center))
+                    .style(Style::default().fg(theme.text_fg).bg(theme.background))
+                    .alignment(Alignment::Left)
+                    .scroll((*scroll as u16, 0));
+                f.render_widget(para, list_area);
+            }
+
+            // List every path a multi-item delete or a large paste will touch before it's confirmed
+            let preview = match &mut explorer.ui_mode {
+                UIMode::ConfirmDelete { items, scroll } if items.len() > 1 => {
+                    Some((format!("Delete {} item(s)? (y/n, Up/Down to scroll)", items.len()), items, scroll))
+                }
+                UIMode::ConfirmPaste { items, destination, is_move, new_dir, scroll } => Some((
+                    format!(
+                        "{} {} item(s) {} {}? (y/n, Up/Down to scroll)",
+                        if *is_move { "Move" } else { "Copy" },
+                        items.len(),
+                        if *new_dir { "into new folder" } else { "to" },
+                        destination.display()
+                    ),
+                    items,
+                    scroll,
+                )),
+                _ => None,
+            };
+            if let Some((title, items, scroll)) = preview {
+                let list_area = chunks[0];
+                f.render_widget(Clear, list_area);
+
+                let visible = list_area.height.saturating_sub(2) as usize;
+                *scroll = (*scroll).min(items.len().saturating_sub(visible));
+
+                let path_lines: Vec<Line> = items.iter()
+                    .map(|item| {
+                        let suffix = if item.is_dir() && !item.is_symlink() { "/" } else { "" };
+                        Line::from(format!("{}{}", item.display(), suffix))
+                    })
+                    .collect();
 
-                        // If cursor is at the end (past all characters), show a block cursor
-                        if *cursor_pos >= new_name.len() {
-                            spans.push(Span::styled("█", Style::default().bg(Color::Rgb(175, 167, 150)).fg(Color::Rgb(30, 30, 30))));
-                        }
+                let para = Paragraph::new(path_lines)
+                    .block(Block::default()
+                        .title(title)
+                        .title_style(Style::default().fg(theme.warning_fg))
+                        .title_alignment(Alignment::Center))
+                    .style(Style::default().fg(theme.text_fg).bg(theme.background))
+                    .alignment(Alignment::Left)
+                    .scroll((*scroll as u16, 0));
+                f.render_widget(para, list_area);
+            }
 
-                        let text = Line::from(spans);
-                        let para = Paragraph::new(text)
-                            .block(Block::default().title("Rename"))
-                            .style(Style::default().fg(Color::Rgb(175, 167, 150)))  // Brightest grey with warm hint (function color)
-                            .alignment(Alignment::Left);
-                        f.render_widget(para, chunks[2]);
-                    }
-                    UIMode::CreateNew { creation_type, name } => {
-                        let text = if creation_type.is_none() {
-                            "Create new: (f)ile or (d)irectory?".to_string()
-                        } else {
-                            let type_str = match creation_type {
-                                Some(CreationType::File) => "file",
-                                Some(CreationType::Directory) => "directory",
-                                None => unreachable!(),
-                            };
-                            format!("Enter {} name: {}", type_str, name)
-                        };
-                        let para = Paragraph::new(text)
-                            .block(Block::default().title("Create New"))
-                            .style(Style::default().fg(Color::Rgb(175, 167, 150)))  // Brightest grey with warm hint (function color)
-                            .alignment(Alignment::Left);
-                        f.render_widget(para, chunks[2]);
+            // Render message log overlay over entire screen
+            if matches!(explorer.ui_mode, UIMode::MessageLog { .. }) {
+                f.render_widget(Clear, area);
+
+                let visible = area.height.saturating_sub(2) as usize;
+                let max_scroll = explorer.message_log.len().saturating_sub(visible);
+                let scroll = match &mut explorer.ui_mode {
+                    UIMode::MessageLog { scroll } => {
+                        *scroll = (*scroll).min(max_scroll);
+                        *scroll
                     }
-                    _ => {}
-                }
+                    _ => 0,
+                };
+
+                let log_lines: Vec<Line> = if explorer.message_log.is_empty() {
+                    vec![Line::from("No messages yet")]
+                } else {
+                    explorer.message_log.iter()
+                        .map(|(time, message)| Line::from(vec![
+                            Span::styled(FileExplorer::format_date(*time), Style::default().fg(theme.timestamp_fg)),
+                            Span::raw("  "),
+                            Span::raw(message.as_str()),
+                        ]))
+                        .collect()
+                };
+
+                let para = Paragraph::new(log_lines)
+                    .block(Block::default()
+                        .title("Message Log (Up/Down/PgUp/PgDn to scroll, Esc to close)")
+                        .title_alignment(Alignment::Center))
+                    .style(Style::default().fg(theme.text_fg).bg(theme.background))
+                    .alignment(Alignment::Left)
+                    .scroll((scroll as u16, 0));
+                f.render_widget(para, area);
             }
 
-            // Render help overlay over entire screen if in Help mode
-            if matches!(explorer.ui_mode, UIMode::Help) {
-                // Clear the entire screen first
+            // Render item info overlay over entire screen
+            if let UIMode::ItemInfo { path, lines } = &explorer.ui_mode {
                 f.render_widget(Clear, area);
 
-                let help_text = vec![
-                    "Keyboard Shortcuts",
-                    "",
-                    "Navigation:",
-                    "  Up/Down        - Move cursor",
-                    "  Left           - Go to parent directory",
-                    "  Right          - Enter directory",
-                    "  Enter          - Open file/directory",
-                    "",
-                    "Selection:",
-                    "  Shift+Up/Down  - Select range",
-                    "  Ctrl+Space     - Toggle selection",
-                    "  Mouse drag     - Select multiple",
-                    "",
-                    "File Operations:",
-                    "  Ctrl+C         - Copy",
-                    "  Ctrl+X         - Cut",
-                    "  Ctrl+V         - Paste",
-                    "  Ctrl+N         - Create new",
-                    "  Ctrl+R         - Rename",
-                    "  Ctrl+D/Delete  - Delete",
-                    "  Ctrl+Z         - Undo",
-                    "",
-                    "View Options:",
-                    "  Ctrl+S         - Toggle sort (Name/Date)",
-                    "  Ctrl+H         - Toggle hidden files",
-                    "  Ctrl+L         - Refresh display",
-                    "",
-                    "Other:",
-                    "  F1             - Show/hide this help",
-                    "  Ctrl+Q         - Quit",
-                    "",
-                    "Press F1 or Esc to close this help",
-                ].join("\n");
-
-                let para = Paragraph::new(help_text)
+                let name = path.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.display().to_string());
+                let info_lines: Vec<Line> = lines.iter().map(|line| Line::from(line.as_str())).collect();
+
+                let para = Paragraph::new(info_lines)
                     .block(Block::default()
-                        .title("Help - Keyboard Shortcuts")
+                        .title(format!("Info - {} (Esc to close)", name))
                         .title_alignment(Alignment::Center))
-                    .style(Style::default().fg(Color::Rgb(165, 162, 157)).bg(Color::Rgb(30, 30, 30)))  // Bright neutral grey (normal text) on background
+                    .style(Style::default().fg(theme.text_fg).bg(theme.background))
                     .alignment(Alignment::Left)
                     .wrap(Wrap { trim: false });
                 f.render_widget(para, area);
             }
+
+            // Render sort menu overlay over entire screen
+            if let UIMode::SortMenu { selected_index } = explorer.ui_mode {
+                f.render_widget(Clear, area);
+
+                let mut menu_items: Vec<ListItem> = SortMode::ALL
+                    .iter()
+                    .map(|mode| {
+                        let marker = if *mode == explorer.sort_mode { "●" } else { " " };
+                        ListItem::new(format!(" {} {}  {}", marker, mode.key(), mode.label()))
+                    })
+                    .collect();
+                let direction = if explorer.sort_reverse { "on" } else { "off" };
+                menu_items.push(ListItem::new(""));
+                menu_items.push(ListItem::new(format!("     r  Reverse order: {}", direction)));
+                menu_items.push(ListItem::new(format!("     g  Directories: {}", explorer.dirs_position.label())));
+
+                let mut menu_state = ListState::default();
+                menu_state.select(Some(selected_index));
+
+                let menu = List::new(menu_items)
+                    .block(Block::default()
+                        .title("Sort By (Enter/key to apply, r to reverse, g to place directories, Esc to cancel)")
+                        .title_alignment(Alignment::Center))
+                    .style(Style::default().fg(theme.text_fg).bg(theme.background))
+                    .highlight_style(Style::default().bg(theme.cursor_bg).add_modifier(Modifier::BOLD));
+                f.render_stateful_widget(menu, area, &mut menu_state);
+            }
+
+            if let UIMode::CommandPalette { query, filtered, selected } = &explorer.ui_mode {
+                f.render_widget(Clear, area);
+
+                let label_width = filtered.iter().map(|command| command.label().width()).max().unwrap_or(0);
+                let palette_items: Vec<ListItem> = filtered
+                    .iter()
+                    .map(|command| ListItem::new(format!(" {:<width$}  {}", command.label(), command.key(), width = label_width)))
+                    .collect();
+                let mut palette_state = ListState::default();
+                palette_state.select((!filtered.is_empty()).then_some(*selected));
+
+                let title = if filtered.is_empty() {
+                    format!("Command Palette: {}▏ (no matching commands, Esc to close)", query)
+                } else {
+                    format!("Command Palette: {}▏ (type to filter, Enter to run, Esc to close)", query)
+                };
+                let palette = List::new(palette_items)
+                    .block(Block::default().title(title).title_alignment(Alignment::Center))
+                    .style(Style::default().fg(theme.text_fg).bg(theme.background))
+                    .highlight_style(Style::default().bg(theme.cursor_bg).add_modifier(Modifier::BOLD));
+                f.render_stateful_widget(palette, area, &mut palette_state);
+            }
+
+            // Render the right-click menu next to the clicked row
+            if let UIMode::ContextMenu { row, column, selected } = explorer.ui_mode {
+                let menu_area = FileExplorer::context_menu_area(row, column, area);
+                f.render_widget(Clear, menu_area);
+
+                let menu_items: Vec<ListItem> = ContextAction::ALL
+                    .iter()
+                    .map(|action| ListItem::new(format!(" {}", action.label())))
+                    .collect();
+                let mut menu_state = ListState::default();
+                menu_state.select(Some(selected));
+
+                let menu = List::new(menu_items)
+                    .block(Block::default().title("Actions").title_alignment(Alignment::Center))
+                    .style(Style::default().fg(theme.text_fg).bg(theme.background))
+                    .highlight_style(Style::default().bg(theme.cursor_bg).add_modifier(Modifier::BOLD));
+                f.render_stateful_widget(menu, menu_area, &mut menu_state);
+            }
         })?;
 
-        if event::poll(std::time::Duration::from_millis(100))? {
+        // Handle everything already queued (e.g. key repeat) before drawing the next frame
+        let mut timeout = std::time::Duration::from_millis(100);
+        while event::poll(timeout)? {
+            timeout = std::time::Duration::ZERO;
             match event::read()? {
                 Event::Key(key) => {
                     // Auto-dismiss status messages on any key press and process the key
@@ -2377,116 +9329,37 @@ fn run_app<B: ratatui::backend::Backend>(
                                     let pwd = password.clone();
                                     explorer.ui_mode = UIMode::Normal;
 
-                                    match &op.operation {
-                                        OperationType::Copy | OperationType::Move => {
-                                            let is_move = matches!(op.operation, OperationType::Move);
-                                            if let Some(dest) = &op.destination {
-                                                // Check if this is a rename operation (single item, same parent directory)
-                                                let is_rename = op.items.len() == 1
-                                                    && op.items[0].parent() == dest.parent();
-
-                                                if is_rename {
-                                                    // Handle rename with sudo
-                                                    let original_path = &op.items[0];
-                                                    let new_name = dest.file_name()
-                                                        .and_then(|n| n.to_str())
-                                                        .unwrap_or("")
-                                                        .to_string();
-
-                                                    match explorer.perform_rename_sudo(original_path, dest, &pwd) {
-                                                        Ok(_) => {
-                                                            explorer.show_status(format!("Renamed to '{}' with sudo", new_name));
-
-                                                            // Add to undo stack
-                                                            explorer.undo_stack.push(UndoAction::Rename {
-                                                                original_path: original_path.clone(),
-                                                                new_path: dest.clone(),
-                                                            });
-
-                                                            // Clear size cache entry for old path
-                                                            explorer.size_cache.remove(original_path);
-
-                                                            explorer.load_directory()?;
-                                                            explorer.select_items_by_name(&[new_name]);
-                                                        }
-                                                        Err(e) => {
-                                                            explorer.show_status(format!("Error: {}", e));
-                                                        }
-                                                    }
-                                                } else {
-                                                    // Handle copy/move with sudo
-                                                    let pasted_names: Vec<String> = op.items.iter()
-                                                        .filter_map(|p| p.file_name())
-                                                        .filter_map(|n| n.to_str())
-                                                        .map(|s| s.to_string())
-                                                        .collect();
-
-                                                    match explorer.perform_file_operation_sudo(&op.items, dest, is_move, &pwd) {
-                                                        Ok(count) => {
-                                                            if is_move {
-                                                                explorer.clipboard = None;
-                                                            }
-                                                            explorer.show_status(format!("Pasted {} item(s) with sudo", count));
-                                                            explorer.load_directory()?;
-                                                            explorer.select_items_by_name(&pasted_names);
-                                                        }
-                                                        Err(e) => {
-                                                            explorer.show_status(format!("Error: {}", e));
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        OperationType::Delete => {
-                                            match explorer.perform_delete_sudo(&op.items, &pwd) {
-                                                Ok(deleted_files) => {
-                                                    let count = deleted_files.len();
-                                                    explorer.undo_stack.push(UndoAction::Delete { deleted_files });
-                                                    explorer.show_status(format!("Deleted {} item(s) with sudo (moved to trash)", count));
-                                                    explorer.selected_indices.clear();
-                                                    explorer.selection_anchor = None;
-                                                    explorer.load_directory()?;
-                                                }
-                                                Err(e) => {
-                                                    explorer.show_status(format!("Error: {}", e));
-                                                }
-                                            }
-                                        }
-                                        OperationType::Undo => {
-                                            if let Some(undo_action) = &op.undo_action {
-                                                match explorer.perform_undo_sudo(undo_action, &pwd) {
-                                                    Ok(count) => {
-                                                        // Pop the action from the stack since we successfully undid it
-                                                        explorer.undo_stack.pop();
-                                                        let msg = match undo_action {
-                                                            UndoAction::Copy { .. } => format!("Undone copy: removed {} item(s) with sudo", count),
-                                                            UndoAction::Move { .. } => format!("Undone move: restored {} item(s) with sudo", count),
-                                                            UndoAction::Delete { .. } => format!("Undone delete: restored {} item(s) with sudo", count),
-                                                            UndoAction::Rename { original_path, .. } => {
-                                                                let name = original_path.file_name()
-                                                                    .and_then(|n| n.to_str())
-                                                                    .unwrap_or("");
-                                                                format!("Undone rename: restored to '{}' with sudo", name)
-                                                            }
-                                                        };
-                                                        explorer.show_status(msg);
-                                                        explorer.load_directory()?;
-                                                    }
-                                                    Err(e) => {
-                                                        explorer.show_status(format!("Error: {}", e));
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
+                                    explorer.run_sudo_operation(&op, &pwd)?;
                                 }
                                 KeyCode::Esc => {
+                                    let op = pending_operation.clone();
                                     explorer.ui_mode = UIMode::Normal;
+                                    explorer.restore_pending_replace(&op)?;
                                 }
                                 _ => {}
                             }
                         }
-                        UIMode::ConfirmDelete { items } => {
+                        UIMode::ConfirmPaste { items, destination, is_move, new_dir, .. } => {
+                            match key.code {
+                                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                                    let items = items.clone();
+                                    let destination = destination.clone();
+                                    let is_move = *is_move;
+                                    let new_dir = *new_dir;
+                                    explorer.ui_mode = UIMode::Normal;
+                                    if new_dir {
+                                        explorer.create_dir_and_paste(items, is_move, destination)?;
+                                    } else {
+                                        explorer.paste_items(items, is_move, destination)?;
+                                    }
+                                }
+                                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                                    explorer.ui_mode = UIMode::Normal;
+                                }
+                                code => explorer.scroll_confirm_list(code),
+                            }
+                        }
+                        UIMode::ConfirmDelete { items, .. } => {
                             match key.code {
                                 KeyCode::Char('y') | KeyCode::Char('Y') => {
                                     let items_to_delete = items.clone();
@@ -2495,16 +9368,15 @@ fn run_app<B: ratatui::backend::Backend>(
                                     match explorer.perform_delete(&items_to_delete) {
                                         Ok(_) => {}
                                         Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
-                                            explorer.ui_mode = UIMode::PasswordPrompt {
-                                                prompt: "Permission denied. Enter sudo password:".to_string(),
-                                                password: String::new(),
-                                                pending_operation: Box::new(PendingOperation {
+                                            explorer.request_sudo(
+                                                "Permission denied. Enter sudo password:".to_string(),
+                                                PendingOperation {
                                                     items: items_to_delete,
                                                     destination: None,
                                                     operation: OperationType::Delete,
                                                     undo_action: None,
-                                                }),
-                                            };
+                                                },
+                                            )?;
                                         }
                                         Err(e) => {
                                             explorer.show_status(format!("Error: {}", e));
@@ -2514,6 +9386,71 @@ fn run_app<B: ratatui::backend::Backend>(
                                 KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                                     explorer.ui_mode = UIMode::Normal;
                                 }
+                                code => explorer.scroll_confirm_list(code),
+                            }
+                        }
+                        UIMode::ConfirmBatchConflicts { .. } => {
+                            explorer.choose_conflicts(key.code)?;
+                        }
+                        UIMode::SanitizeNames { items, options, .. } => {
+                            match key.code {
+                                KeyCode::Enter => {
+                                    let items = items.clone();
+                                    let options = *options;
+                                    explorer.ui_mode = UIMode::Normal;
+                                    explorer.apply_sanitize_names(&items, options)?;
+                                }
+                                KeyCode::Esc => {
+                                    explorer.ui_mode = UIMode::Normal;
+                                }
+                                KeyCode::Char(c @ ('s' | 'l' | 'a')) => {
+                                    if let UIMode::SanitizeNames { options, .. } = &mut explorer.ui_mode {
+                                        let flag = match c {
+                                            's' => &mut options.spaces,
+                                            'l' => &mut options.lowercase,
+                                            _ => &mut options.ascii,
+                                        };
+                                        *flag = !*flag;
+                                    }
+                                }
+                                code => explorer.scroll_confirm_list(code),
+                            }
+                        }
+                        UIMode::ConfirmCrossDeviceMove { items, destination, external, .. } => {
+                            match key.code {
+                                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                                    let items = items.clone();
+                                    let destination = destination.clone();
+                                    let to_external = external.is_some();
+                                    explorer.ui_mode = UIMode::Normal;
+
+                                    if let Err(e) = explorer.perform_cross_device_move(items, destination, to_external) {
+                                        explorer.show_status(format!("Error: {}", e));
+                                    }
+                                    explorer.load_directory()?;
+                                }
+                                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                                    explorer.ui_mode = UIMode::Normal;
+                                    explorer.show_status("Paste cancelled".to_string());
+                                }
+                                _ => {}
+                            }
+                        }
+                        UIMode::ConfirmOverwrite { original_path, new_path } => {
+                            match key.code {
+                                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                                    let original = original_path.clone();
+                                    let target = new_path.clone();
+                                    explorer.ui_mode = UIMode::Normal;
+
+                                    if let Err(e) = explorer.overwrite_and_rename(original, target) {
+                                        explorer.show_status(format!("Error: {}", e));
+                                    }
+                                }
+                                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                                    explorer.ui_mode = UIMode::Normal;
+                                    explorer.show_status("Rename cancelled".to_string());
+                                }
                                 _ => {}
                             }
                         }
@@ -2546,53 +9483,48 @@ fn run_app<B: ratatui::backend::Backend>(
                                 }
                                 KeyCode::Char('c') if ctrl => {
                                     // Copy selection to system clipboard
+                                    let mut selected_text = None;
                                     if let UIMode::RenameItem { new_name, cursor_pos, selection_start, .. } = &explorer.ui_mode {
                                         if let Some(sel_start) = selection_start {
                                             let start = (*sel_start).min(*cursor_pos);
                                             let end = (*sel_start).max(*cursor_pos);
                                             if start < end {
-                                                let selected_text = new_name[start..end].to_string();
-                                                if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                                                    let _ = clipboard.set_text(selected_text);
-                                                }
+                                                selected_text = Some(new_name[start..end].to_string());
                                             }
                                         }
                                     }
+                                    if let Some(text) = selected_text {
+                                        explorer.copy_text(text);
+                                    }
                                 }
                                 KeyCode::Char('v') if ctrl => {
                                     // Paste from system clipboard
-                                    if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                                        if let Ok(clipboard_text) = clipboard.get_text() {
-                                            if !clipboard_text.is_empty() {
-                                                if let UIMode::RenameItem { new_name, cursor_pos, selection_start, .. } = &mut explorer.ui_mode {
-                                                    // Delete selection if any
-                                                    if let Some(sel_start) = selection_start {
-                                                        let start = (*sel_start).min(*cursor_pos);
-                                                        let end = (*sel_start).max(*cursor_pos);
-                                                        new_name.replace_range(start..end, "");
-                                                        *cursor_pos = start;
-                                                        *selection_start = None;
-                                                    }
-                                                    // Insert clipboard content at cursor
-                                                    new_name.insert_str(*cursor_pos, &clipboard_text);
-                                                    *cursor_pos += clipboard_text.len();
-                                                }
-                                            }
+                                    let clipboard_text = explorer.paste_text();
+                                    if !clipboard_text.is_empty()
+                                        && let UIMode::RenameItem { new_name, cursor_pos, selection_start, .. } = &mut explorer.ui_mode
+                                    {
+                                        // Delete selection if any
+                                        if let Some(sel_start) = selection_start {
+                                            let start = (*sel_start).min(*cursor_pos);
+                                            let end = (*sel_start).max(*cursor_pos);
+                                            new_name.replace_range(start..end, "");
+                                            *cursor_pos = start;
+                                            *selection_start = None;
                                         }
+                                        // Insert clipboard content at cursor
+                                        new_name.insert_str(*cursor_pos, &clipboard_text);
+                                        *cursor_pos += clipboard_text.len();
                                     }
                                 }
                                 KeyCode::Char('x') if ctrl => {
                                     // Cut selection (copy + delete)
+                                    let mut selected_text = None;
                                     if let UIMode::RenameItem { new_name, cursor_pos, selection_start, .. } = &mut explorer.ui_mode {
                                         if let Some(sel_start) = selection_start {
                                             let start = (*sel_start).min(*cursor_pos);
                                             let end = (*sel_start).max(*cursor_pos);
                                             if start < end {
-                                                let selected_text = new_name[start..end].to_string();
-                                                // Copy to clipboard
-                                                if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                                                    let _ = clipboard.set_text(selected_text);
-                                                }
+                                                selected_text = Some(new_name[start..end].to_string());
                                                 // Delete from text
                                                 new_name.replace_range(start..end, "");
                                                 *cursor_pos = start;
@@ -2600,6 +9532,9 @@ fn run_app<B: ratatui::backend::Backend>(
                                             }
                                         }
                                     }
+                                    if let Some(text) = selected_text {
+                                        explorer.copy_text(text);
+                                    }
                                 }
                                 KeyCode::Left => {
                                     if let UIMode::RenameItem { cursor_pos, selection_start, .. } = &mut explorer.ui_mode {
@@ -2663,44 +9598,191 @@ fn run_app<B: ratatui::backend::Backend>(
                                         *cursor_pos = new_name.len();
                                     }
                                 }
-                                KeyCode::Backspace => {
-                                    if let UIMode::RenameItem { new_name, cursor_pos, selection_start, .. } = &mut explorer.ui_mode {
-                                        if let Some(sel_start) = selection_start {
-                                            // Delete selection
-                                            let start = (*sel_start).min(*cursor_pos);
-                                            let end = (*sel_start).max(*cursor_pos);
-                                            new_name.replace_range(start..end, "");
-                                            *cursor_pos = start;
-                                            *selection_start = None;
-                                        } else if *cursor_pos > 0 {
-                                            // Delete character before cursor
-                                            new_name.remove(*cursor_pos - 1);
-                                            *cursor_pos -= 1;
-                                        }
+                                KeyCode::Backspace => {
+                                    if let UIMode::RenameItem { new_name, cursor_pos, selection_start, .. } = &mut explorer.ui_mode {
+                                        if let Some(sel_start) = selection_start {
+                                            // Delete selection
+                                            let start = (*sel_start).min(*cursor_pos);
+                                            let end = (*sel_start).max(*cursor_pos);
+                                            new_name.replace_range(start..end, "");
+                                            *cursor_pos = start;
+                                            *selection_start = None;
+                                        } else if *cursor_pos > 0 {
+                                            // Delete character before cursor
+                                            new_name.remove(*cursor_pos - 1);
+                                            *cursor_pos -= 1;
+                                        }
+                                    }
+                                }
+                                KeyCode::Delete => {
+                                    if let UIMode::RenameItem { new_name, cursor_pos, selection_start, .. } = &mut explorer.ui_mode {
+                                        if let Some(sel_start) = selection_start {
+                                            // Delete selection
+                                            let start = (*sel_start).min(*cursor_pos);
+                                            let end = (*sel_start).max(*cursor_pos);
+                                            new_name.replace_range(start..end, "");
+                                            *cursor_pos = start;
+                                            *selection_start = None;
+                                        } else if *cursor_pos < new_name.len() {
+                                            // Delete character at cursor
+                                            new_name.remove(*cursor_pos);
+                                        }
+                                    }
+                                }
+                                KeyCode::Enter => {
+                                    let path = original_path.clone();
+                                    let name = new_name.clone();
+                                    explorer.ui_mode = UIMode::Normal;
+
+                                    if let Err(e) = explorer.rename_item(path, name) {
+                                        explorer.show_status(format!("Error: {}", e));
+                                    }
+                                }
+                                KeyCode::Up => {
+                                    // Swap in the last cancelled input; Up again swaps back
+                                    if let Some(recalled) = explorer.last_rename_input.take()
+                                        && let UIMode::RenameItem { new_name, cursor_pos, selection_start, .. } = &mut explorer.ui_mode
+                                    {
+                                        explorer.last_rename_input = Some(std::mem::replace(new_name, recalled));
+                                        *cursor_pos = new_name.len();
+                                        *selection_start = None;
+                                    }
+                                }
+                                KeyCode::Esc => {
+                                    // Keep what was typed unless it's still just the original name
+                                    let unchanged = original_path.file_name()
+                                        .is_some_and(|n| n.to_string_lossy() == new_name.as_str());
+                                    if !new_name.is_empty() && !unchanged {
+                                        explorer.last_rename_input = Some(new_name.clone());
+                                    }
+                                    explorer.ui_mode = UIMode::Normal;
+                                }
+                                _ => {}
+                            }
+                        }
+                        UIMode::EditTag { path, tags, tag } => {
+                            match key.code {
+                                KeyCode::Char(c) => {
+                                    if let UIMode::EditTag { tag, .. } = &mut explorer.ui_mode {
+                                        tag.push(c);
+                                    }
+                                }
+                                KeyCode::Backspace => {
+                                    if let UIMode::EditTag { tag, .. } = &mut explorer.ui_mode {
+                                        tag.pop();
+                                    }
+                                }
+                                KeyCode::Enter => {
+                                    let path = path.clone();
+                                    let tags = tags.clone();
+                                    let tag = tag.clone();
+                                    explorer.ui_mode = UIMode::Normal;
+
+                                    if let Err(e) = explorer.toggle_tag(&path, tags, &tag) {
+                                        explorer.show_status(format!("Tagging failed: {}", e));
+                                    }
+                                }
+                                KeyCode::Esc => {
+                                    explorer.ui_mode = UIMode::Normal;
+                                }
+                                _ => {}
+                            }
+                        }
+                        UIMode::ContextMenu { row, column, selected } => {
+                            let last = ContextAction::ALL.len() - 1;
+                            match key.code {
+                                KeyCode::Up | KeyCode::Char('k') => {
+                                    explorer.ui_mode = UIMode::ContextMenu { row: *row, column: *column, selected: selected.saturating_sub(1) };
+                                }
+                                KeyCode::Down | KeyCode::Char('j') => {
+                                    explorer.ui_mode = UIMode::ContextMenu { row: *row, column: *column, selected: (*selected + 1).min(last) };
+                                }
+                                KeyCode::Enter => {
+                                    explorer.run_context_action(ContextAction::ALL[*selected])?;
+                                }
+                                KeyCode::Esc => {
+                                    explorer.ui_mode = UIMode::Normal;
+                                }
+                                _ => {}
+                            }
+                        }
+                        UIMode::ExportListing { format, name } => {
+                            match (key.code, format) {
+                                (KeyCode::Char(c @ ('t' | 'c' | 'r')), None) => {
+                                    let format = match c {
+                                        't' => ExportFormat::Text,
+                                        'c' => ExportFormat::Csv,
+                                        _ => ExportFormat::Tree,
+                                    };
+                                    explorer.ui_mode = UIMode::ExportListing { format: Some(format), name: String::new() };
+                                }
+                                (KeyCode::Char(c), Some(_)) => {
+                                    if let UIMode::ExportListing { name, .. } = &mut explorer.ui_mode {
+                                        name.push(c);
+                                    }
+                                }
+                                (KeyCode::Backspace, Some(_)) => {
+                                    if let UIMode::ExportListing { name, .. } = &mut explorer.ui_mode {
+                                        name.pop();
+                                    }
+                                }
+                                (KeyCode::Enter, Some(format)) => {
+                                    let format = *format;
+                                    let name = name.clone();
+                                    explorer.ui_mode = UIMode::Normal;
+
+                                    if let Err(e) = explorer.export_listing(format, &name) {
+                                        explorer.show_status(format!("Export failed: {}", e));
+                                    }
+                                }
+                                (KeyCode::Esc, _) => {
+                                    explorer.ui_mode = UIMode::Normal;
+                                }
+                                _ => {}
+                            }
+                        }
+                        UIMode::JumpToNumber { buffer } => {
+                            match key.code {
+                                KeyCode::Char(c) if c.is_ascii_digit() => {
+                                    if let UIMode::JumpToNumber { buffer } = &mut explorer.ui_mode {
+                                        buffer.push(c);
+                                    }
+                                }
+                                KeyCode::Backspace => {
+                                    if let UIMode::JumpToNumber { buffer } = &mut explorer.ui_mode {
+                                        buffer.pop();
+                                    }
+                                }
+                                KeyCode::Enter => {
+                                    let buffer = buffer.clone();
+                                    explorer.ui_mode = UIMode::Normal;
+                                    explorer.jump_to_number(&buffer);
+                                }
+                                KeyCode::Esc => {
+                                    explorer.ui_mode = UIMode::Normal;
+                                }
+                                _ => {}
+                            }
+                        }
+                        UIMode::SplitFile { path, chunk_size } => {
+                            match key.code {
+                                KeyCode::Char(c) => {
+                                    if let UIMode::SplitFile { chunk_size, .. } = &mut explorer.ui_mode {
+                                        chunk_size.push(c);
                                     }
                                 }
-                                KeyCode::Delete => {
-                                    if let UIMode::RenameItem { new_name, cursor_pos, selection_start, .. } = &mut explorer.ui_mode {
-                                        if let Some(sel_start) = selection_start {
-                                            // Delete selection
-                                            let start = (*sel_start).min(*cursor_pos);
-                                            let end = (*sel_start).max(*cursor_pos);
-                                            new_name.replace_range(start..end, "");
-                                            *cursor_pos = start;
-                                            *selection_start = None;
-                                        } else if *cursor_pos < new_name.len() {
-                                            // Delete character at cursor
-                                            new_name.remove(*cursor_pos);
-                                        }
+                                KeyCode::Backspace => {
+                                    if let UIMode::SplitFile { chunk_size, .. } = &mut explorer.ui_mode {
+                                        chunk_size.pop();
                                     }
                                 }
                                 KeyCode::Enter => {
-                                    let path = original_path.clone();
-                                    let name = new_name.clone();
+                                    let path = path.clone();
+                                    let chunk_size = chunk_size.clone();
                                     explorer.ui_mode = UIMode::Normal;
 
-                                    if let Err(e) = explorer.rename_item(path, name) {
-                                        explorer.show_status(format!("Error: {}", e));
+                                    if let Err(e) = explorer.split_file(&path, &chunk_size) {
+                                        explorer.show_status(format!("Split failed: {}", e));
                                     }
                                 }
                                 KeyCode::Esc => {
@@ -2747,17 +9829,180 @@ fn run_app<B: ratatui::backend::Backend>(
                                         explorer.show_status(format!("Error: {}", e));
                                     }
                                 }
+                                KeyCode::Up if creation_type.is_some() => {
+                                    if let Some(recalled) = explorer.last_create_input.take()
+                                        && let UIMode::CreateNew { name, .. } = &mut explorer.ui_mode
+                                    {
+                                        explorer.last_create_input = Some(std::mem::replace(name, recalled));
+                                    }
+                                }
                                 KeyCode::Esc => {
+                                    if !name.is_empty() {
+                                        explorer.last_create_input = Some(name.clone());
+                                    }
                                     explorer.ui_mode = UIMode::Normal;
                                 }
                                 _ => {}
                             }
                         }
-                        UIMode::Help => {
+                        UIMode::Help { .. } => {
+                            if let UIMode::Help { scroll, filter } = &mut explorer.ui_mode {
+                                match key.code {
+                                    KeyCode::Esc if !filter.is_empty() => {
+                                        filter.clear();
+                                        *scroll = 0;
+                                    }
+                                    KeyCode::F(1) | KeyCode::Esc => {
+                                        explorer.toggle_help();
+                                    }
+                                    KeyCode::Up => *scroll = scroll.saturating_sub(1),
+                                    KeyCode::Down => *scroll += 1,
+                                    KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+                                    KeyCode::PageDown => *scroll += 10,
+                                    KeyCode::Home => *scroll = 0,
+                                    KeyCode::End => *scroll = usize::MAX,
+                                    KeyCode::Char(c) => {
+                                        filter.push(c);
+                                        *scroll = 0;
+                                    }
+                                    KeyCode::Backspace => {
+                                        filter.pop();
+                                        *scroll = 0;
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        UIMode::QueueSummary { .. } => {
+                            if let UIMode::QueueSummary { scroll, .. } = &mut explorer.ui_mode {
+                                match key.code {
+                                    KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                                        explorer.ui_mode = UIMode::Normal;
+                                    }
+                                    KeyCode::Up => *scroll = scroll.saturating_sub(1),
+                                    KeyCode::Down => *scroll = scroll.saturating_add(1),
+                                    KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+                                    KeyCode::PageDown => *scroll = scroll.saturating_add(10),
+                                    _ => {}
+                                }
+                            }
+                        }
+                        UIMode::MessageLog { .. } => {
+                            if let UIMode::MessageLog { scroll } = &mut explorer.ui_mode {
+                                match key.code {
+                                    KeyCode::F(6) | KeyCode::Esc | KeyCode::Char('q') => {
+                                        explorer.toggle_message_log();
+                                    }
+                                    KeyCode::Up => *scroll = scroll.saturating_sub(1),
+                                    KeyCode::Down => *scroll = scroll.saturating_add(1),
+                                    KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+                                    KeyCode::PageDown => *scroll = scroll.saturating_add(10),
+                                    KeyCode::Home => *scroll = 0,
+                                    KeyCode::End => *scroll = usize::MAX,
+                                    _ => {}
+                                }
+                            }
+                        }
+                        UIMode::ItemInfo { .. } => {
+                            if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('i') | KeyCode::Char('q')) {
+                                explorer.ui_mode = UIMode::Normal;
+                            }
+                        }
+                        UIMode::SortMenu { selected_index } => {
+                            let selected_index = *selected_index;
                             match key.code {
-                                KeyCode::F(1) | KeyCode::Esc => {
-                                    explorer.toggle_help();
+                                KeyCode::Up => {
+                                    explorer.ui_mode = UIMode::SortMenu {
+                                        selected_index: selected_index.saturating_sub(1),
+                                    };
+                                }
+                                KeyCode::Down => {
+                                    explorer.ui_mode = UIMode::SortMenu {
+                                        selected_index: (selected_index + 1).min(SortMode::ALL.len() - 1),
+                                    };
+                                }
+                                KeyCode::Char('r') => {
+                                    explorer.sort_reverse = !explorer.sort_reverse;
+                                }
+                                KeyCode::Char('g') => {
+                                    explorer.dirs_position = explorer.dirs_position.next();
+                                    explorer.reload_keeping_position()?;
+                                }
+                                KeyCode::Enter => {
+                                    explorer.apply_sort_mode(SortMode::ALL[selected_index])?;
+                                }
+                                KeyCode::Char(c) => {
+                                    if let Some(mode) = SortMode::ALL.iter().find(|mode| mode.key() == c) {
+                                        explorer.apply_sort_mode(*mode)?;
+                                    }
+                                }
+                                KeyCode::Esc => {
+                                    explorer.ui_mode = UIMode::Normal;
+                                }
+                                _ => {}
+                            }
+                        }
+                        UIMode::CommandPalette { query, filtered, selected } => {
+                            match key.code {
+                                KeyCode::Esc => explorer.ui_mode = UIMode::Normal,
+                                KeyCode::Up => {
+                                    let selected = selected.saturating_sub(1);
+                                    explorer.ui_mode = UIMode::CommandPalette { query: query.clone(), filtered: filtered.clone(), selected };
+                                }
+                                KeyCode::Down => {
+                                    let selected = (*selected + 1).min(filtered.len().saturating_sub(1));
+                                    explorer.ui_mode = UIMode::CommandPalette { query: query.clone(), filtered: filtered.clone(), selected };
+                                }
+                                KeyCode::Enter => {
+                                    match filtered.get(*selected).copied() {
+                                        Some(PaletteCommand::BulkRename) if !explorer.read_only => {
+                                            explorer.ui_mode = UIMode::Normal;
+                                            bulk_rename_in_editor(terminal, &mut explorer)?;
+                                        }
+                                        Some(PaletteCommand::EditConfig) => {
+                                            explorer.ui_mode = UIMode::Normal;
+                                            edit_config_in_editor(terminal, &mut explorer)?;
+                                        }
+                                        Some(command) => explorer.run_palette_command(command)?,
+                                        None => {}
+                                    }
+                                }
+                                KeyCode::Backspace | KeyCode::Char(_) => {
+                                    let mut query = query.clone();
+                                    match key.code {
+                                        KeyCode::Char(c) => query.push(c),
+                                        _ => {
+                                            query.pop();
+                                        }
+                                    }
+                                    let filtered = FileExplorer::filter_palette(&query);
+                                    explorer.ui_mode = UIMode::CommandPalette { query, filtered, selected: 0 };
+                                }
+                                _ => {}
+                            }
+                        }
+                        UIMode::RecentFiles { files, selected_index } => {
+                            match key.code {
+                                KeyCode::Up | KeyCode::Char('k') => {
+                                    let selected_index = selected_index.saturating_sub(1);
+                                    explorer.ui_mode = UIMode::RecentFiles { files: files.clone(), selected_index };
+                                }
+                                KeyCode::Down | KeyCode::Char('j') => {
+                                    let selected_index = (*selected_index + 1).min(files.len().saturating_sub(1));
+                                    explorer.ui_mode = UIMode::RecentFiles { files: files.clone(), selected_index };
+                                }
+                                KeyCode::Enter => {
+                                    if let Some((path, _)) = files.get(*selected_index) {
+                                        let path = path.clone();
+                                        explorer.ui_mode = UIMode::Normal;
+                                        if path.exists() {
+                                            explorer.reveal_path(path)?;
+                                        } else {
+                                            explorer.show_status(format!("{} no longer exists", path.display()));
+                                        }
+                                    }
                                 }
+                                KeyCode::Esc | KeyCode::Char('q') => explorer.close_recent_files(),
                                 _ => {}
                             }
                         }
@@ -2874,73 +10119,245 @@ fn run_app<B: ratatui::backend::Backend>(
                         UIMode::Normal | UIMode::StatusMessage { .. } => {
                             let shift = key.modifiers.contains(KeyModifiers::SHIFT);
                             let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                            let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+                            // Vim-style count prefix: digits accumulate until the next key consumes them
+                            if let KeyCode::Char(c) = key.code
+                                && c.is_ascii_digit()
+                                && !ctrl
+                                && !alt
+                                && (c != '0' || explorer.count_prefix.is_some())
+                            {
+                                let digit = c.to_digit(10).unwrap_or(0) as usize;
+                                let current = explorer.count_prefix.unwrap_or(0);
+                                explorer.count_prefix = Some(current.saturating_mul(10).saturating_add(digit));
+                                continue;
+                            }
+                            let count = explorer.count_prefix.take().unwrap_or(1);
+                            let pending_operator = explorer.pending_operator.take();
+
+                            // Second key of m<letter> / '<letter>
+                            if let (Some((operator @ ('m' | '\''), _)), KeyCode::Char(letter)) = (pending_operator, key.code)
+                                && letter.is_ascii_alphabetic()
+                                && !ctrl
+                                && !alt
+                            {
+                                if operator == 'm' {
+                                    explorer.set_mark(letter);
+                                } else {
+                                    explorer.jump_to_mark(letter)?;
+                                }
+                                continue;
+                            }
 
                             match key.code {
                                 KeyCode::F(1) => {
                                     explorer.toggle_help();
                                 }
-                                KeyCode::Char('q') if ctrl => return Ok(()),
+                                KeyCode::F(2) => {
+                                    explorer.cycle_theme();
+                                }
+                                KeyCode::F(3) => {
+                                    explorer.toggle_age_colors();
+                                }
+                                KeyCode::F(4) => {
+                                    explorer.toggle_title_stats();
+                                }
+                                KeyCode::F(12) => {
+                                    explorer.toggle_view_mode();
+                                }
+                                KeyCode::F(11) => {
+                                    explorer.toggle_key_hints();
+                                }
+                                KeyCode::F(5) => {
+                                    explorer.toggle_inline_tree()?;
+                                }
+                                KeyCode::F(6) => {
+                                    explorer.toggle_message_log();
+                                }
+                                KeyCode::F(7) => {
+                                    explorer.cycle_time_field()?;
+                                }
+                                KeyCode::F(8) => {
+                                    explorer.toggle_split_directories();
+                                }
+                                KeyCode::F(9) => {
+                                    explorer.cycle_density();
+                                }
+                                KeyCode::F(10) => {
+                                    explorer.toggle_type_indicators();
+                                }
+                                KeyCode::Char('q') if ctrl => {
+                                    explorer.save_last_dir();
+                                    return Ok(());
+                                }
+                                code if explorer.read_only && is_mutating_key(code, ctrl, alt) => {
+                                    explorer.refuse_in_read_only();
+                                }
                                 KeyCode::Char('l') if ctrl => {
-                                    // Ctrl+L: Refresh/clear terminal display
+                                    // Ctrl+L: Refresh/clear terminal display and re-read the listing
                                     terminal.clear()?;
+                                    explorer.reload_keeping_position()?;
+                                }
+                                KeyCode::Up => explorer.move_up_by(count, shift),
+                                KeyCode::Down => explorer.move_down_by(count, shift),
+                                KeyCode::Char('k') if !ctrl && !alt => explorer.move_up_by(count, false),
+                                KeyCode::Char('j') if !ctrl && !alt => explorer.move_down_by(count, false),
+                                KeyCode::Char('d') if !ctrl && !alt => {
+                                    // "dd" deletes the cursor line, "3dd" (or "d3d") the next three
+                                    match pending_operator {
+                                        Some(('d', first_count)) => explorer.delete_lines(first_count.saturating_mul(count)),
+                                        _ => explorer.pending_operator = Some(('d', count)),
+                                    }
                                 }
-                                KeyCode::Up => explorer.move_up(shift),
-                                KeyCode::Down => explorer.move_down(shift),
                                 KeyCode::Enter => explorer.open_or_enter()?,
-                                KeyCode::Right => explorer.enter_directory()?,
-                                KeyCode::Left => explorer.go_to_parent()?,
+                                KeyCode::Right => explorer.tree_expand()?,
+                                KeyCode::Left => explorer.tree_collapse()?,
                                 KeyCode::Char(' ') if ctrl => {
                                     explorer.toggle_selection();
                                 }
+                                KeyCode::Char('m') if alt => {
+                                    // A missing mount(8) or a vanished mount point is reported, not fatal
+                                    if let Err(e) = explorer.toggle_mount() {
+                                        explorer.show_status(format!("Mount or unmount failed: {}", e));
+                                    }
+                                }
+                                KeyCode::Char('g') if alt => {
+                                    explorer.go_to_clipboard_path()?;
+                                }
+                                KeyCode::Char('t') if alt => {
+                                    explorer.go_to_trash()?;
+                                }
+                                KeyCode::Char('a') if alt => {
+                                    explorer.start_edit_tag();
+                                }
+                                KeyCode::Char('w') if alt => {
+                                    explorer.open_in_tmux_panes();
+                                }
+                                KeyCode::Char('l') if alt => {
+                                    explorer.start_export_listing();
+                                }
+                                KeyCode::Char('u') if alt => {
+                                    explorer.start_recent_files();
+                                }
+                                KeyCode::Char('d') if alt => {
+                                    explorer.toggle_changed_since()?;
+                                }
+                                KeyCode::Char('x') if alt => {
+                                    explorer.toggle_executable()?;
+                                }
+                                KeyCode::Char('b') if alt => {
+                                    explorer.swap_selected_names()?;
+                                }
+                                KeyCode::Esc if explorer.size_scan.is_some() => {
+                                    explorer.cancel_size_scan();
+                                }
+                                KeyCode::Char('s') if alt => {
+                                    explorer.start_size_scan();
+                                }
+                                KeyCode::Char('p') if alt => {
+                                    explorer.start_split();
+                                }
+                                KeyCode::Char('q') if alt => {
+                                    explorer.toggle_queue_mode();
+                                }
+                                KeyCode::Char('e') if alt => {
+                                    explorer.run_queue()?;
+                                }
+                                KeyCode::Char('j') if alt => {
+                                    if let Err(e) = explorer.join_parts() {
+                                        explorer.show_status(format!("Join failed: {}", e));
+                                    }
+                                }
+                                KeyCode::Char('p') if !ctrl && !alt => {
+                                    explorer.toggle_pin()?;
+                                }
+                                KeyCode::Char('i') if !ctrl && !alt => {
+                                    explorer.show_item_info();
+                                }
+                                KeyCode::Char('v') if !ctrl && !alt => {
+                                    explorer.view_in_pager();
+                                }
+                                KeyCode::Char(operator @ ('m' | '\'')) if !ctrl && !alt => {
+                                    explorer.pending_operator = Some((operator, 1));
+                                }
+                                KeyCode::Char('#') if !ctrl && !alt => {
+                                    explorer.start_jump_to_number();
+                                }
+                                KeyCode::Char('a') if ctrl => {
+                                    explorer.select_all_listed();
+                                }
+                                KeyCode::Char('*') => {
+                                    explorer.select_same_extension();
+                                }
+                                KeyCode::Char('[') => {
+                                    explorer.jump_to_selected(false);
+                                }
+                                KeyCode::Char(']') => {
+                                    explorer.jump_to_selected(true);
+                                }
                                 KeyCode::Char('c') if ctrl => {
-                                    explorer.copy_selected();
+                                    explorer.copy_selected(alt);
                                 }
                                 KeyCode::Char('x') if ctrl => {
-                                    explorer.cut_selected();
+                                    explorer.cut_selected(alt);
+                                }
+                                KeyCode::Char('v') | KeyCode::Char('V') if (ctrl && shift) || alt => {
+                                    explorer.paste_into_cursor_dir()?;
                                 }
                                 KeyCode::Char('v') if ctrl => {
                                     explorer.paste()?;
                                 }
+                                KeyCode::Char('n') if alt => {
+                                    explorer.start_create_from_clipboard();
+                                }
+                                KeyCode::Char('f') if alt => {
+                                    explorer.start_paste_into_new_dir();
+                                }
+                                KeyCode::Char('c') if alt => {
+                                    explorer.start_sanitize_names();
+                                }
                                 KeyCode::Char('n') if ctrl => {
                                     explorer.start_create_new();
                                 }
+                                KeyCode::Char('r') | KeyCode::Char('R') if (ctrl && shift) || alt => {
+                                    bulk_rename_in_editor(terminal, &mut explorer)?;
+                                }
+                                KeyCode::Char('o') if alt => {
+                                    edit_config_in_editor(terminal, &mut explorer)?;
+                                }
                                 KeyCode::Char('r') if ctrl => {
                                     explorer.start_rename();
                                 }
                                 KeyCode::Delete => {
-                                    explorer.delete_selected();
+                                    explorer.delete_selected(alt);
                                 }
                                 KeyCode::Char('d') if ctrl => {
-                                    // Copy full path to clipboard
-                                    if let Some(entry) = explorer.entries.get(explorer.cursor_index) {
-                                        let full_path = entry.path.display().to_string();
-                                        if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                                            if clipboard.set_text(&full_path).is_ok() {
-                                                explorer.show_status(format!("Copied path: {}", full_path));
-                                            } else {
-                                                explorer.show_status("Failed to copy path to clipboard".to_string());
-                                            }
-                                        } else {
-                                            explorer.show_status("Failed to access clipboard".to_string());
-                                        }
-                                    }
+                                    explorer.copy_cursor_path();
                                 }
                                 KeyCode::Char('z') if ctrl => {
                                     explorer.undo()?;
                                 }
+                                KeyCode::Char('u') if ctrl => {
+                                    explorer.undo_last_delete()?;
+                                }
                                 KeyCode::Char('s') if ctrl => {
-                                    explorer.toggle_sort_mode()?;
+                                    explorer.open_sort_menu();
                                 }
                                 KeyCode::Char('h') if ctrl => {
                                     explorer.toggle_hidden()?;
                                 }
+                                KeyCode::Char('g') if ctrl => {
+                                    explorer.go_to_git_root()?;
+                                }
+                                KeyCode::Char('t') if ctrl => {
+                                    explorer.cycle_type_filter()?;
+                                }
                                 KeyCode::Char('f') if ctrl => {
-                                    // Enter fuzzy find mode
-                                    explorer.ui_mode = UIMode::FuzzyFind {
-                                        search_term: String::new(),
-                                        matches: Vec::new(),
-                                        selected_index: 0,
-                                    };
+                                    explorer.start_fuzzy_find();
+                                }
+                                KeyCode::Char('p' | 'P') if ctrl => {
+                                    explorer.open_command_palette();
                                 }
                                 _ => {}
                             }
@@ -2968,24 +10385,108 @@ fn run_app<B: ratatui::backend::Backend>(
                             MouseEventKind::Up(MouseButton::Left) => {
                                 explorer.handle_mouse_up();
                             }
+                            MouseEventKind::Down(MouseButton::Right) => {
+                                explorer.open_context_menu(mouse.row, mouse.column, 0);
+                            }
+                            MouseEventKind::Down(MouseButton::Middle) => {
+                                explorer.handle_middle_click(mouse.row, 0)?;
+                            }
+                            _ => {}
+                        }
+                    } else if let UIMode::ContextMenu { row, column, .. } = explorer.ui_mode {
+                        let size = terminal.size()?;
+                        let menu = FileExplorer::context_menu_area(row, column, Rect::new(0, 0, size.width, size.height));
+                        let inside = mouse.column >= menu.x && mouse.column < menu.x + menu.width
+                            && mouse.row > menu.y && mouse.row < menu.y + menu.height;
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) if inside => {
+                                let action = ContextAction::ALL[(mouse.row - menu.y - 1) as usize];
+                                explorer.run_context_action(action)?;
+                            }
+                            MouseEventKind::Moved if inside => {
+                                explorer.ui_mode = UIMode::ContextMenu { row, column, selected: (mouse.row - menu.y - 1) as usize };
+                            }
+                            MouseEventKind::Down(_) => {
+                                explorer.ui_mode = UIMode::Normal;
+                            }
                             _ => {}
                         }
                     }
                 }
+                Event::Resize(width, _) => {
+                    explorer.handle_resize(width);
+                    // Resize the buffers now so the next draw starts from the new size
+                    terminal.autoresize()?;
+                }
                 _ => {}
             }
+
+            // Commands that need the terminal run here, outside of any key handler
+            if let Some(mut command) = explorer.foreground_command.take() {
+                match run_in_foreground(terminal, &mut command) {
+                    Ok(status) if !status.success() => {
+                        explorer.show_status(format!("Command exited with {}", status));
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        explorer.show_status(format!("Failed to run command: {}", e));
+                    }
+                }
+                // The program may have created or renamed files; stay on the same item
+                explorer.reload_keeping_position()?;
+            }
         }
     }
 }
 
 fn main() -> io::Result<()> {
+    let mut read_only = false;
+    let mut log_file = None;
+    let mut start_dir = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--readonly" => read_only = true,
+            "--log" => {
+                let Some(path) = args.next() else {
+                    eprintln!("--log needs a file path\nUsage: rusty_files [--readonly] [--log FILE] [DIR]");
+                    std::process::exit(2);
+                };
+                // Opened before the TUI starts so a bad path is reported on the terminal
+                match fs::OpenOptions::new().create(true).append(true).open(&path) {
+                    Ok(file) => log_file = Some(file),
+                    Err(e) => {
+                        eprintln!("Cannot open log file {}: {}", path, e);
+                        std::process::exit(2);
+                    }
+                }
+            }
+            "-h" | "--help" => {
+                println!("Usage: rusty_files [--readonly] [--log FILE] [DIR]\n\n  --readonly  Browse without copy, move, rename, create or delete\n  --log FILE  Append timestamped actions, errors and sudo prompts to FILE\n  DIR         Start in DIR instead of the current (or restored last) directory");
+                return Ok(());
+            }
+            other if !other.starts_with('-') && start_dir.is_none() => {
+                let dir = PathBuf::from(other);
+                if !dir.is_dir() {
+                    eprintln!("Not a directory: {}", other);
+                    std::process::exit(2);
+                }
+                start_dir = Some(dir.canonicalize()?);
+            }
+            other => {
+                eprintln!("Unknown argument: {}\nUsage: rusty_files [--readonly] [--log FILE] [DIR]", other);
+                std::process::exit(2);
+            }
+        }
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let explorer = FileExplorer::new()?;
+    let explorer = FileExplorer::new(read_only, log_file, start_dir)?;
     let res = run_app(&mut terminal, explorer);
 
     disable_raw_mode()?;
@@ -2998,3 +10499,326 @@ fn main() -> io::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory under the system temp dir, removed when dropped
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("rusty_files-test-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(dir.join("trash")).unwrap();
+            TestDir(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // An explorer on the test directory with default settings and its own trash; the user's home is never read
+    fn explorer_in(dir: &TestDir) -> FileExplorer {
+        let mut explorer = FileExplorer::with_config(Config::default(), dir.0.join("trash"), dir.0.clone(), false, None);
+        explorer.load_directory().unwrap();
+        explorer
+    }
+
+    #[test]
+    fn truncate_keeps_wide_characters_whole_at_the_cut() {
+        // Each CJK character is two columns; half of one would overflow, so it is dropped
+        assert_eq!(FileExplorer::truncate_to_width("日本語のファイル", 8), "日本...");
+        assert_eq!(FileExplorer::truncate_to_width("日本語のファイル", 9), "日本語...");
+        assert_eq!(FileExplorer::truncate_to_width("a😀😀😀😀", 7), "a😀...");
+        // A ZWJ family is one grapheme and is kept or dropped as a whole
+        assert_eq!(FileExplorer::truncate_to_width("👨‍👩‍👧👨‍👩‍👧👨‍👩‍👧", 5), "👨‍👩‍👧...");
+        for width in 0..20 {
+            for name in ["日本語のファイル", "a😀😀😀😀", "e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}"] {
+                assert!(FileExplorer::truncate_to_width(name, width).width() <= width, "{:?} at {}", name, width);
+            }
+        }
+    }
+
+    #[test]
+    fn truncate_counts_the_ellipsis_in_the_width() {
+        assert_eq!(FileExplorer::truncate_to_width("abcdefgh", 8), "abcdefgh");
+        assert_eq!(FileExplorer::truncate_to_width("abcdefgh", 7), "abcd...");
+        assert_eq!(FileExplorer::truncate_to_width("abcdefgh", 4), "a...");
+        assert_eq!(FileExplorer::truncate_to_width("abcdefgh", 7).width(), 7);
+    }
+
+    #[test]
+    fn truncate_never_exceeds_very_narrow_widths() {
+        assert_eq!(FileExplorer::truncate_to_width("abcdefgh", 0), "");
+        assert_eq!(FileExplorer::truncate_to_width("abcdefgh", 1), "a");
+        assert_eq!(FileExplorer::truncate_to_width("abcdefgh", 3), "abc");
+        assert_eq!(FileExplorer::truncate_to_width("日本語", 1), "");
+        assert_eq!(FileExplorer::truncate_to_width("日本語", 3), "日");
+    }
+
+    // The columns shown for the only entry of a flat listing at the given width, and the name's width
+    fn entry_columns(explorer: &FileExplorer, terminal_width: usize) -> (Option<String>, usize) {
+        let lines = explorer.build_tree_lines(terminal_width);
+        let line = lines.iter().find(|line| line.entry_index == Some(0)).unwrap();
+        (line.timestamp.clone(), line.text.width())
+    }
+
+    #[test]
+    fn narrow_terminals_drop_permissions_then_date() {
+        let dir = TestDir::new("columns");
+        fs::write(dir.0.join("a.txt"), "").unwrap();
+        let mut explorer = explorer_in(&dir);
+        explorer.view_mode = ViewMode::Flat;
+        explorer.load_directory().unwrap();
+
+        // A flat entry spends 3 columns on the icon and gap; the name keeps min_name_width (16) of the rest
+        let (timestamp, name_width) = entry_columns(&explorer, 48);
+        assert_eq!(timestamp.map(|t| t.width()), Some(29));
+        assert_eq!(name_width, 16);
+        let (timestamp, name_width) = entry_columns(&explorer, 47);
+        assert_eq!(timestamp.map(|t| t.width()), Some(16));
+        assert_eq!(name_width, 28);
+        let (timestamp, name_width) = entry_columns(&explorer, 35);
+        assert_eq!(timestamp.map(|t| t.width()), Some(16));
+        assert_eq!(name_width, 16);
+        let (timestamp, name_width) = entry_columns(&explorer, 34);
+        assert_eq!(timestamp, None);
+        assert_eq!(name_width, 31);
+        for width in [0, 1] {
+            assert_eq!(entry_columns(&explorer, width), (None, 0));
+        }
+    }
+
+    #[test]
+    fn rename_rejects_names_with_path_separators() {
+        let dir = TestDir::new("rename-separators");
+        fs::create_dir(dir.0.join("sub")).unwrap();
+        let file = dir.0.join("a.txt");
+        fs::write(&file, "").unwrap();
+        let mut explorer = explorer_in(&dir);
+
+        for name in ["sub/a.txt", "../a.txt", "/tmp/a.txt", "sub/", ".", ".."] {
+            let outcome = explorer.rename_item(file.clone(), name.to_string()).unwrap();
+            assert_eq!(outcome, OperationOutcome::Failed, "{:?}", name);
+            assert!(file.exists(), "{:?}", name);
+        }
+        assert!(fs::symlink_metadata(dir.0.join("sub/a.txt")).is_err());
+        assert!(explorer.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn paste_into_new_dir_removes_the_directory_when_nothing_is_pasted() {
+        let dir = TestDir::new("paste-new-dir");
+        let mut explorer = explorer_in(&dir);
+        explorer.clipboard = Some(Clipboard { items: vec![dir.0.join("gone.txt")], operation: ClipboardOp::Copy });
+
+        explorer.paste_into_new_dir(dir.0.join("new")).unwrap();
+        assert!(fs::symlink_metadata(dir.0.join("new")).is_err());
+        assert_eq!(explorer.current_dir, dir.0);
+        assert!(explorer.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn paste_into_new_dir_waits_for_the_queue_and_the_confirmation() {
+        let dir = TestDir::new("paste-new-dir-checks");
+        fs::write(dir.0.join("a.txt"), "a").unwrap();
+        let mut explorer = explorer_in(&dir);
+        explorer.clipboard = Some(Clipboard { items: vec![dir.0.join("a.txt")], operation: ClipboardOp::Copy });
+
+        explorer.queue_mode = true;
+        explorer.paste_into_new_dir(dir.0.join("queued")).unwrap();
+        assert!(fs::symlink_metadata(dir.0.join("queued")).is_err());
+        explorer.run_queue().unwrap();
+        assert!(dir.0.join("queued/a.txt").exists());
+
+        explorer.ui_mode = UIMode::Normal;
+        explorer.config.confirm_paste_items = 1;
+        explorer.paste_into_new_dir(dir.0.join("confirmed")).unwrap();
+        assert!(fs::symlink_metadata(dir.0.join("confirmed")).is_err());
+        assert!(matches!(explorer.ui_mode, UIMode::ConfirmPaste { new_dir: true, .. }));
+    }
+
+    #[test]
+    fn message_log_merges_only_amended_statuses() {
+        let dir = TestDir::new("message-log");
+        let mut explorer = explorer_in(&dir);
+        explorer.message_log.clear();
+
+        explorer.show_status("Pasted 2 item(s)".to_string());
+        explorer.show_status("Pasted 2 item(s), again".to_string());
+        assert_eq!(explorer.message_log.len(), 2);
+
+        explorer.amend_status("Pasted 2 item(s), again, skipped 1 missing".to_string());
+        assert_eq!(explorer.message_log.len(), 2);
+        assert_eq!(explorer.message_log[1].1, "Pasted 2 item(s), again, skipped 1 missing");
+
+        // Something logged in between is kept; the amended status becomes its own entry
+        explorer.log_message("Trash emptied".to_string());
+        explorer.amend_status("Pasted 2 item(s), again, skipped 2 missing".to_string());
+        assert_eq!(explorer.message_log.len(), 4);
+        assert_eq!(explorer.message_log[2].1, "Trash emptied");
+    }
+
+    #[test]
+    fn pinned_items_stay_in_their_group() {
+        let dir = TestDir::new("pinned");
+        for name in ["a.txt", "z.txt"] {
+            fs::write(dir.0.join(name), "").unwrap();
+        }
+        fs::create_dir(dir.0.join("sub")).unwrap();
+        let mut explorer = explorer_in(&dir);
+        explorer.pinned = HashSet::from([dir.0.join("z.txt")]);
+        explorer.load_directory().unwrap();
+
+        let names: Vec<&str> = explorer.entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["sub", "trash", "z.txt", "a.txt"]);
+    }
+
+    #[test]
+    fn collapsed_chain_survives_a_visit_below_it() {
+        let dir = TestDir::new("chain");
+        let landed = dir.0.join("a/b/c");
+        fs::create_dir_all(landed.join("d")).unwrap();
+        fs::write(landed.join("f.txt"), "").unwrap();
+        let mut explorer = explorer_in(&dir);
+        explorer.config.skip_single_child_dirs = true;
+
+        explorer.enter_dir_path(dir.0.join("a")).unwrap();
+        assert_eq!(explorer.current_dir, landed);
+        explorer.enter_dir_path(landed.join("d")).unwrap();
+        explorer.go_to_parent().unwrap();
+        assert_eq!(explorer.current_dir, landed);
+        explorer.go_to_parent().unwrap();
+        assert_eq!(explorer.current_dir, dir.0);
+    }
+
+    #[test]
+    fn recent_files_count_rejects_zero() {
+        assert!(Config::parse("recent_files_count = 0").is_err());
+        assert_eq!(Config::parse("recent_files_count = 7").unwrap().recent_files_count, 7);
+    }
+
+    #[test]
+    fn bulk_rename_overwrite_undoes_in_one_step() {
+        let dir = TestDir::new("bulk-overwrite");
+        let (a, b) = (dir.0.join("a.txt"), dir.0.join("b.txt"));
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+        let mut explorer = explorer_in(&dir);
+        let choices = HashMap::from([(b.clone(), ConflictChoice::Overwrite)]);
+
+        explorer.perform_bulk_renames(vec![(a.clone(), b.clone())], &choices).unwrap();
+        assert_eq!(fs::read_to_string(&b).unwrap(), "a");
+        assert_eq!(explorer.undo_stack.len(), 1);
+
+        explorer.undo().unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+        assert!(explorer.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn bulk_rename_overwrite_puts_the_target_back_when_the_rename_fails() {
+        let dir = TestDir::new("bulk-overwrite-fail");
+        let b = dir.0.join("b.txt");
+        fs::write(&b, "b").unwrap();
+        let mut explorer = explorer_in(&dir);
+        let choices = HashMap::from([(b.clone(), ConflictChoice::Overwrite)]);
+
+        explorer.perform_bulk_renames(vec![(dir.0.join("gone.txt"), b.clone())], &choices).unwrap();
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+        assert!(explorer.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn sanitize_counts_queued_renames_separately() {
+        let dir = TestDir::new("sanitize-queue");
+        let file = dir.0.join("A B.txt");
+        fs::write(&file, "").unwrap();
+        let mut explorer = explorer_in(&dir);
+        explorer.queue_mode = true;
+
+        let options = SanitizeOptions { spaces: true, lowercase: false, ascii: false };
+        explorer.apply_sanitize_names(std::slice::from_ref(&file), options).unwrap();
+        assert!(file.exists());
+        assert_eq!(explorer.status_message.as_deref(), Some("Sanitized 0 name(s) (each undoes separately), 1 queued"));
+    }
+
+    #[test]
+    fn deleting_forgets_the_sizes_of_the_directories_above() {
+        let dir = TestDir::new("dir-sizes");
+        let (sub, other) = (dir.0.join("sub"), dir.0.join("other"));
+        fs::create_dir(&sub).unwrap();
+        fs::create_dir(&other).unwrap();
+        fs::write(sub.join("f"), "data").unwrap();
+        let mut explorer = explorer_in(&dir);
+        for path in [&dir.0, &sub, &other] {
+            explorer.dir_sizes.insert(path.clone(), Some(4));
+        }
+
+        explorer.perform_delete(&[sub.join("f")]).unwrap();
+        assert!(!explorer.dir_sizes.contains_key(&dir.0));
+        assert!(!explorer.dir_sizes.contains_key(&sub));
+        assert_eq!(explorer.dir_sizes.get(&other), Some(&Some(4)));
+    }
+
+    #[test]
+    fn unique_path_treats_dangling_symlink_as_taken() {
+        let dir = TestDir::new("dangling");
+        let explorer = explorer_in(&dir);
+        let link = dir.0.join("a.txt");
+        std::os::unix::fs::symlink(dir.0.join("missing"), &link).unwrap();
+
+        assert_eq!(explorer.get_unique_path(&link), dir.0.join("a (1).txt"));
+    }
+
+    #[test]
+    fn undo_delete_restores_beside_a_new_file() {
+        let dir = TestDir::new("undo-delete");
+        let mut explorer = explorer_in(&dir);
+        let file = dir.0.join("a.txt");
+        fs::write(&file, "old").unwrap();
+        explorer.perform_delete(std::slice::from_ref(&file)).unwrap();
+        fs::write(&file, "new").unwrap();
+
+        explorer.undo().unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dir.0.join("a (1).txt")).unwrap(), "old");
+    }
+
+    #[test]
+    fn undo_move_restores_beside_a_new_file() {
+        let dir = TestDir::new("undo-move");
+        fs::create_dir(dir.0.join("elsewhere")).unwrap();
+        let mut explorer = explorer_in(&dir);
+        let file = dir.0.join("a.txt");
+        fs::write(&file, "old").unwrap();
+        explorer.paste_items(vec![file.clone()], true, dir.0.join("elsewhere")).unwrap();
+        fs::write(&file, "new").unwrap();
+
+        explorer.undo().unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dir.0.join("a (1).txt")).unwrap(), "old");
+        assert!(fs::symlink_metadata(dir.0.join("elsewhere/a.txt")).is_err());
+    }
+
+    #[test]
+    fn undo_rename_keeps_a_dangling_symlink_on_the_old_name() {
+        let dir = TestDir::new("undo-rename");
+        let mut explorer = explorer_in(&dir);
+        let original = dir.0.join("a.txt");
+        fs::write(&original, "old").unwrap();
+        explorer.rename_item(original.clone(), "b.txt".to_string()).unwrap();
+        std::os::unix::fs::symlink(dir.0.join("missing"), &original).unwrap();
+
+        explorer.undo().unwrap();
+        assert!(fs::symlink_metadata(&original).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(dir.0.join("a (1).txt")).unwrap(), "old");
+        assert!(fs::symlink_metadata(dir.0.join("b.txt")).is_err());
+    }
+}